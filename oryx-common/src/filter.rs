@@ -0,0 +1,42 @@
+// Capture filter expressions are lowered into a disjunction of clauses,
+// each clause being a conjunction of up to MAX_FILTER_CONDITIONS conditions.
+// Clause `i` is stored at the indexes [i * MAX_FILTER_CONDITIONS, (i + 1) * MAX_FILTER_CONDITIONS)
+// and ends at the first condition with the `None` kind.
+
+pub const MAX_FILTER_CLAUSES: u32 = 8;
+pub const MAX_FILTER_CONDITIONS: u32 = 8;
+
+pub const FAMILY_ARP: u8 = 0;
+pub const FAMILY_IPV4: u8 = 4;
+pub const FAMILY_IPV6: u8 = 6;
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[repr(u8)]
+pub enum ConditionKind {
+    #[default]
+    None = 0,
+    // value: family
+    Family = 1,
+    // value: ip protocol number
+    Proto = 2,
+    // value: family of the address
+    Host = 3,
+    SrcHost = 4,
+    DstHost = 5,
+    Port = 6,
+    SrcPort = 7,
+    DstPort = 8,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+pub struct FilterCondition {
+    // IPv4 addresses are stored in the lower half
+    pub addr: [u64; 2],
+    pub mask: [u64; 2],
+    pub port: u16,
+    pub kind: ConditionKind,
+    pub value: u8,
+    pub negate: u8,
+    pub _padding: [u8; 3],
+}
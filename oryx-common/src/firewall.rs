@@ -0,0 +1,73 @@
+pub const PROTO_ANY: u8 = 0;
+
+pub const ACTION_BLOCK: u8 = 0;
+pub const ACTION_ALLOW: u8 = 1;
+// Rate limited per remote address, the rate is in packets or bytes per second
+pub const ACTION_LIMIT_PACKETS: u8 = 2;
+pub const ACTION_LIMIT_BYTES: u8 = 3;
+
+// Size of the RATE_LIMITS map, the least recently seen sources are evicted
+pub const MAX_RATE_LIMITED_SOURCES: u32 = 4096;
+
+// Applied to the IP packets not matching any rule
+pub const POLICY_ACCEPT: u8 = 0;
+pub const POLICY_DENY: u8 = 1;
+
+// A rule on several port ranges takes one entry per pair of local and remote ranges
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[repr(C)]
+pub struct BlocklistEntry {
+    // Inclusive, the ICMP rules use them for the type
+    pub remote_start_port: u16,
+    pub remote_end_port: u16,
+    // Inclusive
+    pub local_start_port: u16,
+    pub local_end_port: u16,
+    // IPv4 addresses are stored in the lower half
+    pub local_addr: [u64; 2],
+    pub local_mask: [u64; 2],
+    // Only for the rate limiting actions
+    pub rate: u32,
+    // Index of the rule in the RULE_COUNTERS map
+    pub slot: u16,
+    // IP protocol number
+    pub proto: u8,
+    pub action: u8,
+}
+
+// Rules of a remote subnet in the BLOCKLIST_IPV4 and BLOCKLIST_IPV6 tries, as a range of the
+// BLOCKLIST_ENTRIES map in the evaluation order. Only the most specific subnet is returned by
+// a lookup, so it also carries the rules of the subnets containing it. The rules on any remote
+// address are stored on the /0 subnet.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[repr(C)]
+pub struct Blocklist {
+    pub start: u32,
+    pub len: u32,
+}
+
+// Token bucket of a remote address for a rate limiting rule
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+pub struct RateLimitKey {
+    pub remote_addr: [u64; 2],
+    // u64 to avoid the padding bytes in the key
+    pub slot: u64,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+pub struct TokenBucket {
+    // In billionths of a token, refilled by `rate` per ns
+    pub tokens: u64,
+    pub last_refill: u64,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+pub struct RuleCounters {
+    pub packets: u64,
+    pub bytes: u64,
+    // Monotonic clock in ns, 0 -> never hit
+    pub last_hit: u64,
+}
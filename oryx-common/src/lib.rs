@@ -7,10 +7,19 @@ use network_types::{
     arp::ArpHdr, eth::EthHdr, icmp::Icmp, ip::IpHdr, sctp::SctpHdr, tcp::TcpHdr, udp::UdpHdr,
 };
 
+pub mod filter;
+pub mod firewall;
 pub mod protocols;
 
+// Default capacity of the firewall maps, resized when loading the programs
 pub const MAX_FIREWALL_RULES: u32 = 32;
-pub const MAX_RULES_PORT: usize = 32;
+pub const MAX_BLOCKLIST_ENTRIES: u32 = 1024;
+// Entries scanned for a remote subnet, bounds the loop for the verifier
+pub const MAX_SUBNET_ENTRIES: u32 = 256;
+pub const MAX_SNAPLEN: usize = 2048;
+
+// Records the ring buffer holds before dropping packets
+pub const RING_BUFFER_RECORDS: usize = 4096;
 
 #[derive(Clone)]
 #[repr(C)]
@@ -29,6 +38,14 @@ impl From<[u8; RawData::LEN]> for RawData {
     }
 }
 
+// When a snaplen is set, the headers are followed in the ring buffer
+// by the first `snaplen` bytes of the packet.
+#[repr(C)]
+pub struct RawRecord {
+    pub data: RawData,
+    pub payload: [u8; MAX_SNAPLEN],
+}
+
 #[derive(Clone)]
 #[repr(C)]
 pub struct RawFrame {
@@ -2,13 +2,12 @@
 #![no_main]
 
 use aya_ebpf::{
-    bindings::{TC_ACT_PIPE, TC_ACT_SHOT},
-    helpers::bpf_get_current_pid_tgid,
+    bindings::{BPF_F_NO_PREALLOC, TC_ACT_PIPE, TC_ACT_SHOT},
+    helpers::{bpf_get_current_pid_tgid, bpf_ktime_get_ns},
     macros::{classifier, map},
-    maps::{Array, HashMap, RingBuf},
+    maps::{Array, LpmTrie, LruHashMap, PerCpuArray, RingBuf, lpm_trie::Key},
     programs::TcContext,
 };
-use branches::unlikely;
 use core::mem;
 use network_types::{
     arp::ArpHdr,
@@ -20,12 +19,30 @@ use network_types::{
     udp::UdpHdr,
 };
 use oryx_common::{
-    MAX_FIREWALL_RULES, MAX_RULES_PORT, ProtoHdr, RawData, RawFrame, RawPacket,
+    MAX_BLOCKLIST_ENTRIES, MAX_FIREWALL_RULES, MAX_SNAPLEN, MAX_SUBNET_ENTRIES, ProtoHdr,
+    RING_BUFFER_RECORDS, RawData, RawFrame, RawPacket, RawRecord,
+    filter::{
+        ConditionKind, FAMILY_ARP, FAMILY_IPV4, FAMILY_IPV6, FilterCondition, MAX_FILTER_CLAUSES,
+        MAX_FILTER_CONDITIONS,
+    },
+    firewall::{
+        ACTION_BLOCK, ACTION_LIMIT_BYTES, ACTION_LIMIT_PACKETS, Blocklist, BlocklistEntry,
+        MAX_RATE_LIMITED_SOURCES, POLICY_DENY, PROTO_ANY, RateLimitKey, RuleCounters, TokenBucket,
+    },
     protocols::{LinkProtocol, NetworkProtocol, Protocol, TransportProtocol},
 };
 
+// Resized from the snaplen when loading the program
 #[map]
-static DATA: RingBuf = RingBuf::with_byte_size(4096 * RawFrame::LEN as u32, 0);
+static DATA: RingBuf =
+    RingBuf::with_byte_size(RING_BUFFER_RECORDS as u32 * RawFrame::LEN as u32, 0);
+
+#[map]
+static RECORD_BUFFER: PerCpuArray<RawRecord> = PerCpuArray::with_max_entries(1, 0);
+
+#[map]
+static CAPTURE_FILTER: Array<FilterCondition> =
+    Array::with_max_entries(MAX_FILTER_CLAUSES * MAX_FILTER_CONDITIONS, 0);
 
 #[map]
 static NETWORK_FILTERS: Array<u32> = Array::with_max_entries(8, 0);
@@ -40,12 +57,29 @@ static LINK_FILTERS: Array<u32> = Array::with_max_entries(8, 0);
 static TRAFFIC_DIRECTION_FILTER: Array<u8> = Array::with_max_entries(1, 0);
 
 #[map]
-static BLOCKLIST_IPV6: HashMap<u128, [u16; MAX_RULES_PORT]> =
-    HashMap::<u128, [u16; MAX_RULES_PORT]>::with_max_entries(MAX_FIREWALL_RULES, 0);
+static BLOCKLIST_IPV6: LpmTrie<[u8; 16], Blocklist> =
+    LpmTrie::with_max_entries(MAX_FIREWALL_RULES, BPF_F_NO_PREALLOC);
 
 #[map]
-static BLOCKLIST_IPV4: HashMap<u32, [u16; MAX_RULES_PORT]> =
-    HashMap::<u32, [u16; MAX_RULES_PORT]>::with_max_entries(MAX_FIREWALL_RULES, 0);
+static BLOCKLIST_IPV4: LpmTrie<[u8; 4], Blocklist> =
+    LpmTrie::with_max_entries(MAX_FIREWALL_RULES, BPF_F_NO_PREALLOC);
+
+// Two halves, the rules are updated in the one the tries do not point to.
+// Resized when loading the program
+#[map]
+static BLOCKLIST_ENTRIES: Array<BlocklistEntry> =
+    Array::with_max_entries(2 * MAX_BLOCKLIST_ENTRIES, 0);
+
+#[map]
+static FIREWALL_POLICY: Array<u8> = Array::with_max_entries(1, 0);
+
+#[map]
+static RULE_COUNTERS: PerCpuArray<RuleCounters> =
+    PerCpuArray::with_max_entries(MAX_FIREWALL_RULES, 0);
+
+#[map]
+static RATE_LIMITS: LruHashMap<RateLimitKey, TokenBucket> =
+    LruHashMap::with_max_entries(MAX_RATE_LIMITED_SOURCES, 0);
 
 #[unsafe(no_mangle)]
 static PID_HELPER_AVAILABILITY: u8 = 0;
@@ -53,6 +87,10 @@ static PID_HELPER_AVAILABILITY: u8 = 0;
 #[unsafe(no_mangle)]
 static TRAFFIC_DIRECTION: i32 = 0;
 
+// 0 -> only the headers are sent to the tui
+#[unsafe(no_mangle)]
+static SNAPLEN: u32 = 0;
+
 #[classifier]
 pub fn oryx(ctx: TcContext) -> i32 {
     match process(ctx) {
@@ -62,11 +100,193 @@ pub fn oryx(ctx: TcContext) -> i32 {
 }
 
 #[inline]
-fn submit(data: RawData) {
-    if let Some(mut buf) = DATA.reserve::<RawData>(0) {
-        unsafe { (*buf.as_mut_ptr()) = data };
-        buf.submit(0);
+fn submit(ctx: &TcContext, data: RawData) {
+    if !capture_filter(&data.frame) {
+        return;
+    }
+
+    let snaplen = unsafe { core::ptr::read_volatile(&SNAPLEN) } as usize;
+
+    if snaplen == 0 {
+        if let Some(mut buf) = DATA.reserve::<RawData>(0) {
+            unsafe { (*buf.as_mut_ptr()) = data };
+            buf.submit(0);
+        }
+        return;
+    }
+
+    let Some(record) = RECORD_BUFFER.get_ptr_mut(0) else {
+        return;
+    };
+
+    unsafe {
+        (*record).data = data;
+
+        let snaplen = if snaplen > MAX_SNAPLEN {
+            MAX_SNAPLEN
+        } else {
+            snaplen
+        };
+
+        let len = ctx
+            .load_bytes(0, &mut (*record).payload[..snaplen])
+            .unwrap_or(0);
+
+        let record = core::slice::from_raw_parts(record as *const u8, RawData::LEN + len);
+        let _ = DATA.output(record, 0);
+    }
+}
+
+struct PacketInfo {
+    family: u8,
+    proto: u8,
+    src_addr: [u64; 2],
+    dst_addr: [u64; 2],
+    src_port: u16,
+    dst_port: u16,
+    has_ports: bool,
+}
+
+#[inline]
+fn packet_info(frame: &RawFrame) -> PacketInfo {
+    match &frame.payload {
+        RawPacket::Ip(IpHdr::V4(ipv4_header), proto_header) => {
+            let (proto, src_port, dst_port, has_ports) = transport_info(proto_header);
+            PacketInfo {
+                family: FAMILY_IPV4,
+                proto,
+                src_addr: [0, u32::from_be_bytes(ipv4_header.src_addr) as u64],
+                dst_addr: [0, u32::from_be_bytes(ipv4_header.dst_addr) as u64],
+                src_port,
+                dst_port,
+                has_ports,
+            }
+        }
+        RawPacket::Ip(IpHdr::V6(ipv6_header), proto_header) => {
+            let (proto, src_port, dst_port, has_ports) = transport_info(proto_header);
+            let src_addr = ipv6_header.src_addr().to_bits();
+            let dst_addr = ipv6_header.dst_addr().to_bits();
+            PacketInfo {
+                family: FAMILY_IPV6,
+                proto,
+                src_addr: [(src_addr >> 64) as u64, src_addr as u64],
+                dst_addr: [(dst_addr >> 64) as u64, dst_addr as u64],
+                src_port,
+                dst_port,
+                has_ports,
+            }
+        }
+        RawPacket::Arp(arp_header) => PacketInfo {
+            family: FAMILY_ARP,
+            proto: 0,
+            src_addr: [0, u32::from_be_bytes(arp_header.spa) as u64],
+            dst_addr: [0, u32::from_be_bytes(arp_header.tpa) as u64],
+            src_port: 0,
+            dst_port: 0,
+            has_ports: false,
+        },
+    }
+}
+
+#[inline]
+fn transport_info(proto_header: &ProtoHdr) -> (u8, u16, u16, bool) {
+    match proto_header {
+        ProtoHdr::Tcp(h) => (
+            6,
+            u16::from_be_bytes(h.source),
+            u16::from_be_bytes(h.dest),
+            true,
+        ),
+        ProtoHdr::Udp(h) => (
+            17,
+            u16::from_be_bytes(h.src),
+            u16::from_be_bytes(h.dst),
+            true,
+        ),
+        ProtoHdr::Sctp(h) => (
+            132,
+            u16::from_be_bytes(h.src),
+            u16::from_be_bytes(h.dst),
+            true,
+        ),
+        ProtoHdr::Icmp(Icmp::V4(_)) => (1, 0, 0, false),
+        ProtoHdr::Icmp(Icmp::V6(_)) => (58, 0, 0, false),
+    }
+}
+
+#[inline]
+fn match_addr(condition: &FilterCondition, family: u8, addr: &[u64; 2]) -> bool {
+    // ARP packets carry IPv4 addresses
+    let family = if family == FAMILY_ARP {
+        FAMILY_IPV4
+    } else {
+        family
+    };
+
+    family == condition.value
+        && addr[0] & condition.mask[0] == condition.addr[0]
+        && addr[1] & condition.mask[1] == condition.addr[1]
+}
+
+#[inline]
+fn match_condition(condition: &FilterCondition, packet: &PacketInfo) -> bool {
+    match condition.kind {
+        ConditionKind::None => true,
+        ConditionKind::Family => packet.family == condition.value,
+        ConditionKind::Proto => packet.family != FAMILY_ARP && packet.proto == condition.value,
+        ConditionKind::Host => {
+            match_addr(condition, packet.family, &packet.src_addr)
+                || match_addr(condition, packet.family, &packet.dst_addr)
+        }
+        ConditionKind::SrcHost => match_addr(condition, packet.family, &packet.src_addr),
+        ConditionKind::DstHost => match_addr(condition, packet.family, &packet.dst_addr),
+        ConditionKind::Port => {
+            packet.has_ports
+                && (packet.src_port == condition.port || packet.dst_port == condition.port)
+        }
+        ConditionKind::SrcPort => packet.has_ports && packet.src_port == condition.port,
+        ConditionKind::DstPort => packet.has_ports && packet.dst_port == condition.port,
+    }
+}
+
+// true -> send to the tui
+#[inline]
+fn capture_filter(frame: &RawFrame) -> bool {
+    let packet = packet_info(frame);
+
+    for clause in 0..MAX_FILTER_CLAUSES {
+        let mut is_empty = true;
+        let mut is_matching = true;
+
+        for index in 0..MAX_FILTER_CONDITIONS {
+            let Some(condition) = CAPTURE_FILTER.get(clause * MAX_FILTER_CONDITIONS + index) else {
+                break;
+            };
+
+            if condition.kind == ConditionKind::None {
+                break;
+            }
+
+            is_empty = false;
+
+            if match_condition(condition, &packet) == (condition.negate != 0) {
+                is_matching = false;
+                break;
+            }
+        }
+
+        // The clauses are contiguous, an empty one marks the end
+        if is_empty {
+            // No filter at all
+            return clause == 0;
+        }
+
+        if is_matching {
+            return true;
+        }
     }
+
+    false
 }
 
 #[inline]
@@ -97,40 +317,196 @@ fn is_ingress() -> bool {
     traffic_direction == -1
 }
 
+// (remote, local) from (source, destination)
 #[inline]
-fn block_ipv4(addr: u32, port: u16) -> bool {
-    if let Some(blocked_ports) = unsafe { BLOCKLIST_IPV4.get(&addr) } {
-        for (idx, blocked_port) in blocked_ports.iter().enumerate() {
-            if *blocked_port == 0 {
-                if idx == 0 {
-                    return true;
-                } else {
-                    break;
-                }
-            } else if *blocked_port == port {
-                return true;
+fn remote_local<T>(src: T, dst: T) -> (T, T) {
+    if is_ingress() { (src, dst) } else { (dst, src) }
+}
+
+// Remote and local sides of a packet.
+// For ICMP, the remote port is the ICMP type and the local one is 0.
+struct Endpoints {
+    remote_addr: [u64; 2],
+    local_addr: [u64; 2],
+    remote_port: u16,
+    local_port: u16,
+}
+
+#[inline]
+fn match_rules(rules: &Blocklist, proto: IpProto, endpoints: &Endpoints) -> Option<BlocklistEntry> {
+    let has_ports = matches!(proto, IpProto::Tcp | IpProto::Udp | IpProto::Sctp);
+
+    for index in 0..MAX_SUBNET_ENTRIES {
+        if index >= rules.len {
+            break;
+        }
+        let entry = BLOCKLIST_ENTRIES.get(rules.start + index)?;
+
+        let is_matching_proto = if entry.proto == PROTO_ANY {
+            // Without ports, only the rules on all the ports apply
+            has_ports
+                || (entry.remote_start_port == 0
+                    && entry.remote_end_port == u16::MAX
+                    && entry.local_start_port == 0
+                    && entry.local_end_port == u16::MAX)
+        } else {
+            entry.proto == proto as u8
+        };
+
+        if is_matching_proto
+            && endpoints.remote_port >= entry.remote_start_port
+            && endpoints.remote_port <= entry.remote_end_port
+            && endpoints.local_port >= entry.local_start_port
+            && endpoints.local_port <= entry.local_end_port
+            && endpoints.local_addr[0] & entry.local_mask[0] == entry.local_addr[0]
+            && endpoints.local_addr[1] & entry.local_mask[1] == entry.local_addr[1]
+        {
+            return Some(*entry);
+        }
+    }
+    None
+}
+
+#[inline]
+fn is_default_deny() -> bool {
+    FIREWALL_POLICY
+        .get(0)
+        .is_some_and(|policy| *policy == POLICY_DENY)
+}
+
+#[inline]
+fn count(ctx: &TcContext, slot: u16) {
+    if let Some(counters) = RULE_COUNTERS.get_ptr_mut(slot as u32) {
+        unsafe {
+            (*counters).packets += 1;
+            (*counters).bytes += ctx.len() as u64;
+            (*counters).last_hit = bpf_ktime_get_ns();
+        }
+    }
+}
+
+const NS_PER_SEC: u64 = 1_000_000_000;
+
+// Token bucket of the remote address, holding up to one second of traffic.
+// The buckets are shared by the CPUs without locking, so the limit is approximate.
+#[inline]
+fn is_over_limit(ctx: &TcContext, entry: &BlocklistEntry, remote_addr: [u64; 2]) -> bool {
+    let key = RateLimitKey {
+        remote_addr,
+        slot: entry.slot as u64,
+    };
+    let rate = entry.rate as u64;
+    let capacity = rate * NS_PER_SEC;
+    let cost = if entry.action == ACTION_LIMIT_BYTES {
+        ctx.len() as u64 * NS_PER_SEC
+    } else {
+        NS_PER_SEC
+    };
+    let now = unsafe { bpf_ktime_get_ns() };
+
+    match RATE_LIMITS.get_ptr_mut(&key) {
+        Some(bucket) => unsafe {
+            let elapsed = now.saturating_sub((*bucket).last_refill).min(NS_PER_SEC);
+            let tokens = ((*bucket).tokens + elapsed * rate).min(capacity);
+            (*bucket).last_refill = now;
+
+            if tokens < cost {
+                (*bucket).tokens = tokens;
+                true
+            } else {
+                (*bucket).tokens = tokens - cost;
+                false
             }
+        },
+        None => {
+            let bucket = TokenBucket {
+                tokens: capacity.saturating_sub(cost),
+                last_refill: now,
+            };
+            let _ = RATE_LIMITS.insert(&key, &bucket, 0);
+            cost > capacity
         }
     }
-    false
 }
 
+// The first matching rule decides, then the policy.
+// true -> drop the packet
 #[inline]
-fn block_ipv6(addr: u128, port: u16) -> bool {
-    if let Some(blocked_ports) = unsafe { BLOCKLIST_IPV6.get(&addr) } {
-        for (idx, blocked_port) in blocked_ports.iter().enumerate() {
-            if *blocked_port == 0 {
-                if idx == 0 {
-                    return true;
-                } else {
-                    break;
-                }
-            } else if *blocked_port == port {
-                return true;
+fn firewall(
+    ctx: &TcContext,
+    rules: Option<&Blocklist>,
+    proto: IpProto,
+    endpoints: &Endpoints,
+) -> bool {
+    match rules.and_then(|rules| match_rules(rules, proto, endpoints)) {
+        // Only the packets over the limit are counted
+        Some(entry)
+            if entry.action == ACTION_LIMIT_PACKETS || entry.action == ACTION_LIMIT_BYTES =>
+        {
+            let is_over_limit = is_over_limit(ctx, &entry, endpoints.remote_addr);
+            if is_over_limit {
+                count(ctx, entry.slot);
             }
+            is_over_limit
+        }
+        Some(entry) => {
+            count(ctx, entry.slot);
+            entry.action == ACTION_BLOCK
         }
+        None => is_default_deny(),
     }
-    false
+}
+
+#[inline]
+fn firewall_ipv4(
+    ctx: &TcContext,
+    ipv4_header: *const Ipv4Hdr,
+    proto: IpProto,
+    (remote_port, local_port): (u16, u16),
+) -> bool {
+    let (remote_addr, local_addr) =
+        unsafe { remote_local((*ipv4_header).src_addr, (*ipv4_header).dst_addr) };
+
+    let endpoints = Endpoints {
+        remote_addr: [0, u32::from_be_bytes(remote_addr) as u64],
+        local_addr: [0, u32::from_be_bytes(local_addr) as u64],
+        remote_port,
+        local_port,
+    };
+    firewall(
+        ctx,
+        BLOCKLIST_IPV4.get(&Key::new(32, remote_addr)),
+        proto,
+        &endpoints,
+    )
+}
+
+#[inline]
+fn firewall_ipv6(
+    ctx: &TcContext,
+    ipv6_header: *const Ipv6Hdr,
+    proto: IpProto,
+    (remote_port, local_port): (u16, u16),
+) -> bool {
+    let (remote_addr, local_addr) =
+        unsafe { remote_local((*ipv6_header).src_addr(), (*ipv6_header).dst_addr()) };
+    let local_addr = local_addr.to_bits();
+
+    let endpoints = Endpoints {
+        remote_addr: [
+            (remote_addr.to_bits() >> 64) as u64,
+            remote_addr.to_bits() as u64,
+        ],
+        local_addr: [(local_addr >> 64) as u64, local_addr as u64],
+        remote_port,
+        local_port,
+    };
+    firewall(
+        ctx,
+        BLOCKLIST_IPV6.get(&Key::new(128, remote_addr.octets())),
+        proto,
+        &endpoints,
+    )
 }
 
 #[inline]
@@ -177,26 +553,19 @@ fn process(ctx: TcContext) -> Result<i32, ()> {
         EtherType::Ipv4 => {
             let ipv4_header: *const Ipv4Hdr = ptr_at(&ctx, EthHdr::LEN)?;
 
-            let addr = unsafe {
-                if is_ingress() {
-                    u32::from_be_bytes((*ipv4_header).src_addr)
-                } else {
-                    u32::from_be_bytes((*ipv4_header).dst_addr)
-                }
-            };
-
             match unsafe { (*ipv4_header).proto } {
                 IpProto::Tcp => {
                     let tcp_header: *const TcpHdr = ptr_at(&ctx, EthHdr::LEN + Ipv4Hdr::LEN)?;
 
-                    let port = if is_ingress() {
-                        u16::from_be_bytes(unsafe { (*tcp_header).source })
-                    } else {
-                        u16::from_be_bytes(unsafe { (*tcp_header).dest })
+                    let ports = unsafe {
+                        remote_local(
+                            u16::from_be_bytes((*tcp_header).source),
+                            u16::from_be_bytes((*tcp_header).dest),
+                        )
                     };
 
-                    if unlikely(block_ipv4(addr, port)) {
-                        return Ok(TC_ACT_SHOT); //block packet
+                    if firewall_ipv4(&ctx, ipv4_header, IpProto::Tcp, ports) {
+                        return Ok(TC_ACT_SHOT);
                     }
 
                     if filter_packet(Protocol::Network(NetworkProtocol::Ipv4))
@@ -207,29 +576,33 @@ fn process(ctx: TcContext) -> Result<i32, ()> {
                     }
 
                     unsafe {
-                        submit(RawData {
-                            frame: RawFrame {
-                                header: *eth_header,
-                                payload: RawPacket::Ip(
-                                    IpHdr::V4(*ipv4_header),
-                                    ProtoHdr::Tcp(*tcp_header),
-                                ),
+                        submit(
+                            &ctx,
+                            RawData {
+                                frame: RawFrame {
+                                    header: *eth_header,
+                                    payload: RawPacket::Ip(
+                                        IpHdr::V4(*ipv4_header),
+                                        ProtoHdr::Tcp(*tcp_header),
+                                    ),
+                                },
+                                pid,
                             },
-                            pid,
-                        });
+                        );
                     }
                 }
                 IpProto::Udp => {
                     let udp_header: *const UdpHdr = ptr_at(&ctx, EthHdr::LEN + Ipv4Hdr::LEN)?;
 
-                    let port = if is_ingress() {
-                        u16::from_be_bytes(unsafe { (*udp_header).src })
-                    } else {
-                        u16::from_be_bytes(unsafe { (*udp_header).dst })
+                    let ports = unsafe {
+                        remote_local(
+                            u16::from_be_bytes((*udp_header).src),
+                            u16::from_be_bytes((*udp_header).dst),
+                        )
                     };
 
-                    if unlikely(block_ipv4(addr, port)) {
-                        return Ok(TC_ACT_SHOT); //block packet
+                    if firewall_ipv4(&ctx, ipv4_header, IpProto::Udp, ports) {
+                        return Ok(TC_ACT_SHOT);
                     }
 
                     if filter_packet(Protocol::Network(NetworkProtocol::Ipv4))
@@ -240,29 +613,33 @@ fn process(ctx: TcContext) -> Result<i32, ()> {
                     }
 
                     unsafe {
-                        submit(RawData {
-                            frame: RawFrame {
-                                header: *eth_header,
-                                payload: RawPacket::Ip(
-                                    IpHdr::V4(*ipv4_header),
-                                    ProtoHdr::Udp(*udp_header),
-                                ),
+                        submit(
+                            &ctx,
+                            RawData {
+                                frame: RawFrame {
+                                    header: *eth_header,
+                                    payload: RawPacket::Ip(
+                                        IpHdr::V4(*ipv4_header),
+                                        ProtoHdr::Udp(*udp_header),
+                                    ),
+                                },
+                                pid,
                             },
-                            pid,
-                        });
+                        );
                     }
                 }
                 IpProto::Sctp => {
                     let sctp_header: *const SctpHdr = ptr_at(&ctx, EthHdr::LEN + Ipv4Hdr::LEN)?;
 
-                    let port = if is_ingress() {
-                        u16::from_be_bytes(unsafe { (*sctp_header).src })
-                    } else {
-                        u16::from_be_bytes(unsafe { (*sctp_header).dst })
+                    let ports = unsafe {
+                        remote_local(
+                            u16::from_be_bytes((*sctp_header).src),
+                            u16::from_be_bytes((*sctp_header).dst),
+                        )
                     };
 
-                    if unlikely(block_ipv4(addr, port)) {
-                        return Ok(TC_ACT_SHOT); //block packet
+                    if firewall_ipv4(&ctx, ipv4_header, IpProto::Sctp, ports) {
+                        return Ok(TC_ACT_SHOT);
                     }
 
                     if filter_packet(Protocol::Network(NetworkProtocol::Ipv4))
@@ -273,65 +650,73 @@ fn process(ctx: TcContext) -> Result<i32, ()> {
                     }
 
                     unsafe {
-                        submit(RawData {
-                            frame: RawFrame {
-                                header: *eth_header,
-                                payload: RawPacket::Ip(
-                                    IpHdr::V4(*ipv4_header),
-                                    ProtoHdr::Sctp(*sctp_header),
-                                ),
+                        submit(
+                            &ctx,
+                            RawData {
+                                frame: RawFrame {
+                                    header: *eth_header,
+                                    payload: RawPacket::Ip(
+                                        IpHdr::V4(*ipv4_header),
+                                        ProtoHdr::Sctp(*sctp_header),
+                                    ),
+                                },
+                                pid,
                             },
-                            pid,
-                        });
+                        );
                     }
                 }
                 IpProto::Icmp => {
+                    let icmp_header: *const IcmpHdr = ptr_at(&ctx, EthHdr::LEN + Ipv4Hdr::LEN)?;
+
+                    let icmp_type = unsafe { (*icmp_header).type_ } as u16;
+
+                    if firewall_ipv4(&ctx, ipv4_header, IpProto::Icmp, (icmp_type, 0)) {
+                        return Ok(TC_ACT_SHOT);
+                    }
+
                     if filter_packet(Protocol::Network(NetworkProtocol::Icmpv4)) {
                         return Ok(TC_ACT_PIPE);
                     }
-                    let icmp_header: *const IcmpHdr = ptr_at(&ctx, EthHdr::LEN + Ipv4Hdr::LEN)?;
 
                     unsafe {
-                        submit(RawData {
-                            frame: RawFrame {
-                                header: *eth_header,
-                                payload: RawPacket::Ip(
-                                    IpHdr::V4(*ipv4_header),
-                                    ProtoHdr::Icmp(Icmp::V4(*icmp_header)),
-                                ),
+                        submit(
+                            &ctx,
+                            RawData {
+                                frame: RawFrame {
+                                    header: *eth_header,
+                                    payload: RawPacket::Ip(
+                                        IpHdr::V4(*ipv4_header),
+                                        ProtoHdr::Icmp(Icmp::V4(*icmp_header)),
+                                    ),
+                                },
+                                pid,
                             },
-                            pid,
-                        });
+                        );
+                    }
+                }
+                proto => {
+                    if firewall_ipv4(&ctx, ipv4_header, proto, (0, 0)) {
+                        return Ok(TC_ACT_SHOT);
                     }
                 }
-                _ => {}
             }
         }
         EtherType::Ipv6 => {
             let ipv6_header: *const Ipv6Hdr = ptr_at(&ctx, EthHdr::LEN)?;
 
-            let addr = unsafe {
-                if is_ingress() {
-                    (*ipv6_header).src_addr().to_bits()
-                } else {
-                    (*ipv6_header).dst_addr().to_bits()
-                }
-            };
-
             match unsafe { (*ipv6_header).next_hdr } {
                 IpProto::Tcp => {
                     let tcp_header: *const TcpHdr = ptr_at(&ctx, EthHdr::LEN + Ipv6Hdr::LEN)?;
 
-                    let port = unsafe {
-                        if is_ingress() {
-                            u16::from_be_bytes((*tcp_header).source)
-                        } else {
-                            u16::from_be_bytes((*tcp_header).dest)
-                        }
+                    let ports = unsafe {
+                        remote_local(
+                            u16::from_be_bytes((*tcp_header).source),
+                            u16::from_be_bytes((*tcp_header).dest),
+                        )
                     };
 
-                    if block_ipv6(addr, port) {
-                        return Ok(TC_ACT_SHOT); //block packet
+                    if firewall_ipv6(&ctx, ipv6_header, IpProto::Tcp, ports) {
+                        return Ok(TC_ACT_SHOT);
                     }
 
                     if filter_packet(Protocol::Network(NetworkProtocol::Ipv6))
@@ -342,31 +727,33 @@ fn process(ctx: TcContext) -> Result<i32, ()> {
                     }
 
                     unsafe {
-                        submit(RawData {
-                            frame: RawFrame {
-                                header: *eth_header,
-                                payload: RawPacket::Ip(
-                                    IpHdr::V6(*ipv6_header),
-                                    ProtoHdr::Tcp(*tcp_header),
-                                ),
+                        submit(
+                            &ctx,
+                            RawData {
+                                frame: RawFrame {
+                                    header: *eth_header,
+                                    payload: RawPacket::Ip(
+                                        IpHdr::V6(*ipv6_header),
+                                        ProtoHdr::Tcp(*tcp_header),
+                                    ),
+                                },
+                                pid,
                             },
-                            pid,
-                        });
+                        );
                     }
                 }
                 IpProto::Udp => {
                     let udp_header: *const UdpHdr = ptr_at(&ctx, EthHdr::LEN + Ipv6Hdr::LEN)?;
 
-                    let port = unsafe {
-                        if is_ingress() {
-                            u16::from_be_bytes((*udp_header).src)
-                        } else {
-                            u16::from_be_bytes((*udp_header).dst)
-                        }
+                    let ports = unsafe {
+                        remote_local(
+                            u16::from_be_bytes((*udp_header).src),
+                            u16::from_be_bytes((*udp_header).dst),
+                        )
                     };
 
-                    if block_ipv6(addr, port) {
-                        return Ok(TC_ACT_SHOT); //block packet
+                    if firewall_ipv6(&ctx, ipv6_header, IpProto::Udp, ports) {
+                        return Ok(TC_ACT_SHOT);
                     }
 
                     if filter_packet(Protocol::Network(NetworkProtocol::Ipv6))
@@ -377,29 +764,33 @@ fn process(ctx: TcContext) -> Result<i32, ()> {
                     }
 
                     unsafe {
-                        submit(RawData {
-                            frame: RawFrame {
-                                header: *eth_header,
-                                payload: RawPacket::Ip(
-                                    IpHdr::V6(*ipv6_header),
-                                    ProtoHdr::Udp(*udp_header),
-                                ),
+                        submit(
+                            &ctx,
+                            RawData {
+                                frame: RawFrame {
+                                    header: *eth_header,
+                                    payload: RawPacket::Ip(
+                                        IpHdr::V6(*ipv6_header),
+                                        ProtoHdr::Udp(*udp_header),
+                                    ),
+                                },
+                                pid,
                             },
-                            pid,
-                        });
+                        );
                     }
                 }
                 IpProto::Sctp => {
                     let sctp_header: *const SctpHdr = ptr_at(&ctx, EthHdr::LEN + Ipv6Hdr::LEN)?;
 
-                    let port = if is_ingress() {
-                        u16::from_be_bytes(unsafe { (*sctp_header).src })
-                    } else {
-                        u16::from_be_bytes(unsafe { (*sctp_header).dst })
+                    let ports = unsafe {
+                        remote_local(
+                            u16::from_be_bytes((*sctp_header).src),
+                            u16::from_be_bytes((*sctp_header).dst),
+                        )
                     };
 
-                    if block_ipv6(addr, port) {
-                        return Ok(TC_ACT_SHOT); //block packet
+                    if firewall_ipv6(&ctx, ipv6_header, IpProto::Sctp, ports) {
+                        return Ok(TC_ACT_SHOT);
                     }
 
                     if filter_packet(Protocol::Network(NetworkProtocol::Ipv6))
@@ -410,38 +801,55 @@ fn process(ctx: TcContext) -> Result<i32, ()> {
                     }
 
                     unsafe {
-                        submit(RawData {
-                            frame: RawFrame {
-                                header: *eth_header,
-                                payload: RawPacket::Ip(
-                                    IpHdr::V6(*ipv6_header),
-                                    ProtoHdr::Sctp(*sctp_header),
-                                ),
+                        submit(
+                            &ctx,
+                            RawData {
+                                frame: RawFrame {
+                                    header: *eth_header,
+                                    payload: RawPacket::Ip(
+                                        IpHdr::V6(*ipv6_header),
+                                        ProtoHdr::Sctp(*sctp_header),
+                                    ),
+                                },
+                                pid,
                             },
-                            pid,
-                        });
+                        );
                     }
                 }
                 IpProto::Ipv6Icmp => {
+                    let icmp_header: *const IcmpV6Hdr = ptr_at(&ctx, EthHdr::LEN + Ipv6Hdr::LEN)?;
+
+                    let icmp_type = unsafe { (*icmp_header).type_ } as u16;
+
+                    if firewall_ipv6(&ctx, ipv6_header, IpProto::Ipv6Icmp, (icmp_type, 0)) {
+                        return Ok(TC_ACT_SHOT);
+                    }
+
                     if filter_packet(Protocol::Network(NetworkProtocol::Icmpv6)) {
                         return Ok(TC_ACT_PIPE);
                     }
-                    let icmp_header: *const IcmpV6Hdr = ptr_at(&ctx, EthHdr::LEN + Ipv6Hdr::LEN)?;
 
                     unsafe {
-                        submit(RawData {
-                            frame: RawFrame {
-                                header: *eth_header,
-                                payload: RawPacket::Ip(
-                                    IpHdr::V6(*ipv6_header),
-                                    ProtoHdr::Icmp(Icmp::V6(*icmp_header)),
-                                ),
+                        submit(
+                            &ctx,
+                            RawData {
+                                frame: RawFrame {
+                                    header: *eth_header,
+                                    payload: RawPacket::Ip(
+                                        IpHdr::V6(*ipv6_header),
+                                        ProtoHdr::Icmp(Icmp::V6(*icmp_header)),
+                                    ),
+                                },
+                                pid,
                             },
-                            pid,
-                        });
+                        );
+                    }
+                }
+                proto => {
+                    if firewall_ipv6(&ctx, ipv6_header, proto, (0, 0)) {
+                        return Ok(TC_ACT_SHOT);
                     }
                 }
-                _ => {}
             }
         }
         EtherType::Arp => {
@@ -452,13 +860,16 @@ fn process(ctx: TcContext) -> Result<i32, ()> {
             let arp_header: *const ArpHdr = ptr_at(&ctx, EthHdr::LEN)?;
 
             unsafe {
-                submit(RawData {
-                    frame: RawFrame {
-                        header: *eth_header,
-                        payload: RawPacket::Arp(*arp_header),
+                submit(
+                    &ctx,
+                    RawData {
+                        frame: RawFrame {
+                            header: *eth_header,
+                            payload: RawPacket::Arp(*arp_header),
+                        },
+                        pid,
                     },
-                    pid,
-                });
+                );
             }
         }
         _ => {}
@@ -1,15 +1,27 @@
 use clap::ArgMatches;
 use itertools::Itertools;
-use oryx_common::protocols::{LinkProtocol, NetworkProtocol, TransportProtocol};
+use oryx_common::{
+    MAX_BLOCKLIST_ENTRIES, MAX_FIREWALL_RULES,
+    protocols::{LinkProtocol, NetworkProtocol, TransportProtocol},
+};
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout},
 };
-use std::{error, str::FromStr, thread, time::Duration};
+use std::{error, path::Path, str::FromStr, thread, time::Duration};
 
+use crate::section::alert::{
+    hook::{AlertHook, Webhook},
+    response::AutoBlock,
+    threat::Severity,
+};
+use crate::section::firewall::{IpNet, ttl::RuleTtl};
 use crate::{
-    filter::Filter, filter::IoChannels, help::Help, notification::Notification,
+    capture_file::CaptureFile, event::Event, export::ExportFormat, filter::Filter,
+    filter::IoChannels, help::Help, notification::Notification,
     packet::direction::TrafficDirection, packet_store::PacketStore, section::Section,
+    section::firewall::FirewallCapacity, section::firewall::FirewallSignal,
+    section::firewall::FirewallStats, section::stats::Stats,
 };
 
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
@@ -23,6 +35,8 @@ pub enum ActivePopup {
     PacketInfos,
     NewFirewallRule,
     NewMetricExplorer,
+    ExportCapture,
+    FirewallPolicy,
 }
 
 #[derive(Debug)]
@@ -42,121 +56,213 @@ pub struct App {
     pub is_editing: bool,
     pub active_popup: Option<ActivePopup>,
     pub start_from_cli: bool,
+    pub capture_file: Option<CaptureFile>,
 }
 
-impl App {
-    pub fn new(cli_args: &ArgMatches) -> Self {
-        let app_packets = PacketStore::new();
-
-        let firewall_channels = IoChannels::new();
-
-        let (interface_name, transport_protocols, network_protocols, link_protocols, direction) = {
-            if let Some(interface) = cli_args.get_one::<String>("interface") {
-                let transport_protocols = {
-                    if let Some(protocols) = cli_args.get_many::<String>("transport") {
-                        if protocols.clone().any(|protocol| protocol == "all") {
-                            TransportProtocol::all().to_vec()
-                        } else {
-                            let mut protocols = protocols
-                                .sorted()
-                                .map(|protocol| TransportProtocol::from_str(protocol).unwrap())
-                                .collect::<Vec<TransportProtocol>>();
-                            protocols.dedup();
-                            protocols
-                        }
-                    } else {
+// Capture settings of the command line, shared with the headless mode
+pub fn cli_filter(
+    cli_args: &ArgMatches,
+    firewall_channels: IoChannels<FirewallSignal>,
+    firewall_stats: FirewallStats,
+) -> Filter {
+    let (interface_name, transport_protocols, network_protocols, link_protocols, direction) = {
+        if let Some(interface) = cli_args.get_one::<String>("interface") {
+            let transport_protocols = {
+                if let Some(protocols) = cli_args.get_many::<String>("transport") {
+                    if protocols.clone().any(|protocol| protocol == "all") {
                         TransportProtocol::all().to_vec()
-                    }
-                };
-
-                let network_protocols = {
-                    if let Some(protocols) = cli_args.get_many::<String>("network") {
-                        if protocols.clone().any(|protocol| protocol == "all") {
-                            NetworkProtocol::all().to_vec()
-                        } else {
-                            let mut protocols = protocols
-                                .sorted()
-                                .map(|protocol| NetworkProtocol::from_str(protocol).unwrap())
-                                .collect::<Vec<NetworkProtocol>>();
-                            protocols.dedup();
-                            protocols
-                        }
                     } else {
-                        NetworkProtocol::all().to_vec()
+                        let mut protocols = protocols
+                            .sorted()
+                            .map(|protocol| TransportProtocol::from_str(protocol).unwrap())
+                            .collect::<Vec<TransportProtocol>>();
+                        protocols.dedup();
+                        protocols
                     }
-                };
-
-                let link_protocols = {
-                    if let Some(protocols) = cli_args.get_many::<String>("link") {
-                        if protocols.clone().any(|protocol| protocol == "all") {
-                            LinkProtocol::all().to_vec()
-                        } else {
-                            let mut protocols = protocols
-                                .sorted()
-                                .map(|protocol| LinkProtocol::from_str(protocol).unwrap())
-                                .collect::<Vec<LinkProtocol>>();
-                            protocols.dedup();
-                            protocols
-                        }
+                } else {
+                    TransportProtocol::all().to_vec()
+                }
+            };
+
+            let network_protocols = {
+                if let Some(protocols) = cli_args.get_many::<String>("network") {
+                    if protocols.clone().any(|protocol| protocol == "all") {
+                        NetworkProtocol::all().to_vec()
                     } else {
-                        LinkProtocol::all().to_vec()
+                        let mut protocols = protocols
+                            .sorted()
+                            .map(|protocol| NetworkProtocol::from_str(protocol).unwrap())
+                            .collect::<Vec<NetworkProtocol>>();
+                        protocols.dedup();
+                        protocols
                     }
-                };
-
-                let direction = {
-                    if let Some(directions) = cli_args.get_many::<String>("direction") {
-                        if directions.clone().any(|direction| direction == "all") {
-                            TrafficDirection::all().to_vec()
-                        } else {
-                            let mut directions = directions
-                                .sorted()
-                                .map(|direction| TrafficDirection::from_str(direction).unwrap())
-                                .collect::<Vec<TrafficDirection>>();
-                            directions.dedup();
-                            directions
-                        }
+                } else {
+                    NetworkProtocol::all().to_vec()
+                }
+            };
+
+            let link_protocols = {
+                if let Some(protocols) = cli_args.get_many::<String>("link") {
+                    if protocols.clone().any(|protocol| protocol == "all") {
+                        LinkProtocol::all().to_vec()
                     } else {
+                        let mut protocols = protocols
+                            .sorted()
+                            .map(|protocol| LinkProtocol::from_str(protocol).unwrap())
+                            .collect::<Vec<LinkProtocol>>();
+                        protocols.dedup();
+                        protocols
+                    }
+                } else {
+                    LinkProtocol::all().to_vec()
+                }
+            };
+
+            let direction = {
+                if let Some(directions) = cli_args.get_many::<String>("direction") {
+                    if directions.clone().any(|direction| direction == "all") {
                         TrafficDirection::all().to_vec()
+                    } else {
+                        let mut directions = directions
+                            .sorted()
+                            .map(|direction| TrafficDirection::from_str(direction).unwrap())
+                            .collect::<Vec<TrafficDirection>>();
+                        directions.dedup();
+                        directions
                     }
-                };
-
-                (
-                    Some(interface.clone()),
-                    transport_protocols,
-                    network_protocols,
-                    link_protocols,
-                    direction,
-                )
-            } else {
-                (
-                    None,
-                    TransportProtocol::all().to_vec(),
-                    NetworkProtocol::all().to_vec(),
-                    LinkProtocol::all().to_vec(),
-                    TrafficDirection::all().to_vec(),
-                )
-            }
-        };
+                } else {
+                    TrafficDirection::all().to_vec()
+                }
+            };
 
-        Self {
-            running: true,
-            help: Help::new(),
-            filter: Filter::new(
-                firewall_channels.clone(),
-                interface_name.clone(),
+            (
+                Some(interface.clone()),
                 transport_protocols,
                 network_protocols,
                 link_protocols,
                 direction,
-            ),
-            start_sniffing: false,
+            )
+        } else {
+            (
+                None,
+                TransportProtocol::all().to_vec(),
+                NetworkProtocol::all().to_vec(),
+                LinkProtocol::all().to_vec(),
+                TrafficDirection::all().to_vec(),
+            )
+        }
+    };
+
+    let snaplen = cli_args.get_one::<u32>("snaplen").copied().unwrap_or(0);
+
+    let firewall_capacity = FirewallCapacity {
+        rules: cli_args
+            .get_one::<u32>("firewall-capacity")
+            .copied()
+            .unwrap_or(MAX_FIREWALL_RULES),
+        entries: cli_args
+            .get_one::<u32>("firewall-entries")
+            .copied()
+            .unwrap_or(MAX_BLOCKLIST_ENTRIES),
+    };
+
+    let expression = cli_args
+        .get_one::<String>("filter")
+        .cloned()
+        .unwrap_or_default();
+
+    Filter::new(
+        firewall_channels,
+        firewall_stats,
+        interface_name,
+        transport_protocols,
+        network_protocols,
+        link_protocols,
+        direction,
+        expression,
+        snaplen,
+        firewall_capacity,
+    )
+}
+
+impl App {
+    pub fn new(cli_args: &ArgMatches) -> AppResult<Self> {
+        let app_packets = PacketStore::new();
+
+        let firewall_channels = IoChannels::new();
+        let firewall_stats = FirewallStats::default();
+
+        let export_format = cli_args
+            .get_one::<String>("export-format")
+            .map(|format| ExportFormat::from_str(format).unwrap())
+            .unwrap_or_default();
+
+        let filter = cli_filter(cli_args, firewall_channels.clone(), firewall_stats.clone());
+
+        let alert_hooks: Vec<AlertHook> = [
+            cli_args
+                .get_one::<String>("alert-exec")
+                .map(|path| AlertHook::Exec(path.into())),
+            cli_args
+                .get_flag("alert-syslog")
+                .then_some(AlertHook::Syslog),
+            cli_args
+                .get_one::<Webhook>("alert-webhook")
+                .cloned()
+                .map(AlertHook::Webhook),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let auto_block = cli_args
+            .get_one::<String>("auto-block")
+            .map(|severity| AutoBlock {
+                min_severity: Severity::from_str(severity).unwrap(),
+                ttl: cli_args
+                    .get_one::<RuleTtl>("auto-block-ttl")
+                    .copied()
+                    .unwrap(),
+                allow_list: cli_args
+                    .get_many::<IpNet>("auto-block-allow")
+                    .map(|subnets| subnets.copied().collect())
+                    .unwrap_or_default(),
+            });
+
+        let capture_file = match cli_args.get_one::<String>("read") {
+            Some(path) => Some(CaptureFile::read(Path::new(path), &app_packets)?),
+            None => None,
+        };
+
+        let mut section = Section::new(
+            app_packets.clone(),
+            firewall_channels.clone(),
+            firewall_stats,
+            filter.firewall_capacity,
+            alert_hooks,
+            auto_block,
+            export_format,
+            capture_file.is_some(),
+        );
+
+        // The capture is already there, no need to go through the setup
+        if capture_file.is_some() {
+            section.stats = Some(Stats::new(app_packets.clone()));
+        }
+
+        Ok(Self {
+            running: true,
+            help: Help::new(),
+            filter,
+            start_sniffing: capture_file.is_some(),
             app_packets: app_packets.clone(),
             notifications: Vec::new(),
-            section: Section::new(app_packets.clone(), firewall_channels.clone()),
+            section,
             is_editing: false,
             active_popup: None,
-            start_from_cli: interface_name.is_some(),
-        }
+            start_from_cli: cli_args.get_one::<String>("interface").is_some(),
+            capture_file,
+        })
     }
 
     pub fn render(&mut self, frame: &mut Frame) {
@@ -184,18 +290,26 @@ impl App {
                 self.active_popup.as_ref(),
             );
 
-            self.filter.render_on_sniffing(frame, settings_block);
+            match &self.capture_file {
+                Some(capture_file) => capture_file.render(frame, settings_block),
+                None => self.filter.render_on_sniffing(frame, settings_block),
+            }
         }
     }
 
-    pub fn tick(&mut self) {
+    pub fn tick(&mut self, sender: kanal::Sender<Event>) -> AppResult<()> {
         self.notifications.iter_mut().for_each(|n| n.ttl -= 1);
         self.notifications.retain(|n| n.ttl > 0);
         self.section.alert.check();
+        for request in self.section.alert.take_block_requests() {
+            self.section.firewall.auto_block(&request, sender.clone())?;
+        }
+        Ok(())
     }
 
     pub fn quit(&mut self) {
         self.filter.terminate();
+        self.section.alert.terminate();
         thread::sleep(Duration::from_millis(110));
         self.running = false;
     }
@@ -0,0 +1,220 @@
+pub mod pcap;
+pub mod pcapng;
+
+use std::{
+    fs, mem,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use network_types::{
+    arp::ArpHdr,
+    eth::EthHdr,
+    icmp::{Icmp, IcmpHdr, IcmpV6Hdr},
+    ip::{IpHdr, Ipv4Hdr, Ipv6Hdr},
+    sctp::SctpHdr,
+    tcp::TcpHdr,
+    udp::UdpHdr,
+};
+use oryx_common::{ProtoHdr, RawFrame, RawPacket};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Rect},
+    style::{Style, Stylize},
+    text::Span,
+    widgets::{Block, BorderType, Borders, Padding, Row, Table},
+};
+
+use crate::{
+    packet::{AppPacket, EthFrame, direction::TrafficDirection},
+    packet_store::PacketStore,
+};
+
+const LINKTYPE_ETHERNET: u16 = 1;
+
+// Number of packets parsed before being written to the store
+const BATCH_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    pub fn u16(self, data: &[u8], offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = data.get(offset..offset + 2)?.try_into().ok()?;
+        match self {
+            Self::Little => Some(u16::from_le_bytes(bytes)),
+            Self::Big => Some(u16::from_be_bytes(bytes)),
+        }
+    }
+
+    pub fn u32(self, data: &[u8], offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+        match self {
+            Self::Little => Some(u32::from_le_bytes(bytes)),
+            Self::Big => Some(u32::from_be_bytes(bytes)),
+        }
+    }
+}
+
+// A packet read from a capture file
+pub struct Record<'a> {
+    pub timestamp: DateTime<Utc>,
+    pub direction: Option<TrafficDirection>,
+    pub pid: Option<u32>,
+    pub data: &'a [u8],
+}
+
+#[derive(Debug)]
+pub struct CaptureFile {
+    pub path: PathBuf,
+    pub nb_packets: usize,
+    pub nb_skipped: usize,
+}
+
+impl CaptureFile {
+    pub fn read(path: &Path, packet_store: &PacketStore) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("Can not read {}", path.display()))?;
+
+        let mut packets = Vec::with_capacity(BATCH_SIZE);
+        let mut nb_packets = 0;
+        let mut nb_skipped = 0;
+
+        let on_record = |record: Record| {
+            let Some(frame) = parse_frame(record.data) else {
+                nb_skipped += 1;
+                return;
+            };
+
+            let raw_len = record.data.len().min(u16::MAX as usize);
+
+            packets.push(AppPacket {
+                frame,
+                // Packets without direction are considered as received
+                direction: record.direction.unwrap_or(TrafficDirection::Ingress),
+                pid: record.pid,
+                timestamp: record.timestamp,
+                raw: Some(packet_store.write_raw_bytes(&record.data[..raw_len])),
+            });
+
+            if packets.len() >= BATCH_SIZE {
+                packet_store.write_many(&packets);
+                nb_packets += packets.len();
+                packets.clear();
+            }
+        };
+
+        if pcapng::is_pcapng(&data) {
+            pcapng::read(&data, on_record)?;
+        } else if pcap::is_pcap(&data) {
+            pcap::read(&data, on_record)?;
+        } else {
+            bail!("{} is neither a pcap nor a pcapng file", path.display());
+        }
+
+        packet_store.write_many(&packets);
+        nb_packets += packets.len();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            nb_packets,
+            nb_skipped,
+        })
+    }
+
+    pub fn render(&self, frame: &mut Frame, block: Rect) {
+        let widths = [Constraint::Length(8), Constraint::Fill(1)];
+
+        let infos = [
+            Row::new(vec![
+                Span::styled("File", Style::new().bold()),
+                Span::from(self.path.display().to_string()),
+            ]),
+            Row::new(vec![
+                Span::styled("Packets", Style::new().bold()),
+                Span::from(self.nb_packets.to_string()),
+            ]),
+            Row::new(vec![
+                Span::styled("Skipped", Style::new().bold()),
+                Span::from(self.nb_skipped.to_string()),
+            ]),
+        ];
+
+        let table = Table::new(infos, widths).column_spacing(3).block(
+            Block::default()
+                .title(" Capture File 󰈙 ")
+                .title_style(Style::default().bold().green())
+                .title_alignment(Alignment::Center)
+                .padding(Padding::horizontal(2))
+                .borders(Borders::ALL)
+                .style(Style::default())
+                .border_type(BorderType::default())
+                .border_style(Style::default().green()),
+        );
+
+        frame.render_widget(table, block);
+    }
+}
+
+fn read_header<T>(data: &[u8], offset: usize) -> Option<T> {
+    let bytes = data.get(offset..offset + mem::size_of::<T>())?;
+    Some(unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const T) })
+}
+
+// Same parsing as the eBPF classifier, frames it can not handle are skipped
+fn parse_frame(data: &[u8]) -> Option<EthFrame> {
+    let header: EthHdr = read_header(data, 0)?;
+    let ether_type = u16::from_be_bytes(data.get(12..14)?.try_into().ok()?);
+
+    let payload = match ether_type {
+        // IPv4
+        0x0800 => {
+            let ipv4_header: Ipv4Hdr = read_header(data, EthHdr::LEN)?;
+            let ihl = (*data.get(EthHdr::LEN)? & 0x0f) as usize * 4;
+            let proto = *data.get(EthHdr::LEN + 9)?;
+
+            let proto_header = match proto {
+                1 => ProtoHdr::Icmp(Icmp::V4(read_header::<IcmpHdr>(data, EthHdr::LEN + ihl)?)),
+                _ => parse_transport(data, EthHdr::LEN + ihl, proto)?,
+            };
+
+            RawPacket::Ip(IpHdr::V4(ipv4_header), proto_header)
+        }
+        // IPv6
+        0x86DD => {
+            let ipv6_header: Ipv6Hdr = read_header(data, EthHdr::LEN)?;
+            let next_header = *data.get(EthHdr::LEN + 6)?;
+            let offset = EthHdr::LEN + Ipv6Hdr::LEN;
+
+            let proto_header = match next_header {
+                58 => ProtoHdr::Icmp(Icmp::V6(read_header::<IcmpV6Hdr>(data, offset)?)),
+                _ => parse_transport(data, offset, next_header)?,
+            };
+
+            RawPacket::Ip(IpHdr::V6(ipv6_header), proto_header)
+        }
+        // ARP
+        0x0806 => {
+            let arp_header: ArpHdr = read_header(data, EthHdr::LEN)?;
+            if !matches!(u16::from_be_bytes(arp_header.oper), 1 | 2) {
+                return None;
+            }
+            RawPacket::Arp(arp_header)
+        }
+        _ => return None,
+    };
+
+    Some(EthFrame::from(RawFrame { header, payload }))
+}
+
+fn parse_transport(data: &[u8], offset: usize, proto: u8) -> Option<ProtoHdr> {
+    match proto {
+        6 => Some(ProtoHdr::Tcp(read_header::<TcpHdr>(data, offset)?)),
+        17 => Some(ProtoHdr::Udp(read_header::<UdpHdr>(data, offset)?)),
+        132 => Some(ProtoHdr::Sctp(read_header::<SctpHdr>(data, offset)?)),
+        _ => None,
+    }
+}
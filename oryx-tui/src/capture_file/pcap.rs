@@ -0,0 +1,77 @@
+use anyhow::{Result, bail};
+use chrono::DateTime;
+
+use super::{Endianness, LINKTYPE_ETHERNET, Record};
+
+// https://www.ietf.org/archive/id/draft-ietf-opsawg-pcap-04.html
+
+const MAGIC_MICROSECONDS: u32 = 0xA1B2_C3D4;
+const MAGIC_NANOSECONDS: u32 = 0xA1B2_3C4D;
+
+const FILE_HEADER_LEN: usize = 24;
+const RECORD_HEADER_LEN: usize = 16;
+
+fn header(data: &[u8]) -> Option<(Endianness, bool)> {
+    let magic: [u8; 4] = data.get(0..4)?.try_into().ok()?;
+
+    match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+        (MAGIC_MICROSECONDS, _) => Some((Endianness::Little, false)),
+        (MAGIC_NANOSECONDS, _) => Some((Endianness::Little, true)),
+        (_, MAGIC_MICROSECONDS) => Some((Endianness::Big, false)),
+        (_, MAGIC_NANOSECONDS) => Some((Endianness::Big, true)),
+        _ => None,
+    }
+}
+
+pub fn is_pcap(data: &[u8]) -> bool {
+    header(data).is_some()
+}
+
+pub fn read<F>(data: &[u8], mut on_record: F) -> Result<()>
+where
+    F: FnMut(Record),
+{
+    let Some((endianness, is_nanoseconds)) = header(data) else {
+        bail!("Invalid pcap file header");
+    };
+
+    let Some(link_type) = endianness.u32(data, 20) else {
+        bail!("Invalid pcap file header");
+    };
+
+    if link_type & 0xffff != LINKTYPE_ETHERNET as u32 {
+        bail!("Unsupported link type {link_type}, only ethernet captures can be read");
+    }
+
+    let mut offset = FILE_HEADER_LEN;
+
+    while offset + RECORD_HEADER_LEN <= data.len() {
+        let seconds = endianness.u32(data, offset).unwrap_or_default();
+        let fraction = endianness.u32(data, offset + 4).unwrap_or_default();
+        let captured_length = endianness.u32(data, offset + 8).unwrap_or_default() as usize;
+
+        let start = offset + RECORD_HEADER_LEN;
+
+        // Truncated capture
+        let Some(packet) = data.get(start..start + captured_length) else {
+            break;
+        };
+
+        let nanoseconds = if is_nanoseconds {
+            fraction
+        } else {
+            fraction.saturating_mul(1000)
+        };
+
+        on_record(Record {
+            timestamp: DateTime::from_timestamp(seconds as i64, nanoseconds).unwrap_or_default(),
+            direction: None,
+            pid: None,
+            data: packet,
+        });
+
+        offset = start + captured_length;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,334 @@
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+
+use crate::packet::direction::TrafficDirection;
+
+use super::{Endianness, LINKTYPE_ETHERNET, Record};
+
+// https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-02.html
+
+const SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+// Options
+const OPT_END_OF_OPT: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+const IF_TSRESOL: u16 = 9;
+const EPB_FLAGS: u16 = 2;
+
+// Microseconds when if_tsresol is not present
+const DEFAULT_TS_RESOLUTION: u8 = 6;
+
+struct Interface {
+    link_type: u16,
+    ts_resolution: u8,
+}
+
+pub fn is_pcapng(data: &[u8]) -> bool {
+    data.get(0..4) == Some(&SECTION_HEADER_BLOCK.to_le_bytes())
+}
+
+pub fn read<F>(data: &[u8], mut on_record: F) -> Result<()>
+where
+    F: FnMut(Record),
+{
+    let mut endianness = Endianness::Little;
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut offset = 0;
+
+    while offset + 12 <= data.len() {
+        // The section header block type is a palindrome
+        let block_type = endianness.u32(data, offset).unwrap_or_default();
+
+        if block_type == SECTION_HEADER_BLOCK {
+            let Some(magic) = data.get(offset + 8..offset + 12) else {
+                break;
+            };
+
+            endianness = if magic == BYTE_ORDER_MAGIC.to_le_bytes() {
+                Endianness::Little
+            } else if magic == BYTE_ORDER_MAGIC.to_be_bytes() {
+                Endianness::Big
+            } else {
+                bail!("Invalid pcapng section header");
+            };
+
+            // Interfaces ids are local to a section
+            interfaces.clear();
+        }
+
+        let block_length = endianness.u32(data, offset + 4).unwrap_or_default() as usize;
+        if block_length < 12 || !block_length.is_multiple_of(4) {
+            bail!("Invalid pcapng block length at offset {offset}");
+        }
+
+        // Truncated capture
+        let Some(body) = data.get(offset + 8..offset + block_length - 4) else {
+            break;
+        };
+
+        match block_type {
+            INTERFACE_DESCRIPTION_BLOCK => {
+                let mut interface = Interface {
+                    link_type: endianness.u16(body, 0).unwrap_or_default(),
+                    ts_resolution: DEFAULT_TS_RESOLUTION,
+                };
+
+                for_each_option(
+                    body.get(8..).unwrap_or_default(),
+                    endianness,
+                    |code, value| {
+                        if let (IF_TSRESOL, Some(resolution)) = (code, value.first()) {
+                            interface.ts_resolution = *resolution;
+                        }
+                    },
+                );
+
+                interfaces.push(interface);
+            }
+
+            ENHANCED_PACKET_BLOCK => {
+                let interface_id = endianness.u32(body, 0).unwrap_or_default() as usize;
+                let Some(interface) = interfaces.get(interface_id) else {
+                    offset += block_length;
+                    continue;
+                };
+
+                if interface.link_type != LINKTYPE_ETHERNET {
+                    offset += block_length;
+                    continue;
+                }
+
+                let timestamp_high = endianness.u32(body, 4).unwrap_or_default() as u64;
+                let timestamp_low = endianness.u32(body, 8).unwrap_or_default() as u64;
+                let captured_length = endianness.u32(body, 12).unwrap_or_default() as usize;
+
+                let Some(packet) = body.get(20..20 + captured_length) else {
+                    bail!("Invalid pcapng packet length at offset {offset}");
+                };
+
+                let mut direction = None;
+                let mut pid = None;
+
+                let options_offset = 20 + captured_length.next_multiple_of(4);
+                for_each_option(
+                    body.get(options_offset..).unwrap_or_default(),
+                    endianness,
+                    |code, value| match code {
+                        EPB_FLAGS => {
+                            direction = match endianness.u32(value, 0).unwrap_or_default() & 0b11 {
+                                0b01 => Some(TrafficDirection::Ingress),
+                                0b10 => Some(TrafficDirection::Egress),
+                                _ => None,
+                            };
+                        }
+                        // pid written by oryx on export
+                        OPT_COMMENT => {
+                            pid = std::str::from_utf8(value)
+                                .ok()
+                                .and_then(|comment| comment.strip_prefix("pid: "))
+                                .and_then(|pid| pid.parse().ok());
+                        }
+                        _ => {}
+                    },
+                );
+
+                on_record(Record {
+                    timestamp: timestamp(
+                        (timestamp_high << 32) | timestamp_low,
+                        interface.ts_resolution,
+                    ),
+                    direction,
+                    pid,
+                    data: packet,
+                });
+            }
+
+            _ => {}
+        }
+
+        offset += block_length;
+    }
+
+    Ok(())
+}
+
+fn for_each_option<F>(mut options: &[u8], endianness: Endianness, mut f: F)
+where
+    F: FnMut(u16, &[u8]),
+{
+    while let (Some(code), Some(length)) = (endianness.u16(options, 0), endianness.u16(options, 2))
+    {
+        if code == OPT_END_OF_OPT {
+            break;
+        }
+
+        let length = length as usize;
+        let Some(value) = options.get(4..4 + length) else {
+            break;
+        };
+
+        f(code, value);
+
+        options = options
+            .get(4 + length.next_multiple_of(4)..)
+            .unwrap_or_default();
+    }
+}
+
+fn timestamp(value: u64, resolution: u8) -> DateTime<Utc> {
+    // The most significant bit tells if the resolution is a power of 2 or 10
+    let units_per_second = if resolution & 0x80 == 0 {
+        10u64.checked_pow(resolution as u32)
+    } else {
+        1u64.checked_shl((resolution & 0x7f) as u32)
+    }
+    .unwrap_or(1_000_000);
+
+    let seconds = value / units_per_second;
+    let nanoseconds =
+        ((value % units_per_second) as u128 * 1_000_000_000 / units_per_second as u128) as u32;
+
+    DateTime::from_timestamp(seconds as i64, nanoseconds).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{capture_file::parse_frame, export::pcapng::PcapngWriter, packet::AppPacket};
+
+    // Ethernet, IPv4 and UDP headers with a 4 bytes payload
+    fn udp_frame() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&[0x02, 0, 0, 0, 0, 0x02]);
+        data.extend_from_slice(&[0x02, 0, 0, 0, 0, 0x01]);
+        data.extend_from_slice(&0x0800u16.to_be_bytes());
+        data.extend_from_slice(&[0x45, 0]);
+        data.extend_from_slice(&32u16.to_be_bytes());
+        data.extend_from_slice(&[0, 1, 0, 0, 64, 17, 0, 0]);
+        data.extend_from_slice(&[10, 0, 0, 1, 10, 0, 0, 2]);
+        data.extend_from_slice(&5353u16.to_be_bytes());
+        data.extend_from_slice(&53u16.to_be_bytes());
+        data.extend_from_slice(&12u16.to_be_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(b"oryx");
+        data
+    }
+
+    fn app_packet(
+        direction: TrafficDirection,
+        pid: Option<u32>,
+        timestamp: DateTime<Utc>,
+    ) -> AppPacket {
+        AppPacket {
+            frame: parse_frame(&udp_frame()).unwrap(),
+            direction,
+            pid,
+            timestamp,
+            raw: None,
+        }
+    }
+
+    fn export(packets: &[AppPacket]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut writer = PcapngWriter::new(&mut data).unwrap();
+        let interface_id = writer.add_interface("eth0").unwrap();
+        for packet in packets {
+            writer
+                .write_packet(interface_id, packet, Some(udp_frame()))
+                .unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+        data
+    }
+
+    type ReadRecord = (
+        DateTime<Utc>,
+        Option<TrafficDirection>,
+        Option<u32>,
+        Vec<u8>,
+    );
+
+    fn import(data: &[u8]) -> Vec<ReadRecord> {
+        let mut records = Vec::new();
+        read(data, |record| {
+            records.push((
+                record.timestamp,
+                record.direction,
+                record.pid,
+                record.data.to_vec(),
+            ))
+        })
+        .unwrap();
+        records
+    }
+
+    #[test]
+    fn round_trip() {
+        let timestamp = DateTime::from_timestamp(1_700_000_000, 123_456_000).unwrap();
+        let data = export(&[
+            app_packet(TrafficDirection::Egress, Some(4242), timestamp),
+            app_packet(TrafficDirection::Ingress, None, timestamp),
+        ]);
+
+        assert!(is_pcapng(&data));
+
+        let records = import(&data);
+        assert_eq!(records.len(), 2);
+
+        let (time, direction, pid, packet) = &records[0];
+        assert_eq!(*time, timestamp);
+        assert_eq!(*direction, Some(TrafficDirection::Egress));
+        assert_eq!(*pid, Some(4242));
+        assert_eq!(*packet, udp_frame());
+
+        let (_, direction, pid, _) = &records[1];
+        assert_eq!(*direction, Some(TrafficDirection::Ingress));
+        assert_eq!(*pid, None);
+    }
+
+    #[test]
+    fn timestamp_resolution() {
+        // Exported in microseconds
+        let time = DateTime::from_timestamp(1_700_000_000, 123_456_789).unwrap();
+        let records = import(&export(&[app_packet(
+            TrafficDirection::Ingress,
+            None,
+            time,
+        )]));
+        assert_eq!(
+            records[0].0,
+            DateTime::from_timestamp(1_700_000_000, 123_456_000).unwrap()
+        );
+
+        // Nanoseconds
+        assert_eq!(
+            timestamp(1_700_000_000_123_456_789, 9),
+            DateTime::from_timestamp(1_700_000_000, 123_456_789).unwrap()
+        );
+
+        // Power of 2, 1/1024 of a second
+        assert_eq!(
+            timestamp((3 << 10) | 512, 0x80 | 10),
+            DateTime::from_timestamp(3, 500_000_000).unwrap()
+        );
+    }
+
+    #[test]
+    fn truncated_block() {
+        let timestamp = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let data = export(&[
+            app_packet(TrafficDirection::Ingress, None, timestamp),
+            app_packet(TrafficDirection::Egress, None, timestamp),
+        ]);
+
+        // The last block is cut, the packets before it are kept
+        let records = import(&data[..data.len() - 8]);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].1, Some(TrafficDirection::Ingress));
+    }
+}
@@ -5,7 +5,14 @@ use clap::{
     error::{ContextValue, ErrorKind},
 };
 
-use crate::interface::NetworkInterface;
+use oryx_common::{MAX_BLOCKLIST_ENTRIES, MAX_FIREWALL_RULES, MAX_SNAPLEN};
+
+use crate::{
+    filter::expression::compile,
+    interface::NetworkInterface,
+    section::alert::hook::Webhook,
+    section::firewall::{IpNet, ttl::RuleTtl},
+};
 
 fn parse_interface(interface: &str) -> Result<String, clap::Error> {
     let interfaces = NetworkInterface::list()
@@ -25,6 +32,24 @@ fn parse_interface(interface: &str) -> Result<String, clap::Error> {
     }
 }
 
+fn parse_filter(expression: &str) -> Result<String, String> {
+    compile(expression)
+        .map(|_| expression.to_string())
+        .map_err(|e| e.to_string())
+}
+
+fn parse_webhook(url: &str) -> Result<Webhook, String> {
+    url.parse()
+}
+
+fn parse_ttl(ttl: &str) -> Result<RuleTtl, String> {
+    ttl.parse()
+}
+
+fn parse_subnet(subnet: &str) -> Result<IpNet, String> {
+    subnet.parse()
+}
+
 pub fn cli() -> Command {
     Command::new("oryx")
         .about(crate_description!())
@@ -36,6 +61,13 @@ pub fn cli() -> Command {
                 .required(false)
                 .value_parser(ValueParser::new(parse_interface)),
         )
+        .arg(
+            arg!(--read <file>)
+                .short('r')
+                .help("Read packets from a pcap or pcapng file")
+                .required(false)
+                .conflicts_with("interface"),
+        )
         .arg(
             arg!(--transport <transport>)
                 .short('t')
@@ -76,4 +108,108 @@ pub fn cli() -> Command {
                 .default_value("all")
                 .value_parser(["ingress", "egress", "all"]),
         )
+        .arg(
+            arg!(--filter <expression>)
+                .help("Capture filter, e.g. \"tcp and dst port 443 and not host 10.0.0.1\"")
+                .required(false)
+                .conflicts_with("read")
+                .value_parser(ValueParser::new(parse_filter)),
+        )
+        .arg(
+            arg!(--"export-format" <format>)
+                .help("Default format used to export the capture")
+                .required(false)
+                .default_value("text")
+                .value_parser(["text", "pcapng"]),
+        )
+        .arg(
+            arg!(--snaplen <snaplen>)
+                .help("Number of bytes to capture per packet, 0 to capture only the headers")
+                .required(false)
+                .default_value("0")
+                .value_parser(clap::value_parser!(u32).range(0..=MAX_SNAPLEN as i64)),
+        )
+        .arg(
+            arg!(--"firewall-capacity" <capacity>)
+                .help("Maximum number of firewall rules and of subnets per direction")
+                .required(false)
+                .default_value(MAX_FIREWALL_RULES.to_string())
+                .value_parser(clap::value_parser!(u32).range(1..=u16::MAX as i64)),
+        )
+        .arg(
+            arg!(--"firewall-entries" <entries>)
+                .help("Maximum number of port ranges of the firewall rules per direction")
+                .required(false)
+                .default_value(MAX_BLOCKLIST_ENTRIES.to_string())
+                .value_parser(clap::value_parser!(u32).range(1..=1 << 20)),
+        )
+        .arg(
+            arg!(--"alert-exec" <path>)
+                .help("Executable run on each alert, with the finding as JSON on stdin")
+                .required(false),
+        )
+        .arg(
+            arg!(--"alert-syslog")
+                .help("Write the alerts to syslog")
+                .required(false),
+        )
+        .arg(
+            arg!(--"alert-webhook" <url>)
+                .help("HTTP URL receiving each alert as a JSON POST request")
+                .required(false)
+                .value_parser(ValueParser::new(parse_webhook)),
+        )
+        .arg(
+            arg!(--"auto-block" <severity>)
+                .help("Block the sources of the alerts of at least this severity")
+                .required(false)
+                // The detectors only run in the TUI
+                .conflicts_with("headless")
+                .value_parser(["low", "medium", "high", "critical"]),
+        )
+        .arg(
+            arg!(--"auto-block-ttl" <duration>)
+                .help("Time the sources stay blocked, e.g. 30m or 1h")
+                .required(false)
+                .requires("auto-block")
+                .default_value("15m")
+                .value_parser(ValueParser::new(parse_ttl)),
+        )
+        .arg(
+            arg!(--"auto-block-allow" <subnets>)
+                .help("Addresses or subnets never blocked, e.g. 10.0.0.0/8,192.168.1.1")
+                .required(false)
+                .requires("auto-block")
+                .value_delimiter(',')
+                .num_args(1..)
+                .value_parser(ValueParser::new(parse_subnet)),
+        )
+        .arg(
+            arg!(--headless)
+                .help("Stream the packets to stdout instead of starting the TUI")
+                .required(false),
+        )
+        .arg(
+            arg!(--output <format>)
+                .short('o')
+                .help("Output format in headless mode [default: text]")
+                .required(false)
+                .requires("headless")
+                .value_parser(["json", "text"]),
+        )
+        .arg(
+            arg!(--count <count>)
+                .short('c')
+                .help("Stop after writing this number of packets in headless mode")
+                .required(false)
+                .requires("headless")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            arg!(--duration <seconds>)
+                .help("Stop after this number of seconds in headless mode")
+                .required(false)
+                .requires("headless")
+                .value_parser(clap::value_parser!(u64)),
+        )
 }
@@ -1,16 +1,34 @@
 pub mod egress;
-mod firewall;
+pub mod firewall;
 pub mod ingress;
 
 use std::{io, os::fd::AsRawFd};
 
 use aya::{
-    Ebpf,
+    Ebpf, Pod,
     maps::{MapData, RingBuf, ring_buf::RingBufItem},
 };
+use oryx_common::{
+    MAX_SNAPLEN, RING_BUFFER_RECORDS, RawData,
+    filter::FilterCondition,
+    firewall::{Blocklist, BlocklistEntry, RuleCounters},
+};
 
 use mio::{Interest, Registry, Token, event::Source, unix::SourceFd};
 
+// Bytes of the DATA ring buffer, a power of two pages holding the same number of records
+// whatever the snaplen
+pub fn ring_buffer_size(snaplen: u32) -> u32 {
+    // Each record has an 8 bytes header and is 8 bytes aligned
+    let record_len = (RawData::LEN + (snaplen as usize).min(MAX_SNAPLEN) + 8).next_multiple_of(8);
+
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+
+    (RING_BUFFER_RECORDS * record_len)
+        .next_power_of_two()
+        .max(page_size) as u32
+}
+
 pub struct RingBuffer<'a> {
     pub buffer: RingBuf<&'a mut MapData>,
 }
@@ -49,6 +67,34 @@ impl Source for RingBuffer<'_> {
         SourceFd(&self.buffer.as_raw_fd()).deregister(registry)
     }
 }
+// Capture filter conditions as stored in the CAPTURE_FILTER map
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct CaptureFilterCondition(pub FilterCondition);
+
+unsafe impl Pod for CaptureFilterCondition {}
+
+// Rules of a subnet as stored in the BLOCKLIST_IPV4 and BLOCKLIST_IPV6 maps
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct BlocklistValue(pub Blocklist);
+
+unsafe impl Pod for BlocklistValue {}
+
+// Port ranges of a rule as stored in the BLOCKLIST_ENTRIES map
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct BlocklistEntryValue(pub BlocklistEntry);
+
+unsafe impl Pod for BlocklistEntryValue {}
+
+// Per rule hit counters as stored in the RULE_COUNTERS map
+#[derive(Clone, Copy, Default)]
+#[repr(transparent)]
+pub struct DropCounters(pub RuleCounters);
+
+unsafe impl Pod for DropCounters {}
+
 enum EbpfTrafficDirection {
     Ingress = -1,
     Egress = 1,
@@ -1,5 +1,4 @@
 use std::{
-    net::IpAddr,
     os::fd::AsRawFd,
     sync::{Arc, atomic::AtomicBool},
     thread,
@@ -8,13 +7,13 @@ use std::{
 
 use aya::{
     EbpfLoader, include_bytes_aligned,
-    maps::{Array, HashMap},
+    maps::Array,
     programs::{SchedClassifier, TcAttachType, tc},
     util::KernelVersion,
 };
 use branches::{likely, unlikely};
 use log::error;
-use oryx_common::{MAX_RULES_PORT, RawData, protocols::Protocol};
+use oryx_common::{RawData, protocols::Protocol};
 
 use crate::{
     event::Event,
@@ -22,13 +21,14 @@ use crate::{
     notification::{Notification, NotificationLevel},
     packet::{AppPacket, direction::TrafficDirection},
     packet_store::PacketStore,
-    section::firewall::FirewallSignal,
+    section::firewall::{FirewallCapacity, FirewallEvent, FirewallSignal, FirewallStats},
 };
 use mio::{Events, Interest, Poll, Token, unix::SourceFd};
 
 use super::{
-    EbpfTrafficDirection, RingBuffer,
-    firewall::{update_ipv4_blocklist, update_ipv6_blocklist},
+    CaptureFilterCondition, EbpfTrafficDirection, RingBuffer,
+    firewall::{COUNTERS_SYNC_INTERVAL, FirewallMaps},
+    ring_buffer_size,
 };
 
 fn is_pid_helper_available() -> bool {
@@ -45,7 +45,10 @@ pub fn load_egress(
     packet_store: PacketStore,
     filter_channel_receiver: kanal::Receiver<FilterChannelSignal>,
     firewall_egress_receiver: kanal::Receiver<FirewallSignal>,
+    firewall_stats: FirewallStats,
     terminate: Arc<AtomicBool>,
+    snaplen: u32,
+    firewall_capacity: FirewallCapacity,
 ) {
     thread::spawn({
         let iface = iface.to_owned();
@@ -64,6 +67,12 @@ pub fn load_egress(
             #[cfg(debug_assertions)]
             let mut bpf = match EbpfLoader::new()
                 .set_global("TRAFFIC_DIRECTION", &traffic_direction, true)
+                .set_global("SNAPLEN", &snaplen, true)
+                .set_max_entries("DATA", ring_buffer_size(snaplen))
+                .set_max_entries("BLOCKLIST_IPV4", firewall_capacity.rules)
+                .set_max_entries("BLOCKLIST_IPV6", firewall_capacity.rules)
+                .set_max_entries("BLOCKLIST_ENTRIES", 2 * firewall_capacity.entries)
+                .set_max_entries("RULE_COUNTERS", firewall_capacity.rules)
                 .set_global(
                     "PID_HELPER_AVAILABILITY",
                     &(is_pid_helper_available() as u8),
@@ -87,6 +96,12 @@ pub fn load_egress(
             #[cfg(not(debug_assertions))]
             let mut bpf = match EbpfLoader::new()
                 .set_global("TRAFFIC_DIRECTION", &traffic_direction, true)
+                .set_global("SNAPLEN", &snaplen, true)
+                .set_max_entries("DATA", ring_buffer_size(snaplen))
+                .set_max_entries("BLOCKLIST_IPV4", firewall_capacity.rules)
+                .set_max_entries("BLOCKLIST_IPV6", firewall_capacity.rules)
+                .set_max_entries("BLOCKLIST_ENTRIES", 2 * firewall_capacity.entries)
+                .set_max_entries("RULE_COUNTERS", firewall_capacity.rules)
                 .set_global(
                     "PID_HELPER_AVAILABILITY",
                     &(is_pid_helper_available() as u8),
@@ -149,37 +164,36 @@ pub fn load_egress(
             let mut traffic_direction_filter: Array<_, u8> =
                 Array::try_from(bpf.take_map("TRAFFIC_DIRECTION_FILTER").unwrap()).unwrap();
 
-            // firewall-ebpf interface
-            let mut ipv4_firewall: HashMap<_, u32, [u16; MAX_RULES_PORT]> =
-                HashMap::try_from(bpf.take_map("BLOCKLIST_IPV4").unwrap()).unwrap();
+            let mut capture_filter: Array<_, CaptureFilterCondition> =
+                Array::try_from(bpf.take_map("CAPTURE_FILTER").unwrap()).unwrap();
 
-            let mut ipv6_firewall: HashMap<_, u128, [u16; MAX_RULES_PORT]> =
-                HashMap::try_from(bpf.take_map("BLOCKLIST_IPV6").unwrap()).unwrap();
+            // firewall-ebpf interface
+            let mut firewall = FirewallMaps::new(&mut bpf, firewall_stats, firewall_capacity);
 
             // firewall thread
             thread::spawn(move || {
                 loop {
-                    if let Ok(signal) = firewall_egress_receiver.recv() {
-                        match signal {
-                            FirewallSignal::Rule(rule) => match rule.ip {
-                                IpAddr::V4(addr) => update_ipv4_blocklist(
-                                    &mut ipv4_firewall,
-                                    addr,
-                                    rule.port,
-                                    rule.enabled,
-                                ),
-
-                                IpAddr::V6(addr) => update_ipv6_blocklist(
-                                    &mut ipv6_firewall,
-                                    addr,
-                                    rule.port,
-                                    rule.enabled,
-                                ),
-                            },
-                            FirewallSignal::Kill => {
-                                break;
+                    match firewall_egress_receiver.recv_timeout(COUNTERS_SYNC_INTERVAL) {
+                        Ok(FirewallSignal::Rule(rule)) => {
+                            let rule_id = rule.id;
+                            if let Err(e) = firewall.update(rule) {
+                                error!("Failed to apply the firewall rule. {e}");
+                                let _ = notification_sender
+                                    .send(Event::Firewall(FirewallEvent::Rejected(rule_id, e)));
                             }
                         }
+                        Ok(FirewallSignal::Policy(policy)) => firewall.set_policy(policy),
+                        Ok(FirewallSignal::Kill) => {
+                            break;
+                        }
+                        Err(_) => {}
+                    }
+
+                    firewall.sync_stats();
+
+                    for rule_id in firewall.expire_rules() {
+                        let _ = notification_sender
+                            .send(Event::Firewall(FirewallEvent::Expired(rule_id)));
                     }
                 }
             });
@@ -203,6 +217,15 @@ pub fn load_egress(
                             FilterChannelSignal::DirectionUpdate(flag) => {
                                 let _ = traffic_direction_filter.set(0, flag as u8, 0);
                             }
+                            FilterChannelSignal::ExpressionUpdate(conditions) => {
+                                for (index, condition) in conditions.into_iter().enumerate() {
+                                    let _ = capture_filter.set(
+                                        index as u32,
+                                        CaptureFilterCondition(condition),
+                                        0,
+                                    );
+                                }
+                            }
                             FilterChannelSignal::Kill => {
                                 break;
                             }
@@ -241,13 +264,20 @@ pub fn load_egress(
                             if unlikely(terminate.load(std::sync::atomic::Ordering::Relaxed)) {
                                 break;
                             }
-                            let data: [u8; RawData::LEN] = item.to_owned().try_into().unwrap();
+                            let data: [u8; RawData::LEN] = item[..RawData::LEN].try_into().unwrap();
                             let raw = RawData::from(data);
+
+                            // the headers are followed by the captured bytes
+                            let payload = &item[RawData::LEN..];
+                            let raw_bytes = (!payload.is_empty())
+                                .then(|| packet_store.write_raw_bytes(payload));
+
                             packet_buffer.push(AppPacket {
                                 frame: raw.frame.into(),
                                 direction: TrafficDirection::Egress,
                                 pid: raw.pid,
                                 timestamp: chrono::Utc::now(),
+                                raw: raw_bytes,
                             })
                         }
                         if likely(!packet_buffer.is_empty()) {
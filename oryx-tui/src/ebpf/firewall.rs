@@ -1,138 +1,469 @@
-use std::net::{Ipv4Addr, Ipv6Addr};
-
-use aya::maps::{HashMap, MapData};
-use oryx_common::MAX_RULES_PORT;
-
-use crate::section::firewall::BlockedPort;
-
-pub fn update_ipv4_blocklist(
-    ipv4_firewall: &mut HashMap<MapData, u32, [u16; MAX_RULES_PORT]>,
-    addr: Ipv4Addr,
-    port: BlockedPort,
-    to_insert: bool,
-) {
-    if let Ok(mut blocked_ports) = ipv4_firewall.get(&addr.to_bits(), 0) {
-        match port {
-            BlockedPort::Single(port) => {
-                if to_insert {
-                    if let Some((first_zero_index, _)) = blocked_ports
-                        .iter()
-                        .enumerate()
-                        .find(|&(_, &value)| value == 0)
-                    {
-                        blocked_ports[first_zero_index] = port;
-                        ipv4_firewall
-                            .insert(addr.to_bits(), blocked_ports, 0)
-                            .unwrap();
-                    } else {
-                        unreachable!();
-                    }
-                } else {
-                    let not_null_ports =
-                        blocked_ports.into_iter().filter(|p| *p != 0 && *p != port);
-
-                    let mut blocked_ports = [0; MAX_RULES_PORT];
-
-                    for (idx, p) in not_null_ports.enumerate() {
-                        blocked_ports[idx] = p;
-                    }
-
-                    if blocked_ports.iter().all(|&port| port == 0) {
-                        ipv4_firewall.remove(&addr.to_bits()).unwrap();
-                    } else {
-                        ipv4_firewall
-                            .insert(addr.to_bits(), blocked_ports, 0)
-                            .unwrap();
-                    }
-                }
+use std::{collections::HashMap, net::IpAddr, time::Duration};
+
+use aya::{
+    Ebpf,
+    maps::{
+        Array, MapData, MapError, PerCpuArray, PerCpuValues,
+        lpm_trie::{Key, LpmTrie},
+    },
+    util::nr_cpus,
+};
+use chrono::{TimeDelta, Utc};
+use itertools::Itertools;
+use log::error;
+use oryx_common::{
+    MAX_SUBNET_ENTRIES,
+    firewall::{
+        ACTION_ALLOW, ACTION_BLOCK, ACTION_LIMIT_BYTES, ACTION_LIMIT_PACKETS, Blocklist,
+        BlocklistEntry, POLICY_ACCEPT, POLICY_DENY,
+    },
+};
+
+use crate::section::firewall::{
+    FirewallCapacity, FirewallPolicy, FirewallRule, FirewallStats, IpNet, RuleAction, RuleStats,
+    rate::{RateLimit, RateUnit},
+};
+
+use super::{BlocklistEntryValue, BlocklistValue, DropCounters};
+
+// Interval between two reads of the RULE_COUNTERS map
+pub const COUNTERS_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+struct Slot {
+    index: u16,
+    // Stats of the previous activations of the rule
+    base: RuleStats,
+}
+
+// Each enabled rule owns a slot in the RULE_COUNTERS map
+#[derive(Debug)]
+struct RuleSlots {
+    slots: HashMap<uuid::Uuid, Slot>,
+    // Size of the RULE_COUNTERS map
+    capacity: u32,
+}
+
+impl RuleSlots {
+    fn new(capacity: u32) -> Self {
+        Self {
+            slots: HashMap::new(),
+            capacity,
+        }
+    }
+
+    fn acquire(
+        &mut self,
+        rule_id: uuid::Uuid,
+        counters: &mut PerCpuArray<MapData, DropCounters>,
+        stats: &FirewallStats,
+    ) -> Option<u16> {
+        if let Some(slot) = self.slots.get(&rule_id) {
+            return Some(slot.index);
+        }
+
+        let index = (0..self.capacity as u16)
+            .find(|index| self.slots.values().all(|slot| slot.index != *index))?;
+
+        // Reset the counters left by the previous owner of the slot
+        let nr_cpus = nr_cpus().ok()?;
+        let values = PerCpuValues::try_from(vec![DropCounters::default(); nr_cpus]).ok()?;
+        counters.set(index as u32, values, 0).ok()?;
+
+        let base = *stats.lock().unwrap().entry(rule_id).or_default();
+        self.slots.insert(rule_id, Slot { index, base });
+
+        Some(index)
+    }
+
+    fn release(
+        &mut self,
+        rule_id: uuid::Uuid,
+        counters: &PerCpuArray<MapData, DropCounters>,
+        stats: &FirewallStats,
+    ) {
+        self.sync(counters, stats);
+        self.slots.remove(&rule_id);
+    }
+
+    fn sync(&self, counters: &PerCpuArray<MapData, DropCounters>, stats: &FirewallStats) {
+        if self.slots.is_empty() {
+            return;
+        }
+
+        let now = monotonic_now();
+        let mut stats = stats.lock().unwrap();
+
+        for (rule_id, slot) in &self.slots {
+            // The rule was deleted
+            let Some(rule_stats) = stats.get_mut(rule_id) else {
+                continue;
+            };
+
+            let Ok(values) = counters.get(&(slot.index as u32), 0) else {
+                continue;
+            };
+
+            let mut current = slot.base;
+            let mut last_hit = 0;
+
+            for DropCounters(counters) in values.iter() {
+                current.packets += counters.packets;
+                current.bytes += counters.bytes;
+                last_hit = last_hit.max(counters.last_hit);
             }
-            BlockedPort::All => {
-                if to_insert {
-                    ipv4_firewall
-                        .insert(addr.to_bits(), [0; MAX_RULES_PORT], 0)
-                        .unwrap();
-                } else {
-                    ipv4_firewall.remove(&addr.to_bits()).unwrap();
-                }
+
+            if last_hit != 0 {
+                current.last_hit =
+                    Some(Utc::now() - TimeDelta::nanoseconds(now.saturating_sub(last_hit) as i64));
             }
+
+            *rule_stats = current;
         }
-    } else if to_insert {
-        let mut blocked_ports: [u16; MAX_RULES_PORT] = [0; MAX_RULES_PORT];
-        match port {
-            BlockedPort::Single(port) => {
-                blocked_ports[0] = port;
-            }
-            BlockedPort::All => {}
+    }
+}
+
+// (addr, mask) as stored in BlocklistEntry, IPv4 in the lower half
+fn local_net(ip: Option<IpNet>) -> ([u64; 2], [u64; 2]) {
+    let Some(ip) = ip else {
+        return ([0; 2], [0; 2]);
+    };
+
+    let (bits, mask) = match ip.addr() {
+        IpAddr::V4(addr) => {
+            let mask = u32::MAX.checked_shl(32 - ip.prefix() as u32).unwrap_or(0);
+            (addr.to_bits() as u128, mask as u128)
         }
+        IpAddr::V6(addr) => {
+            let mask = u128::MAX.checked_shl(128 - ip.prefix() as u32).unwrap_or(0);
+            (addr.to_bits(), mask)
+        }
+    };
+
+    (
+        [((bits & mask) >> 64) as u64, (bits & mask) as u64],
+        [(mask >> 64) as u64, mask as u64],
+    )
+}
 
-        ipv4_firewall
-            .insert(addr.to_bits(), blocked_ports, 0)
-            .unwrap();
+// Entries of each remote subnet, in the evaluation order
+pub fn subnet_entries(rules: &[(&FirewallRule, u16)]) -> HashMap<IpNet, Vec<BlocklistEntry>> {
+    // Rules by remote subnet, the allow rules first, then the limit and the block ones
+    let mut rules_by_subnet: HashMap<IpNet, Vec<(&FirewallRule, u16)>> = HashMap::new();
+    for (rule, slot) in rules {
+        for subnet in rule.remote_subnets() {
+            rules_by_subnet
+                .entry(subnet)
+                .or_default()
+                .push((rule, *slot));
+        }
+    }
+    for subnet_rules in rules_by_subnet.values_mut() {
+        subnet_rules.sort_by_key(|(rule, _)| match rule.action {
+            RuleAction::Allow => 0,
+            RuleAction::Limit(_) => 1,
+            RuleAction::Block => 2,
+        });
     }
+
+    rules_by_subnet
+        .keys()
+        .map(|subnet| {
+            // The rules of the subnet, then the ones of the subnets containing it
+            let entries = (0..=subnet.prefix())
+                .rev()
+                .filter_map(|prefix| rules_by_subnet.get(&IpNet::new(subnet.addr(), prefix)))
+                .flatten()
+                .flat_map(|(rule, slot)| {
+                    // The ICMP types are matched as remote ports
+                    let remote_ranges = match rule.protocol.icmp_types() {
+                        Some(icmp_types) => vec![icmp_types],
+                        None => rule.remote.port.ranges(),
+                    };
+                    let (local_addr, local_mask) = local_net(rule.local.ip);
+                    let (action, rate) = match rule.action {
+                        RuleAction::Allow => (ACTION_ALLOW, 0),
+                        RuleAction::Block => (ACTION_BLOCK, 0),
+                        RuleAction::Limit(RateLimit { value, unit }) => match unit {
+                            RateUnit::Packets => (ACTION_LIMIT_PACKETS, value),
+                            RateUnit::Bytes => (ACTION_LIMIT_BYTES, value),
+                        },
+                    };
+
+                    rule.local
+                        .port
+                        .ranges()
+                        .into_iter()
+                        .cartesian_product(remote_ranges)
+                        .map(move |(local_ports, remote_ports)| BlocklistEntry {
+                            remote_start_port: remote_ports.0,
+                            remote_end_port: remote_ports.1,
+                            local_start_port: local_ports.0,
+                            local_end_port: local_ports.1,
+                            local_addr,
+                            local_mask,
+                            rate,
+                            slot: *slot,
+                            proto: rule.protocol.number(),
+                            action,
+                        })
+                })
+                .collect();
+
+            (*subnet, entries)
+        })
+        .collect()
 }
 
-pub fn update_ipv6_blocklist(
-    ipv6_firewall: &mut HashMap<MapData, u128, [u16; MAX_RULES_PORT]>,
-    addr: Ipv6Addr,
-    port: BlockedPort,
-    to_insert: bool,
-) {
-    if let Ok(mut blocked_ports) = ipv6_firewall.get(&addr.to_bits(), 0) {
-        match port {
-            BlockedPort::Single(port) => {
-                if to_insert {
-                    if let Some((first_zero_index, _)) = blocked_ports
-                        .iter()
-                        .enumerate()
-                        .find(|&(_, &value)| value == 0)
-                    {
-                        blocked_ports[first_zero_index] = port;
-                        ipv6_firewall
-                            .insert(addr.to_bits(), blocked_ports, 0)
-                            .unwrap();
-                    } else {
-                        unreachable!(); // list is full
-                    }
-                } else {
-                    let not_null_ports =
-                        blocked_ports.into_iter().filter(|p| *p != 0 && *p != port);
-
-                    let mut blocked_ports = [0; MAX_RULES_PORT];
-
-                    for (idx, p) in not_null_ports.enumerate() {
-                        blocked_ports[idx] = p;
-                    }
-
-                    if blocked_ports.iter().all(|&port| port == 0) {
-                        ipv6_firewall.remove(&addr.to_bits()).unwrap();
-                    } else {
-                        ipv6_firewall
-                            .insert(addr.to_bits(), blocked_ports, 0)
-                            .unwrap();
-                    }
-                }
+// Same clock as bpf_ktime_get_ns
+fn monotonic_now() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+// Userspace side of the firewall of one direction
+pub struct FirewallMaps {
+    ipv4_blocklist: LpmTrie<MapData, [u8; 4], BlocklistValue>,
+    ipv6_blocklist: LpmTrie<MapData, [u8; 16], BlocklistValue>,
+    entries: Array<MapData, BlocklistEntryValue>,
+    policy: Array<MapData, u8>,
+    counters: PerCpuArray<MapData, DropCounters>,
+    slots: RuleSlots,
+    stats: FirewallStats,
+    // Enabled rules with their slot
+    rules: Vec<(FirewallRule, u16)>,
+    // Size of each half of the BLOCKLIST_ENTRIES map
+    entries_capacity: u32,
+    // Half of the BLOCKLIST_ENTRIES map the tries point to
+    half: u32,
+    // Entries of each subnet in the maps
+    subnets: HashMap<IpNet, Vec<BlocklistEntry>>,
+    // Trie values of each subnet, restored when a sync fails
+    blocklists: HashMap<IpNet, Blocklist>,
+    // A subnet could not be restored or removed, the next sync is not skipped
+    is_dirty: bool,
+}
+
+impl FirewallMaps {
+    pub fn new(bpf: &mut Ebpf, stats: FirewallStats, capacity: FirewallCapacity) -> Self {
+        Self {
+            ipv4_blocklist: LpmTrie::try_from(bpf.take_map("BLOCKLIST_IPV4").unwrap()).unwrap(),
+            ipv6_blocklist: LpmTrie::try_from(bpf.take_map("BLOCKLIST_IPV6").unwrap()).unwrap(),
+            entries: Array::try_from(bpf.take_map("BLOCKLIST_ENTRIES").unwrap()).unwrap(),
+            policy: Array::try_from(bpf.take_map("FIREWALL_POLICY").unwrap()).unwrap(),
+            counters: PerCpuArray::try_from(bpf.take_map("RULE_COUNTERS").unwrap()).unwrap(),
+            slots: RuleSlots::new(capacity.rules),
+            stats,
+            rules: Vec::new(),
+            entries_capacity: capacity.entries,
+            half: 0,
+            subnets: HashMap::new(),
+            blocklists: HashMap::new(),
+            is_dirty: false,
+        }
+    }
+
+    fn insert_subnet(&mut self, subnet: IpNet, blocklist: Blocklist) -> Result<(), MapError> {
+        match subnet.addr() {
+            IpAddr::V4(addr) => self.ipv4_blocklist.insert(
+                &Key::new(subnet.prefix() as u32, addr.octets()),
+                BlocklistValue(blocklist),
+                0,
+            ),
+            IpAddr::V6(addr) => self.ipv6_blocklist.insert(
+                &Key::new(subnet.prefix() as u32, addr.octets()),
+                BlocklistValue(blocklist),
+                0,
+            ),
+        }
+    }
+
+    fn remove_subnet(&mut self, subnet: IpNet) -> Result<(), MapError> {
+        match subnet.addr() {
+            IpAddr::V4(addr) => self
+                .ipv4_blocklist
+                .remove(&Key::new(subnet.prefix() as u32, addr.octets())),
+            IpAddr::V6(addr) => self
+                .ipv6_blocklist
+                .remove(&Key::new(subnet.prefix() as u32, addr.octets())),
+        }
+    }
+
+    // An enabled rule that does not fit in the maps is removed, with the reason
+    pub fn update(&mut self, rule: FirewallRule) -> Result<(), String> {
+        self.rules.retain(|(r, _)| r.id != rule.id);
+
+        if !rule.enabled {
+            self.slots.release(rule.id, &self.counters, &self.stats);
+            return self.sync_blocklists();
+        }
+
+        let rule_id = rule.id;
+        let Some(slot) = self.slots.acquire(rule_id, &mut self.counters, &self.stats) else {
+            return Err("No counters slot left".to_string());
+        };
+        self.rules.push((rule, slot));
+
+        if let Err(e) = self.sync_blocklists() {
+            self.rules.retain(|(r, _)| r.id != rule_id);
+            self.slots.release(rule_id, &self.counters, &self.stats);
+            if let Err(e) = self.sync_blocklists() {
+                error!("Failed to restore the firewall maps. {e}");
             }
-            BlockedPort::All => {
-                if to_insert {
-                    ipv6_firewall
-                        .insert(addr.to_bits(), [0; MAX_RULES_PORT], 0)
-                        .unwrap();
-                } else {
-                    ipv6_firewall.remove(&addr.to_bits()).unwrap();
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn set_policy(&mut self, policy: FirewallPolicy) {
+        let policy = match policy {
+            FirewallPolicy::Accept => POLICY_ACCEPT,
+            FirewallPolicy::Deny => POLICY_DENY,
+        };
+        let _ = self.policy.set(0, policy, 0);
+    }
+
+    pub fn sync_stats(&self) {
+        self.slots.sync(&self.counters, &self.stats);
+    }
+
+    // Disables the rules past their expiry, returns their ids
+    pub fn expire_rules(&mut self) -> Vec<uuid::Uuid> {
+        let now = Utc::now();
+
+        let expired: Vec<uuid::Uuid> = self
+            .rules
+            .iter()
+            .filter(|(rule, _)| rule.expires_at.is_some_and(|expires_at| expires_at <= now))
+            .map(|(rule, _)| rule.id)
+            .collect();
+
+        if expired.is_empty() {
+            return expired;
+        }
+
+        for rule_id in &expired {
+            self.slots.release(*rule_id, &self.counters, &self.stats);
+        }
+        self.rules.retain(|(rule, _)| !expired.contains(&rule.id));
+
+        if let Err(e) = self.sync_blocklists() {
+            error!("Failed to remove the expired firewall rules. {e}");
+        }
+
+        expired
+    }
+
+    fn sync_blocklists(&mut self) -> Result<(), String> {
+        let rules: Vec<(&FirewallRule, u16)> = self
+            .rules
+            .iter()
+            .map(|(rule, slot)| (rule, *slot))
+            .collect();
+
+        let subnets = subnet_entries(&rules);
+
+        if let Some((subnet, entries)) = subnets
+            .iter()
+            .find(|(_, entries)| entries.len() > MAX_SUBNET_ENTRIES as usize)
+        {
+            return Err(format!(
+                "{subnet} would hold {} entries, the limit is {MAX_SUBNET_ENTRIES}",
+                entries.len()
+            ));
+        }
+
+        let nb_entries: usize = subnets.values().map(Vec::len).sum();
+        if nb_entries > self.entries_capacity as usize {
+            return Err(format!(
+                "{nb_entries} entries, the limit is {}",
+                self.entries_capacity
+            ));
+        }
+
+        // Unchanged
+        if subnets == self.subnets && !self.is_dirty {
+            return Ok(());
+        }
+
+        // The packets keep matching the current entries while the other half is written
+        let half = 1 - self.half;
+        let mut start = half * self.entries_capacity;
+        let mut blocklists = HashMap::with_capacity(subnets.len());
+
+        for (subnet, entries) in &subnets {
+            for (index, entry) in entries.iter().enumerate() {
+                if let Err(e) =
+                    self.entries
+                        .set(start + index as u32, BlocklistEntryValue(*entry), 0)
+                {
+                    error!("Failed to write the firewall entries. {e}");
+                    return Err("Failed to write the firewall entries".to_string());
                 }
             }
+
+            blocklists.insert(
+                *subnet,
+                Blocklist {
+                    start,
+                    len: entries.len() as u32,
+                },
+            );
+            start += entries.len() as u32;
+        }
+
+        // Re-pointed one by one, undone if one fails so that no key is left on the written half
+        let mut inserted = Vec::with_capacity(blocklists.len());
+        for (subnet, blocklist) in &blocklists {
+            if let Err(e) = self.insert_subnet(*subnet, *blocklist) {
+                error!("Failed to add the firewall subnet {subnet}. {e}");
+                self.restore_subnets(&inserted);
+                return Err(format!("Failed to add the subnet {subnet}"));
+            }
+            inserted.push(*subnet);
         }
-    } else if to_insert {
-        let mut blocked_ports: [u16; MAX_RULES_PORT] = [0; MAX_RULES_PORT];
-        match port {
-            BlockedPort::Single(port) => {
-                blocked_ports[0] = port;
+
+        self.is_dirty = false;
+
+        // The stale subnets go once the new entries are live, kept to be retried on failure
+        let stale: Vec<(IpNet, Blocklist)> = self
+            .blocklists
+            .iter()
+            .filter(|(subnet, _)| !blocklists.contains_key(subnet))
+            .map(|(subnet, blocklist)| (*subnet, *blocklist))
+            .collect();
+        for (subnet, blocklist) in stale {
+            if let Err(e) = self.remove_subnet(subnet) {
+                error!("Failed to remove the firewall subnet {subnet}. {e}");
+                blocklists.insert(subnet, blocklist);
+                self.is_dirty = true;
             }
-            BlockedPort::All => {}
         }
 
-        ipv6_firewall
-            .insert(addr.to_bits(), blocked_ports, 0)
-            .unwrap();
+        self.half = half;
+        self.subnets = subnets;
+        self.blocklists = blocklists;
+
+        Ok(())
+    }
+
+    // Points the given subnets back to their previous entries
+    fn restore_subnets(&mut self, subnets: &[IpNet]) {
+        for subnet in subnets {
+            let result = match self.blocklists.get(subnet).copied() {
+                Some(blocklist) => self.insert_subnet(*subnet, blocklist),
+                None => self.remove_subnet(*subnet),
+            };
+
+            if let Err(e) = result {
+                error!("Failed to restore the firewall subnet {subnet}. {e}");
+                self.is_dirty = true;
+            }
+        }
     }
 }
@@ -1,4 +1,4 @@
-use crate::{app::AppResult, notification::Notification};
+use crate::{app::AppResult, notification::Notification, section::firewall::FirewallEvent};
 use crossterm::event::{self, Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent};
 use std::{
     thread,
@@ -12,6 +12,7 @@ pub enum Event {
     Mouse(MouseEvent),
     Resize(u16, u16),
     Notification(Notification),
+    Firewall(FirewallEvent),
     Reset,
 }
 
@@ -1,9 +1,13 @@
+pub mod pcapng;
+
 use std::{
     ffi::CString,
-    fs::{OpenOptions, create_dir},
-    io::prelude::*,
+    fmt::Display,
+    fs::{File, OpenOptions, create_dir},
+    io::{BufWriter, prelude::*},
     os::unix::fs::chown,
     path::PathBuf,
+    str::FromStr,
 };
 
 use chrono::Local;
@@ -18,8 +22,52 @@ use crate::{
 
 use anyhow::{Result, bail};
 
-pub fn export(packets: &PacketStore) -> Result<()> {
-    let local_date = Local::now().format("%Y-%m-%d_%H-%M");
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Text,
+    Pcapng,
+}
+
+impl ExportFormat {
+    pub fn all() -> [ExportFormat; 2] {
+        [ExportFormat::Text, ExportFormat::Pcapng]
+    }
+
+    fn file_name(&self, date: &str) -> String {
+        match self {
+            ExportFormat::Text => format!("capture-{date}"),
+            ExportFormat::Pcapng => format!("capture-{date}.pcapng"),
+        }
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Text => write!(f, "Text"),
+            ExportFormat::Pcapng => write!(f, "Pcapng"),
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Text" | "text" => Ok(Self::Text),
+            "Pcapng" | "pcapng" => Ok(Self::Pcapng),
+            _ => Err(format!("Unknown export format {s}")),
+        }
+    }
+}
+
+pub fn export(
+    packets: &PacketStore,
+    format: ExportFormat,
+    interfaces: &[String],
+) -> Result<PathBuf> {
+    let local_date = Local::now().format("%Y-%m-%d_%H-%M").to_string();
 
     let user = match std::env::var("SUDO_USER") {
         Ok(user) => user,
@@ -47,16 +95,50 @@ pub fn export(packets: &PacketStore) -> Result<()> {
         chown(&oryx_export_dir, Some(uid), Some(gid))?;
     }
 
-    let oryx_export_file = oryx_export_dir.join(format!("capture-{local_date}"));
+    let oryx_export_file = oryx_export_dir.join(format.file_name(&local_date));
 
-    let mut file = OpenOptions::new()
+    let file = OpenOptions::new()
         .create(true)
         .write(true)
         .truncate(true)
-        .open(&oryx_export_file)
-        .unwrap();
-    chown(oryx_export_file, Some(uid), Some(uid))?;
+        .open(&oryx_export_file)?;
+    chown(&oryx_export_file, Some(uid), Some(gid))?;
+
+    match format {
+        ExportFormat::Text => export_text(file, packets)?,
+        ExportFormat::Pcapng => export_pcapng(file, packets, interfaces)?,
+    }
+
+    Ok(oryx_export_file)
+}
+
+fn export_pcapng(file: File, packets: &PacketStore, interfaces: &[String]) -> Result<()> {
+    let mut writer = pcapng::PcapngWriter::new(BufWriter::new(file))?;
+
+    let mut interface_ids = Vec::with_capacity(interfaces.len());
+    for interface in interfaces {
+        interface_ids.push(writer.add_interface(interface)?);
+    }
+
+    // Packets are not tagged with the interface they were captured on,
+    // so they are all attached to the first one.
+    let interface_id = match interface_ids.first() {
+        Some(id) => *id,
+        None => writer.add_interface("unknown")?,
+    };
+
+    packets.for_each(|app_packet| {
+        let raw = app_packet.raw.and_then(|raw| packets.raw_bytes(raw));
+        writer.write_packet(interface_id, app_packet, raw)?;
+        Ok(())
+    })?;
+
+    writer.flush()?;
+
+    Ok(())
+}
 
+fn export_text(mut file: File, packets: &PacketStore) -> Result<()> {
     let headers = (
         "Src Ip", "Src Port", "Dst Ip", "Dst Port", "Protocol", "Pid", "Date",
     );
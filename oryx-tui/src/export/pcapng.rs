@@ -0,0 +1,283 @@
+use std::io::{self, Write};
+
+use network_types::eth::EthHdr;
+
+use crate::packet::{
+    AppPacket, NetworkPacket,
+    direction::TrafficDirection,
+    link::{ArpPacket, ArpType},
+    network::{IpPacket, icmp::IcmpPacket, ip::IpProto},
+};
+
+// https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-02.html
+
+const SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+const LINKTYPE_ETHERNET: u16 = 1;
+
+// Options
+const OPT_END_OF_OPT: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+const SHB_USER_APPL: u16 = 4;
+const IF_NAME: u16 = 2;
+const IF_TSRESOL: u16 = 9;
+const EPB_FLAGS: u16 = 2;
+
+// epb_flags inbound/outbound bits
+const EPB_FLAG_INBOUND: u32 = 0b01;
+const EPB_FLAG_OUTBOUND: u32 = 0b10;
+
+// Timestamps are written in microseconds
+const TS_RESOLUTION: u8 = 6;
+
+pub struct PcapngWriter<W: Write> {
+    inner: W,
+    nb_interfaces: u32,
+}
+
+impl<W: Write> PcapngWriter<W> {
+    pub fn new(inner: W) -> io::Result<Self> {
+        let mut writer = Self {
+            inner,
+            nb_interfaces: 0,
+        };
+
+        let mut body = Vec::with_capacity(64);
+        body.extend_from_slice(&BYTE_ORDER_MAGIC.to_ne_bytes());
+        body.extend_from_slice(&1u16.to_ne_bytes()); // major version
+        body.extend_from_slice(&0u16.to_ne_bytes()); // minor version
+        body.extend_from_slice(&(-1i64).to_ne_bytes()); // section length not specified
+
+        let user_appl = format!("oryx {}", env!("CARGO_PKG_VERSION"));
+        push_option(&mut body, SHB_USER_APPL, user_appl.as_bytes());
+        push_option(&mut body, OPT_END_OF_OPT, &[]);
+
+        writer.write_block(SECTION_HEADER_BLOCK, &body)?;
+
+        Ok(writer)
+    }
+
+    // returns the interface id to be used by the packets captured on it
+    pub fn add_interface(&mut self, name: &str) -> io::Result<u32> {
+        let mut body = Vec::with_capacity(32);
+        body.extend_from_slice(&LINKTYPE_ETHERNET.to_ne_bytes());
+        body.extend_from_slice(&0u16.to_ne_bytes()); // reserved
+        body.extend_from_slice(&0u32.to_ne_bytes()); // no snaplen
+
+        push_option(&mut body, IF_NAME, name.as_bytes());
+        push_option(&mut body, IF_TSRESOL, &[TS_RESOLUTION]);
+        push_option(&mut body, OPT_END_OF_OPT, &[]);
+
+        self.write_block(INTERFACE_DESCRIPTION_BLOCK, &body)?;
+
+        let id = self.nb_interfaces;
+        self.nb_interfaces += 1;
+        Ok(id)
+    }
+
+    // `raw` holds the captured bytes of the packet when a snaplen is set
+    pub fn write_packet(
+        &mut self,
+        interface_id: u32,
+        app_packet: &AppPacket,
+        raw: Option<Vec<u8>>,
+    ) -> io::Result<()> {
+        let (headers, original_length) = frame_bytes(app_packet);
+
+        let data = match raw {
+            Some(raw) if raw.len() >= headers.len() => raw,
+            _ => headers,
+        };
+        let original_length = original_length.max(data.len() as u32);
+
+        let timestamp = app_packet.timestamp.timestamp_micros() as u64;
+
+        let mut body = Vec::with_capacity(data.len() + 64);
+        body.extend_from_slice(&interface_id.to_ne_bytes());
+        body.extend_from_slice(&((timestamp >> 32) as u32).to_ne_bytes());
+        body.extend_from_slice(&(timestamp as u32).to_ne_bytes());
+        body.extend_from_slice(&(data.len() as u32).to_ne_bytes());
+        body.extend_from_slice(&original_length.to_ne_bytes());
+        body.extend_from_slice(&data);
+        pad(&mut body);
+
+        let flags = match app_packet.direction {
+            TrafficDirection::Ingress => EPB_FLAG_INBOUND,
+            TrafficDirection::Egress => EPB_FLAG_OUTBOUND,
+        };
+        push_option(&mut body, EPB_FLAGS, &flags.to_ne_bytes());
+
+        if let Some(pid) = app_packet.pid {
+            push_option(&mut body, OPT_COMMENT, format!("pid: {pid}").as_bytes());
+        }
+
+        push_option(&mut body, OPT_END_OF_OPT, &[]);
+
+        self.write_block(ENHANCED_PACKET_BLOCK, &body)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    fn write_block(&mut self, block_type: u32, body: &[u8]) -> io::Result<()> {
+        // block type + 2 * block total length + body
+        let total_length = (12 + body.len()) as u32;
+
+        self.inner.write_all(&block_type.to_ne_bytes())?;
+        self.inner.write_all(&total_length.to_ne_bytes())?;
+        self.inner.write_all(body)?;
+        self.inner.write_all(&total_length.to_ne_bytes())?;
+
+        Ok(())
+    }
+}
+
+fn pad(buffer: &mut Vec<u8>) {
+    let padding = (4 - buffer.len() % 4) % 4;
+    buffer.extend(std::iter::repeat_n(0, padding));
+}
+
+fn push_option(buffer: &mut Vec<u8>, code: u16, value: &[u8]) {
+    buffer.extend_from_slice(&code.to_ne_bytes());
+    buffer.extend_from_slice(&(value.len() as u16).to_ne_bytes());
+    buffer.extend_from_slice(value);
+    pad(buffer);
+}
+
+// Rebuild the on-wire bytes of the frame from the parsed headers.
+// The original length is derived from the IP length fields and
+// the payload is left out.
+fn frame_bytes(app_packet: &AppPacket) -> (Vec<u8>, u32) {
+    let mut data = Vec::with_capacity(128);
+
+    let eth_header = unsafe {
+        core::slice::from_raw_parts(
+            &app_packet.frame.header as *const EthHdr as *const u8,
+            EthHdr::LEN,
+        )
+    };
+    data.extend_from_slice(eth_header);
+
+    let original_length = match app_packet.frame.payload {
+        NetworkPacket::Arp(arp_packet) => {
+            write_arp(&mut data, &arp_packet);
+            data.len()
+        }
+        NetworkPacket::Ip(IpPacket::V4(ipv4_packet)) => {
+            // IP options are not captured
+            data.push((4 << 4) | 5);
+            data.push(ipv4_packet.tos);
+            data.extend_from_slice(&ipv4_packet.total_length.to_be_bytes());
+            data.extend_from_slice(&ipv4_packet.id.to_be_bytes());
+            data.extend_from_slice(&(ipv4_packet.fragment_offset & 0x1fff).to_be_bytes());
+            data.push(ipv4_packet.ttl);
+            data.push(ip_proto_number(&ipv4_packet.proto));
+            data.extend_from_slice(&ipv4_packet.checksum.to_be_bytes());
+            data.extend_from_slice(&ipv4_packet.src_ip.octets());
+            data.extend_from_slice(&ipv4_packet.dst_ip.octets());
+
+            write_ip_proto(&mut data, &ipv4_packet.proto);
+
+            EthHdr::LEN + ipv4_packet.total_length as usize
+        }
+        NetworkPacket::Ip(IpPacket::V6(ipv6_packet)) => {
+            let first_word = (6u32 << 28)
+                | ((ipv6_packet.ds as u32 & 0x3f) << 22)
+                | ((ipv6_packet.ecn as u32 & 0x3) << 20)
+                | (ipv6_packet.flow_label & 0x000f_ffff);
+            data.extend_from_slice(&first_word.to_be_bytes());
+            data.extend_from_slice(&ipv6_packet.payload_length.to_be_bytes());
+            data.push(ip_proto_number(&ipv6_packet.proto));
+            data.push(ipv6_packet.hop_limit);
+            data.extend_from_slice(&ipv6_packet.src_ip.octets());
+            data.extend_from_slice(&ipv6_packet.dst_ip.octets());
+
+            write_ip_proto(&mut data, &ipv6_packet.proto);
+
+            EthHdr::LEN + 40 + ipv6_packet.payload_length as usize
+        }
+    };
+
+    let original_length = original_length.max(data.len()) as u32;
+
+    (data, original_length)
+}
+
+fn ip_proto_number(proto: &IpProto) -> u8 {
+    match proto {
+        IpProto::Tcp(_) => 6,
+        IpProto::Udp(_) => 17,
+        IpProto::Sctp(_) => 132,
+        IpProto::Icmp(IcmpPacket::V4(_)) => 1,
+        IpProto::Icmp(IcmpPacket::V6(_)) => 58,
+    }
+}
+
+fn write_ip_proto(data: &mut Vec<u8>, proto: &IpProto) {
+    match proto {
+        IpProto::Tcp(p) => {
+            let flags = ((p.cwr & 1) << 7)
+                | ((p.ece & 1) << 6)
+                | ((p.urg & 1) << 5)
+                | ((p.ack & 1) << 4)
+                | ((p.psh & 1) << 3)
+                | ((p.rst & 1) << 2)
+                | ((p.syn & 1) << 1)
+                | (p.fin & 1);
+
+            data.extend_from_slice(&p.src_port.to_be_bytes());
+            data.extend_from_slice(&p.dst_port.to_be_bytes());
+            data.extend_from_slice(&p.seq.to_be_bytes());
+            data.extend_from_slice(&p.ack_seq.to_be_bytes());
+            // TCP options are not captured
+            data.push(5 << 4);
+            data.push(flags as u8);
+            data.extend_from_slice(&p.window.to_be_bytes());
+            data.extend_from_slice(&p.checksum.to_be_bytes());
+            data.extend_from_slice(&p.urg_ptr.to_be_bytes());
+        }
+        IpProto::Udp(p) => {
+            data.extend_from_slice(&p.src_port.to_be_bytes());
+            data.extend_from_slice(&p.dst_port.to_be_bytes());
+            data.extend_from_slice(&p.length.to_be_bytes());
+            data.extend_from_slice(&p.checksum.to_be_bytes());
+        }
+        IpProto::Sctp(p) => {
+            data.extend_from_slice(&p.src_port.to_be_bytes());
+            data.extend_from_slice(&p.dst_port.to_be_bytes());
+            data.extend_from_slice(&p.verification_tag.to_be_bytes());
+            data.extend_from_slice(&p.checksum.to_be_bytes());
+        }
+        IpProto::Icmp(IcmpPacket::V4(p)) => {
+            data.push(u8::from(p.icmp_type));
+            data.push(p.code);
+            data.extend_from_slice(&p.checksum.to_be_bytes());
+        }
+        IpProto::Icmp(IcmpPacket::V6(p)) => {
+            data.push(p.icmp_type as u8);
+            data.push(p.code);
+            data.extend_from_slice(&p.checksum.to_be_bytes());
+        }
+    }
+}
+
+fn write_arp(data: &mut Vec<u8>, arp_packet: &ArpPacket) {
+    let oper: u16 = match arp_packet.arp_type {
+        ArpType::Request => 1,
+        ArpType::Reply => 2,
+    };
+
+    data.extend_from_slice(&arp_packet.htype.to_be_bytes());
+    data.extend_from_slice(&arp_packet.ptype.to_be_bytes());
+    data.push(arp_packet.hlen);
+    data.push(arp_packet.plen);
+    data.extend_from_slice(&oper.to_be_bytes());
+    data.extend_from_slice(&arp_packet.src_mac.0);
+    data.extend_from_slice(&arp_packet.src_ip.octets());
+    data.extend_from_slice(&arp_packet.dst_mac.0);
+    data.extend_from_slice(&arp_packet.dst_ip.octets());
+}
@@ -1,4 +1,6 @@
 pub mod direction;
+pub mod display;
+pub mod expression;
 pub mod fuzzy;
 mod link;
 mod network;
@@ -6,11 +8,15 @@ mod transport;
 
 use crossterm::event::{KeyCode, KeyEvent};
 use direction::TrafficDirectionFilter;
+use expression::ExpressionFilter;
 use link::LinkFilter;
 use network::NetworkFilter;
-use oryx_common::protocols::{
-    LinkProtocol, NB_LINK_PROTOCOL, NB_NETWORK_PROTOCOL, NB_TRANSPORT_PROTOCOL, NetworkProtocol,
-    Protocol, TransportProtocol,
+use oryx_common::{
+    filter::FilterCondition,
+    protocols::{
+        LinkProtocol, NB_LINK_PROTOCOL, NB_NETWORK_PROTOCOL, NB_TRANSPORT_PROTOCOL,
+        NetworkProtocol, Protocol, TransportProtocol,
+    },
 };
 use ratatui::{
     Frame,
@@ -29,13 +35,14 @@ use crate::{
     interface::Interface,
     packet::direction::TrafficDirection,
     packet_store::PacketStore,
-    section::firewall::FirewallSignal,
+    section::firewall::{FirewallCapacity, FirewallSignal, FirewallStats},
 };
 
 #[derive(Debug, Clone)]
 pub enum FilterChannelSignal {
     ProtoUpdate((Protocol, bool)),
     DirectionUpdate(bool),
+    ExpressionUpdate(Vec<FilterCondition>),
     Kill,
 }
 
@@ -86,6 +93,7 @@ pub enum FocusedBlock {
     NetworkFilter,
     LinkFilter,
     TrafficDirection,
+    Expression,
     Apply,
 }
 
@@ -96,19 +104,27 @@ pub struct Filter {
     pub transport: TransportFilter,
     pub link: LinkFilter,
     pub traffic_direction: TrafficDirectionFilter,
+    pub expression: ExpressionFilter,
     pub filter_chans: IoChannels<FilterChannelSignal>,
     pub firewall_chans: IoChannels<FirewallSignal>,
+    pub firewall_stats: FirewallStats,
     pub focused_block: FocusedBlock,
+    pub snaplen: u32,
+    pub firewall_capacity: FirewallCapacity,
 }
 
 impl Filter {
     pub fn new(
         firewall_chans: IoChannels<FirewallSignal>,
+        firewall_stats: FirewallStats,
         interface_name: Option<String>,
         transport: Vec<TransportProtocol>,
         network: Vec<NetworkProtocol>,
         link: Vec<LinkProtocol>,
         direction: Vec<TrafficDirection>,
+        expression: String,
+        snaplen: u32,
+        firewall_capacity: FirewallCapacity,
     ) -> Self {
         let focused_block = if interface_name.is_some() {
             FocusedBlock::Apply
@@ -122,9 +138,13 @@ impl Filter {
             network: NetworkFilter::new(network),
             link: LinkFilter::new(link),
             traffic_direction: TrafficDirectionFilter::new(direction),
+            expression: ExpressionFilter::new(expression),
             filter_chans: IoChannels::new(),
             firewall_chans,
+            firewall_stats,
             focused_block,
+            snaplen,
+            firewall_capacity,
         }
     }
 
@@ -169,7 +189,10 @@ impl Filter {
             packet_store.clone(),
             self.filter_chans.ingress.receiver.clone(),
             self.firewall_chans.ingress.receiver.clone(),
+            self.firewall_stats.clone(),
             self.traffic_direction.terminate_ingress.clone(),
+            self.snaplen,
+            self.firewall_capacity,
         );
 
         load_egress(
@@ -178,7 +201,10 @@ impl Filter {
             packet_store,
             self.filter_chans.egress.receiver.clone(),
             self.firewall_chans.egress.receiver.clone(),
+            self.firewall_stats.clone(),
             self.traffic_direction.terminate_egress.clone(),
+            self.snaplen,
+            self.firewall_capacity,
         );
 
         self.sync()?;
@@ -196,6 +222,8 @@ impl Filter {
         self.traffic_direction.selected_direction =
             self.traffic_direction.applied_direction.clone();
 
+        self.expression.trigger();
+
         self.transport.state = TableState::default().with_selected(0);
 
         self.focused_block = FocusedBlock::TransportFilter;
@@ -336,10 +364,30 @@ impl Filter {
                 .send(FilterChannelSignal::DirectionUpdate(true))?;
         }
 
+        self.filter_chans
+            .ingress
+            .sender
+            .send(FilterChannelSignal::ExpressionUpdate(
+                self.expression.applied_conditions.clone(),
+            ))?;
+        self.filter_chans
+            .egress
+            .sender
+            .send(FilterChannelSignal::ExpressionUpdate(
+                self.expression.applied_conditions.clone(),
+            ))?;
+
         Ok(())
     }
 
     pub fn handle_key_events(&mut self, key_event: KeyEvent, is_update_popup_displayed: bool) {
+        if self.focused_block == FocusedBlock::Expression
+            && !matches!(key_event.code, KeyCode::Tab | KeyCode::BackTab)
+        {
+            self.expression.handle_key_events(key_event);
+            return;
+        }
+
         match key_event.code {
             KeyCode::Tab => match self.focused_block {
                 FocusedBlock::Interface => {
@@ -366,10 +414,14 @@ impl Filter {
                 }
 
                 FocusedBlock::TrafficDirection => {
-                    self.focused_block = FocusedBlock::Apply;
+                    self.focused_block = FocusedBlock::Expression;
                     self.traffic_direction.state.select(None);
                 }
 
+                FocusedBlock::Expression => {
+                    self.focused_block = FocusedBlock::Apply;
+                }
+
                 FocusedBlock::Apply => {
                     if is_update_popup_displayed {
                         self.focused_block = FocusedBlock::TransportFilter;
@@ -414,10 +466,14 @@ impl Filter {
                     self.traffic_direction.state.select(None);
                 }
 
-                FocusedBlock::Apply => {
+                FocusedBlock::Expression => {
                     self.focused_block = FocusedBlock::TrafficDirection;
                     self.traffic_direction.state.select(Some(0));
                 }
+
+                FocusedBlock::Apply => {
+                    self.focused_block = FocusedBlock::Expression;
+                }
             },
 
             KeyCode::Char('j') | KeyCode::Down => match &self.focused_block {
@@ -502,6 +558,7 @@ impl Filter {
         self.transport.apply();
         self.link.apply();
         self.traffic_direction.apply();
+        self.expression.apply();
     }
 
     pub fn render_on_setup(&mut self, frame: &mut Frame) {
@@ -521,6 +578,7 @@ impl Filter {
             network_filter_block,
             link_filter_block,
             traffic_direction_block,
+            expression_block,
             start_block,
         ) = {
             let chunks = Layout::default()
@@ -531,13 +589,14 @@ impl Filter {
                     Constraint::Length(NB_NETWORK_PROTOCOL),
                     Constraint::Length(NB_LINK_PROTOCOL),
                     Constraint::Length(2),
+                    Constraint::Length(2),
                     Constraint::Length(4),
                 ])
                 .margin(1)
                 .flex(Flex::SpaceBetween)
                 .split(filters_block);
             (
-                chunks[0], chunks[1], chunks[2], chunks[3], chunks[4], chunks[5],
+                chunks[0], chunks[1], chunks[2], chunks[3], chunks[4], chunks[5], chunks[6],
             )
         };
 
@@ -575,6 +634,13 @@ impl Filter {
             false,
         );
 
+        self.expression.render(
+            frame,
+            expression_block,
+            self.focused_block == FocusedBlock::Expression,
+            false,
+        );
+
         let start = BigText::builder()
             .pixel_size(PixelSize::Sextant)
             .style(if self.focused_block == FocusedBlock::Apply {
@@ -730,6 +796,13 @@ impl Filter {
                 .title(" Filters 󱪤 ")
                 .title_style(Style::default().bold().green())
                 .title_alignment(Alignment::Center)
+                .title_bottom(if self.expression.applied_expression.is_empty() {
+                    Line::default()
+                } else {
+                    Line::from(format!(" {} ", self.expression.applied_expression))
+                        .centered()
+                        .yellow()
+                })
                 .padding(Padding::horizontal(2))
                 .borders(Borders::ALL)
                 .style(Style::default())
@@ -745,7 +818,7 @@ impl Filter {
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Fill(1),
-                Constraint::Length(30),
+                Constraint::Length(32),
                 Constraint::Fill(1),
             ])
             .flex(ratatui::layout::Flex::SpaceBetween)
@@ -766,6 +839,7 @@ impl Filter {
             network_filter_block,
             link_filter_block,
             traffic_direction_block,
+            expression_block,
             apply_block,
         ) = {
             let chunks = Layout::default()
@@ -775,12 +849,15 @@ impl Filter {
                     Constraint::Length(NB_NETWORK_PROTOCOL),
                     Constraint::Length(NB_LINK_PROTOCOL),
                     Constraint::Length(2),
+                    Constraint::Length(2),
                     Constraint::Length(4),
                 ])
                 .margin(2)
                 .flex(Flex::SpaceBetween)
                 .split(block);
-            (chunks[0], chunks[1], chunks[2], chunks[3], chunks[4])
+            (
+                chunks[0], chunks[1], chunks[2], chunks[3], chunks[4], chunks[5],
+            )
         };
 
         frame.render_widget(Clear, block);
@@ -821,6 +898,13 @@ impl Filter {
             true,
         );
 
+        self.expression.render(
+            frame,
+            expression_block,
+            self.focused_block == FocusedBlock::Expression,
+            true,
+        );
+
         let apply = BigText::builder()
             .pixel_size(PixelSize::Sextant)
             .style(if self.focused_block == FocusedBlock::Apply {
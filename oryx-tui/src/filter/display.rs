@@ -0,0 +1,614 @@
+use std::{net::IpAddr, str::FromStr};
+
+use crate::packet::{
+    AppPacket, NetworkPacket,
+    direction::TrafficDirection,
+    link::{ArpPacket, ArpType},
+    network::{IpPacket, icmp::IcmpPacket, ip::IpProto},
+    transport::TcpPacket,
+};
+
+use super::expression::{ParseError, parse_net};
+
+// Grammar, similar to the wireshark display filters:
+//
+// expr       := and ( ("or" | "||") and )*
+// and        := not ( ("and" | "&&") not )*
+// not        := ("not" | "!") not | "(" expr ")" | comparison
+// comparison := FIELD [ ("==" | "!=" | "<" | "<=" | ">" | ">=") VALUE ]
+//
+// A field alone is true when the packet has it, like `tcp` or `pid`.
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum TcpFlag {
+    Syn,
+    Ack,
+    Fin,
+    Rst,
+    Psh,
+    Urg,
+    Ece,
+    Cwr,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Field {
+    Pid,
+    Direction,
+    EthSrc,
+    EthDst,
+    EthAddr,
+    Arp,
+    ArpOpcode,
+    ArpSrc,
+    ArpDst,
+    Ip,
+    Ipv4,
+    Ipv6,
+    IpSrc,
+    IpDst,
+    IpAddr,
+    IpTtl,
+    Tcp,
+    Udp,
+    Sctp,
+    Icmp,
+    Icmpv6,
+    // None matches any transport protocol
+    SrcPort(Option<u8>),
+    DstPort(Option<u8>),
+    Port(Option<u8>),
+    TcpSeq,
+    TcpAck,
+    TcpWindow,
+    TcpFlag(TcpFlag),
+    UdpLength,
+    IcmpType,
+    IcmpCode,
+}
+
+const FIELDS: &[(&str, Field)] = &[
+    ("pid", Field::Pid),
+    ("direction", Field::Direction),
+    ("eth.src", Field::EthSrc),
+    ("eth.dst", Field::EthDst),
+    ("eth.addr", Field::EthAddr),
+    ("arp", Field::Arp),
+    ("arp.opcode", Field::ArpOpcode),
+    ("arp.src", Field::ArpSrc),
+    ("arp.dst", Field::ArpDst),
+    ("ip", Field::Ip),
+    ("ipv4", Field::Ipv4),
+    ("ipv6", Field::Ipv6),
+    ("ip.src", Field::IpSrc),
+    ("ip.dst", Field::IpDst),
+    ("ip.addr", Field::IpAddr),
+    ("ip.ttl", Field::IpTtl),
+    ("tcp", Field::Tcp),
+    ("udp", Field::Udp),
+    ("sctp", Field::Sctp),
+    ("icmp", Field::Icmp),
+    ("icmpv6", Field::Icmpv6),
+    ("port", Field::Port(None)),
+    ("tcp.srcport", Field::SrcPort(Some(6))),
+    ("tcp.dstport", Field::DstPort(Some(6))),
+    ("tcp.port", Field::Port(Some(6))),
+    ("udp.srcport", Field::SrcPort(Some(17))),
+    ("udp.dstport", Field::DstPort(Some(17))),
+    ("udp.port", Field::Port(Some(17))),
+    ("sctp.srcport", Field::SrcPort(Some(132))),
+    ("sctp.dstport", Field::DstPort(Some(132))),
+    ("sctp.port", Field::Port(Some(132))),
+    ("tcp.seq", Field::TcpSeq),
+    ("tcp.ack", Field::TcpAck),
+    ("tcp.window", Field::TcpWindow),
+    ("tcp.flags.syn", Field::TcpFlag(TcpFlag::Syn)),
+    ("tcp.flags.ack", Field::TcpFlag(TcpFlag::Ack)),
+    ("tcp.flags.fin", Field::TcpFlag(TcpFlag::Fin)),
+    ("tcp.flags.rst", Field::TcpFlag(TcpFlag::Rst)),
+    ("tcp.flags.psh", Field::TcpFlag(TcpFlag::Psh)),
+    ("tcp.flags.urg", Field::TcpFlag(TcpFlag::Urg)),
+    ("tcp.flags.ece", Field::TcpFlag(TcpFlag::Ece)),
+    ("tcp.flags.cwr", Field::TcpFlag(TcpFlag::Cwr)),
+    ("udp.length", Field::UdpLength),
+    ("icmp.type", Field::IcmpType),
+    ("icmp.code", Field::IcmpCode),
+];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ValueKind {
+    Protocol,
+    Number,
+    Ip,
+    Mac,
+    Direction,
+}
+
+impl Field {
+    fn kind(self) -> ValueKind {
+        match self {
+            Self::Arp
+            | Self::Ip
+            | Self::Ipv4
+            | Self::Ipv6
+            | Self::Tcp
+            | Self::Udp
+            | Self::Sctp
+            | Self::Icmp
+            | Self::Icmpv6 => ValueKind::Protocol,
+            Self::EthSrc | Self::EthDst | Self::EthAddr => ValueKind::Mac,
+            Self::ArpSrc | Self::ArpDst | Self::IpSrc | Self::IpDst | Self::IpAddr => ValueKind::Ip,
+            Self::Direction => ValueKind::Direction,
+            _ => ValueKind::Number,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Value {
+    Present,
+    Number(u64),
+    Ip(IpAddr),
+    Mac([u8; 6]),
+    Direction(TrafficDirection),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Literal {
+    Number(u64),
+    Net(IpAddr, u8),
+    Mac([u8; 6]),
+    Direction(TrafficDirection),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Exists(Field),
+    Compare(Field, Op, Literal),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Op(Op),
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, ParseError> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        let (kind, len) = match (c, next) {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('(', _) => (TokenKind::LParen, 1),
+            (')', _) => (TokenKind::RParen, 1),
+            ('&', Some('&')) => (TokenKind::And, 2),
+            ('|', Some('|')) => (TokenKind::Or, 2),
+            ('=', Some('=')) => (TokenKind::Op(Op::Eq), 2),
+            ('!', Some('=')) => (TokenKind::Op(Op::Ne), 2),
+            ('<', Some('=')) => (TokenKind::Op(Op::Le), 2),
+            ('>', Some('=')) => (TokenKind::Op(Op::Ge), 2),
+            ('<', _) => (TokenKind::Op(Op::Lt), 1),
+            ('>', _) => (TokenKind::Op(Op::Gt), 1),
+            ('!', _) => (TokenKind::Not, 1),
+            ('&' | '|' | '=', _) => {
+                return Err(ParseError {
+                    message: format!("Unexpected `{c}`"),
+                    position: i,
+                });
+            }
+            _ => {
+                let start = i;
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !matches!(chars[i], '(' | ')' | '!' | '&' | '|' | '=' | '<' | '>')
+                {
+                    i += 1;
+                }
+
+                let word: String = chars[start..i].iter().collect();
+                tokens.push(Token {
+                    kind: match word.to_lowercase().as_str() {
+                        "and" => TokenKind::And,
+                        "or" => TokenKind::Or,
+                        "not" => TokenKind::Not,
+                        "eq" => TokenKind::Op(Op::Eq),
+                        "ne" => TokenKind::Op(Op::Ne),
+                        "lt" => TokenKind::Op(Op::Lt),
+                        "le" => TokenKind::Op(Op::Le),
+                        "gt" => TokenKind::Op(Op::Gt),
+                        "ge" => TokenKind::Op(Op::Ge),
+                        _ => TokenKind::Word(word),
+                    },
+                    position: start,
+                });
+                continue;
+            }
+        };
+
+        tokens.push(Token { kind, position: i });
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.index).map(|token| &token.kind)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map(|token| token.position)
+            .unwrap_or(self.end)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError {
+            message: message.into(),
+            position: self.position(),
+        })
+    }
+
+    fn parse(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.parse_or()?;
+        if self.peek().is_some() {
+            return self.error("Unexpected token");
+        }
+        Ok(expr)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&TokenKind::Or) {
+            self.index += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_not()?;
+        while self.peek() == Some(&TokenKind::And) {
+            self.index += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Some(TokenKind::Not) => {
+                self.index += 1;
+                Ok(Expr::Not(Box::new(self.parse_not()?)))
+            }
+            Some(TokenKind::LParen) => {
+                self.index += 1;
+                let expr = self.parse_or()?;
+                if self.peek() != Some(&TokenKind::RParen) {
+                    return self.error("Missing closing parenthesis");
+                }
+                self.index += 1;
+                Ok(expr)
+            }
+            Some(TokenKind::Word(_)) => self.parse_comparison(),
+            Some(_) => self.error("Unexpected token"),
+            None => self.error("Unexpected end of the expression"),
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, ParseError> {
+        let position = self.position();
+        let Some(TokenKind::Word(name)) = self.peek() else {
+            return self.error("Expected a field");
+        };
+
+        let name = name.to_lowercase();
+        let Some((_, field)) = FIELDS.iter().find(|(n, _)| *n == name) else {
+            return self.error(format!("Unknown field `{name}`"));
+        };
+        self.index += 1;
+
+        let Some(TokenKind::Op(op)) = self.peek() else {
+            return Ok(Expr::Exists(*field));
+        };
+        let op = *op;
+
+        let kind = field.kind();
+        if kind == ValueKind::Protocol {
+            return Err(ParseError {
+                message: format!("`{name}` can not be compared, use it alone"),
+                position,
+            });
+        }
+
+        if kind != ValueKind::Number && !matches!(op, Op::Eq | Op::Ne) {
+            return self.error(format!("`{name}` only supports `==` and `!=`"));
+        }
+        self.index += 1;
+
+        let position = self.position();
+        let Some(TokenKind::Word(value)) = self.peek() else {
+            return self.error("Expected a value");
+        };
+
+        let literal = match kind {
+            ValueKind::Number => parse_number(value).map(Literal::Number),
+            ValueKind::Ip => parse_net(value).map(|(addr, prefix)| Literal::Net(addr, prefix)),
+            ValueKind::Mac => parse_mac(value).map(Literal::Mac),
+            ValueKind::Direction => TrafficDirection::from_str(&value.to_lowercase())
+                .ok()
+                .map(Literal::Direction),
+            ValueKind::Protocol => None,
+        };
+
+        let Some(literal) = literal else {
+            return Err(ParseError {
+                message: format!("Invalid value `{value}` for `{name}`"),
+                position,
+            });
+        };
+        self.index += 1;
+
+        Ok(Expr::Compare(*field, op, literal))
+    }
+}
+
+fn parse_number(value: &str) -> Option<u64> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+fn parse_mac(value: &str) -> Option<[u8; 6]> {
+    let mut mac = [0u8; 6];
+    let mut parts = value.split([':', '-']);
+    for byte in mac.iter_mut() {
+        *byte = u8::from_str_radix(parts.next()?, 16).ok()?;
+    }
+    parts.next().is_none().then_some(mac)
+}
+
+fn is_in_net(addr: IpAddr, net: IpAddr, prefix: u8) -> bool {
+    match (addr, net) {
+        (IpAddr::V4(addr), IpAddr::V4(net)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            addr.to_bits() & mask == net.to_bits() & mask
+        }
+        (IpAddr::V6(addr), IpAddr::V6(net)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            addr.to_bits() & mask == net.to_bits() & mask
+        }
+        _ => false,
+    }
+}
+
+fn tcp_flag(p: &TcpPacket, flag: TcpFlag) -> u16 {
+    match flag {
+        TcpFlag::Syn => p.syn,
+        TcpFlag::Ack => p.ack,
+        TcpFlag::Fin => p.fin,
+        TcpFlag::Rst => p.rst,
+        TcpFlag::Psh => p.psh,
+        TcpFlag::Urg => p.urg,
+        TcpFlag::Ece => p.ece,
+        TcpFlag::Cwr => p.cwr,
+    }
+}
+
+fn ports(proto: &IpProto) -> Option<(u8, u16, u16)> {
+    match proto {
+        IpProto::Tcp(p) => Some((6, p.src_port, p.dst_port)),
+        IpProto::Udp(p) => Some((17, p.src_port, p.dst_port)),
+        IpProto::Sctp(p) => Some((132, p.src_port, p.dst_port)),
+        IpProto::Icmp(_) => None,
+    }
+}
+
+// The values of a field in the packet, fields like `ip.addr` have two of them
+fn field_values(field: Field, app_packet: &AppPacket) -> [Option<Value>; 2] {
+    let present = |condition: bool| [condition.then_some(Value::Present), None];
+    let number = |value: Option<u64>| [value.map(Value::Number), None];
+
+    let header = &app_packet.frame.header;
+
+    let (ip, arp): (Option<(IpAddr, IpAddr, u8, IpProto)>, Option<&ArpPacket>) =
+        match &app_packet.frame.payload {
+            NetworkPacket::Ip(IpPacket::V4(p)) => (
+                Some((p.src_ip.into(), p.dst_ip.into(), p.ttl, p.proto)),
+                None,
+            ),
+            NetworkPacket::Ip(IpPacket::V6(p)) => (
+                Some((p.src_ip.into(), p.dst_ip.into(), p.hop_limit, p.proto)),
+                None,
+            ),
+            NetworkPacket::Arp(p) => (None, Some(p)),
+        };
+
+    let proto = ip.map(|(_, _, _, proto)| proto);
+    let tcp = match proto {
+        Some(IpProto::Tcp(p)) => Some(p),
+        _ => None,
+    };
+    let ports_of = |filter: Option<u8>| {
+        proto
+            .as_ref()
+            .and_then(ports)
+            .filter(|(number, _, _)| filter.is_none_or(|filter| filter == *number))
+    };
+
+    match field {
+        Field::Pid => number(app_packet.pid.map(|pid| pid as u64)),
+        Field::Direction => [Some(Value::Direction(app_packet.direction)), None],
+        Field::EthSrc => [Some(Value::Mac(header.src_addr)), None],
+        Field::EthDst => [Some(Value::Mac(header.dst_addr)), None],
+        Field::EthAddr => [
+            Some(Value::Mac(header.src_addr)),
+            Some(Value::Mac(header.dst_addr)),
+        ],
+        Field::Arp => present(arp.is_some()),
+        Field::ArpOpcode => number(arp.map(|p| match p.arp_type {
+            ArpType::Request => 1,
+            ArpType::Reply => 2,
+        })),
+        Field::ArpSrc => [arp.map(|p| Value::Ip(p.src_ip.into())), None],
+        Field::ArpDst => [arp.map(|p| Value::Ip(p.dst_ip.into())), None],
+        Field::Ip => present(ip.is_some()),
+        Field::Ipv4 => present(matches!(
+            app_packet.frame.payload,
+            NetworkPacket::Ip(IpPacket::V4(_))
+        )),
+        Field::Ipv6 => present(matches!(
+            app_packet.frame.payload,
+            NetworkPacket::Ip(IpPacket::V6(_))
+        )),
+        Field::IpSrc => [ip.map(|(src, _, _, _)| Value::Ip(src)), None],
+        Field::IpDst => [ip.map(|(_, dst, _, _)| Value::Ip(dst)), None],
+        Field::IpAddr => [
+            ip.map(|(src, _, _, _)| Value::Ip(src)),
+            ip.map(|(_, dst, _, _)| Value::Ip(dst)),
+        ],
+        Field::IpTtl => number(ip.map(|(_, _, ttl, _)| ttl as u64)),
+        Field::Tcp => present(matches!(proto, Some(IpProto::Tcp(_)))),
+        Field::Udp => present(matches!(proto, Some(IpProto::Udp(_)))),
+        Field::Sctp => present(matches!(proto, Some(IpProto::Sctp(_)))),
+        Field::Icmp => present(matches!(proto, Some(IpProto::Icmp(IcmpPacket::V4(_))))),
+        Field::Icmpv6 => present(matches!(proto, Some(IpProto::Icmp(IcmpPacket::V6(_))))),
+        Field::SrcPort(filter) => number(ports_of(filter).map(|(_, src, _)| src as u64)),
+        Field::DstPort(filter) => number(ports_of(filter).map(|(_, _, dst)| dst as u64)),
+        Field::Port(filter) => [
+            ports_of(filter).map(|(_, src, _)| Value::Number(src as u64)),
+            ports_of(filter).map(|(_, _, dst)| Value::Number(dst as u64)),
+        ],
+        Field::TcpSeq => number(tcp.map(|p| p.seq as u64)),
+        Field::TcpAck => number(tcp.map(|p| p.ack_seq as u64)),
+        Field::TcpWindow => number(tcp.map(|p| p.window as u64)),
+        Field::TcpFlag(flag) => number(tcp.map(|p| tcp_flag(&p, flag) as u64)),
+        Field::UdpLength => number(match proto {
+            Some(IpProto::Udp(p)) => Some(p.length as u64),
+            _ => None,
+        }),
+        Field::IcmpType => number(match proto {
+            Some(IpProto::Icmp(IcmpPacket::V4(p))) => Some(u8::from(p.icmp_type) as u64),
+            Some(IpProto::Icmp(IcmpPacket::V6(p))) => Some(p.icmp_type as u8 as u64),
+            _ => None,
+        }),
+        Field::IcmpCode => number(match proto {
+            Some(IpProto::Icmp(IcmpPacket::V4(p))) => Some(p.code as u64),
+            Some(IpProto::Icmp(IcmpPacket::V6(p))) => Some(p.code as u64),
+            _ => None,
+        }),
+    }
+}
+
+fn compare(value: Value, op: Op, literal: Literal) -> bool {
+    match (value, literal) {
+        (Value::Number(value), Literal::Number(literal)) => match op {
+            Op::Eq => value == literal,
+            Op::Ne => value != literal,
+            Op::Lt => value < literal,
+            Op::Le => value <= literal,
+            Op::Gt => value > literal,
+            Op::Ge => value >= literal,
+        },
+        (Value::Ip(addr), Literal::Net(net, prefix)) => is_in_net(addr, net, prefix),
+        (Value::Mac(mac), Literal::Mac(literal)) => mac == literal,
+        (Value::Direction(direction), Literal::Direction(literal)) => direction == literal,
+        _ => false,
+    }
+}
+
+impl Expr {
+    fn matches(&self, app_packet: &AppPacket) -> bool {
+        match self {
+            Self::Exists(field) => field_values(*field, app_packet)[0].is_some(),
+            // `ip.addr != 10.0.0.1` excludes the packets where any of the addresses is 10.0.0.1,
+            // and the packets without the field, like `tcp.port != 80` for UDP
+            Self::Compare(field, Op::Ne, literal) => {
+                let mut values = field_values(*field, app_packet)
+                    .into_iter()
+                    .flatten()
+                    .peekable();
+                values.peek().is_some() && values.all(|value| !compare(value, Op::Eq, *literal))
+            }
+            Self::Compare(field, op, literal) => field_values(*field, app_packet)
+                .into_iter()
+                .flatten()
+                .any(|value| compare(value, *op, *literal)),
+            Self::Not(expr) => !expr.matches(app_packet),
+            Self::And(lhs, rhs) => lhs.matches(app_packet) && rhs.matches(app_packet),
+            Self::Or(lhs, rhs) => lhs.matches(app_packet) || rhs.matches(app_packet),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DisplayFilter {
+    // None matches every packet
+    expr: Option<Expr>,
+}
+
+impl DisplayFilter {
+    pub fn parse(expression: &str) -> Result<Self, ParseError> {
+        let tokens = tokenize(expression)?;
+        if tokens.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let mut parser = Parser {
+            tokens,
+            index: 0,
+            end: expression.chars().count(),
+        };
+
+        Ok(Self {
+            expr: Some(parser.parse()?),
+        })
+    }
+
+    pub fn matches(&self, app_packet: &AppPacket) -> bool {
+        self.expr
+            .as_ref()
+            .is_none_or(|expr| expr.matches(app_packet))
+    }
+}
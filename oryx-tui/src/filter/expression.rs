@@ -0,0 +1,702 @@
+use std::{fmt::Display, net::IpAddr};
+
+use crossterm::event::{Event, KeyEvent};
+use oryx_common::filter::{
+    ConditionKind, FAMILY_ARP, FAMILY_IPV4, FAMILY_IPV6, FilterCondition, MAX_FILTER_CLAUSES,
+    MAX_FILTER_CONDITIONS,
+};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Flex, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::Paragraph,
+};
+use tui_input::{Input, backend::crossterm::EventHandler};
+
+// Grammar, similar to the pcap filters:
+//
+// expr      := and ( ("or" | "||") and )*
+// and       := not ( ("and" | "&&") not )*
+// not       := ("not" | "!") not | "(" expr ")" | primitive
+// primitive := "ip" | "ip6" | "arp" | "tcp" | "udp" | "sctp" | "icmp" | "icmp6"
+//            | [ "src" | "dst" ] ( "host" ADDR | "net" CIDR | "port" NUMBER )
+//
+// A protocol directly followed by a qualifier, like `tcp port 443`, is a shorthand for
+// `tcp and port 443`.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    // Position in chars of the first invalid token
+    pub position: usize,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (position {})", self.message, self.position)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Dir {
+    Any,
+    Src,
+    Dst,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Primitive {
+    Family(u8),
+    Proto(u8),
+    Host(Dir, IpAddr, u8),
+    Port(Dir, u16),
+}
+
+#[derive(Debug)]
+enum Expr {
+    Primitive(Primitive),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+fn tokenize(expression: &str) -> Vec<Token> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        let kind = match (c, next) {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('(', _) => TokenKind::LParen,
+            (')', _) => TokenKind::RParen,
+            ('&', Some('&')) => TokenKind::And,
+            ('|', Some('|')) => TokenKind::Or,
+            ('!', _) => TokenKind::Not,
+            _ => {
+                let start = i;
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !matches!(chars[i], '(' | ')' | '!' | '&' | '|')
+                {
+                    i += 1;
+                }
+
+                // lone `&` or `|`
+                if i == start {
+                    i += 1;
+                }
+
+                let word: String = chars[start..i].iter().collect();
+                tokens.push(Token {
+                    kind: match word.to_lowercase().as_str() {
+                        "and" => TokenKind::And,
+                        "or" => TokenKind::Or,
+                        "not" => TokenKind::Not,
+                        _ => TokenKind::Word(word),
+                    },
+                    position: start,
+                });
+                continue;
+            }
+        };
+
+        let len = if matches!(kind, TokenKind::And | TokenKind::Or) {
+            2
+        } else {
+            1
+        };
+
+        tokens.push(Token { kind, position: i });
+        i += len;
+    }
+
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.index).map(|token| &token.kind)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map(|token| token.position)
+            .unwrap_or(self.end)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError {
+            message: message.into(),
+            position: self.position(),
+        })
+    }
+
+    fn word(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(TokenKind::Word(word)) => {
+                let word = word.to_lowercase();
+                self.index += 1;
+                Some(word)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.parse_or()?;
+        if self.peek().is_some() {
+            return self.error("Unexpected token");
+        }
+        Ok(expr)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&TokenKind::Or) {
+            self.index += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_not()?;
+        while self.peek() == Some(&TokenKind::And) {
+            self.index += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Some(TokenKind::Not) => {
+                self.index += 1;
+                Ok(Expr::Not(Box::new(self.parse_not()?)))
+            }
+            Some(TokenKind::LParen) => {
+                self.index += 1;
+                let expr = self.parse_or()?;
+                if self.peek() != Some(&TokenKind::RParen) {
+                    return self.error("Missing closing parenthesis");
+                }
+                self.index += 1;
+                Ok(expr)
+            }
+            Some(TokenKind::Word(_)) => self.parse_primitive(),
+            Some(_) => self.error("Unexpected token"),
+            None => self.error("Unexpected end of the expression"),
+        }
+    }
+
+    fn parse_primitive(&mut self) -> Result<Expr, ParseError> {
+        let position = self.position();
+        let word = self.word().unwrap_or_default();
+
+        let protocol = match word.as_str() {
+            "ip" => Some(Primitive::Family(FAMILY_IPV4)),
+            "ip6" => Some(Primitive::Family(FAMILY_IPV6)),
+            "arp" => Some(Primitive::Family(FAMILY_ARP)),
+            "tcp" => Some(Primitive::Proto(6)),
+            "udp" => Some(Primitive::Proto(17)),
+            "sctp" => Some(Primitive::Proto(132)),
+            "icmp" => Some(Primitive::Proto(1)),
+            "icmp6" => Some(Primitive::Proto(58)),
+            _ => None,
+        };
+
+        if let Some(protocol) = protocol {
+            let expr = Expr::Primitive(protocol);
+            // `tcp port 443`
+            if let Some(TokenKind::Word(next)) = self.peek()
+                && matches!(
+                    next.to_lowercase().as_str(),
+                    "src" | "dst" | "host" | "net" | "port"
+                )
+            {
+                let qualifier = self.parse_primitive()?;
+                return Ok(Expr::And(Box::new(expr), Box::new(qualifier)));
+            }
+            return Ok(expr);
+        }
+
+        let (dir, word) = match word.as_str() {
+            "src" => (Dir::Src, self.word()),
+            "dst" => (Dir::Dst, self.word()),
+            _ => (Dir::Any, Some(word)),
+        };
+
+        match word.as_deref() {
+            Some("host") => {
+                let position = self.position();
+                let Some(value) = self.word() else {
+                    return self.error("Expected an ip address");
+                };
+                match value.parse::<IpAddr>() {
+                    Ok(addr) => {
+                        let prefix = if addr.is_ipv4() { 32 } else { 128 };
+                        Ok(Expr::Primitive(Primitive::Host(dir, addr, prefix)))
+                    }
+                    Err(_) => Err(ParseError {
+                        message: format!("Invalid ip address `{value}`"),
+                        position,
+                    }),
+                }
+            }
+            Some("net") => {
+                let position = self.position();
+                let Some(value) = self.word() else {
+                    return self.error("Expected a network like 10.0.0.0/8");
+                };
+                match parse_net(&value) {
+                    Some((addr, prefix)) => Ok(Expr::Primitive(Primitive::Host(dir, addr, prefix))),
+                    None => Err(ParseError {
+                        message: format!("Invalid network `{value}`"),
+                        position,
+                    }),
+                }
+            }
+            Some("port") => {
+                let position = self.position();
+                let Some(value) = self.word() else {
+                    return self.error("Expected a port number");
+                };
+                match value.parse::<u16>() {
+                    Ok(port) if port > 0 => Ok(Expr::Primitive(Primitive::Port(dir, port))),
+                    _ => Err(ParseError {
+                        message: format!("Invalid port `{value}`"),
+                        position,
+                    }),
+                }
+            }
+            Some(other) if dir == Dir::Any => Err(ParseError {
+                message: format!("Unknown primitive `{other}`"),
+                position,
+            }),
+            _ => self.error("Expected `host`, `net` or `port`"),
+        }
+    }
+}
+
+pub fn parse_net(value: &str) -> Option<(IpAddr, u8)> {
+    let (addr, prefix) = match value.split_once('/') {
+        Some((addr, prefix)) => (
+            addr.parse::<IpAddr>().ok()?,
+            Some(prefix.parse::<u8>().ok()?),
+        ),
+        None => (value.parse::<IpAddr>().ok()?, None),
+    };
+
+    let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max_prefix);
+
+    (prefix <= max_prefix).then_some((addr, prefix))
+}
+
+type Clause = Vec<(Primitive, bool)>;
+
+// Pushes the negations down to the primitives, then distributes the conjunctions
+fn to_dnf(expr: &Expr, negate: bool) -> Result<Vec<Clause>, String> {
+    let clauses = match (expr, negate) {
+        (Expr::Primitive(primitive), _) => vec![vec![(*primitive, negate)]],
+        (Expr::Not(expr), _) => to_dnf(expr, !negate)?,
+        (Expr::Or(lhs, rhs), false) | (Expr::And(lhs, rhs), true) => {
+            let mut clauses = to_dnf(lhs, negate)?;
+            clauses.extend(to_dnf(rhs, negate)?);
+            clauses
+        }
+        (Expr::And(lhs, rhs), false) | (Expr::Or(lhs, rhs), true) => {
+            let lhs = to_dnf(lhs, negate)?;
+            let rhs = to_dnf(rhs, negate)?;
+            let mut clauses = Vec::with_capacity(lhs.len() * rhs.len());
+            for l in &lhs {
+                for r in &rhs {
+                    clauses.push(l.iter().chain(r.iter()).copied().collect());
+                }
+            }
+            clauses
+        }
+    };
+
+    if clauses.len() > MAX_FILTER_CLAUSES as usize {
+        return Err(format!(
+            "The expression is too complex, it expands to more than {MAX_FILTER_CLAUSES} alternatives"
+        ));
+    }
+
+    if clauses
+        .iter()
+        .any(|clause| clause.len() > MAX_FILTER_CONDITIONS as usize)
+    {
+        return Err(format!(
+            "The expression is too complex, more than {MAX_FILTER_CONDITIONS} conditions are combined"
+        ));
+    }
+
+    Ok(clauses)
+}
+
+fn to_condition(primitive: Primitive, negate: bool) -> FilterCondition {
+    let mut condition = FilterCondition {
+        negate: negate as u8,
+        ..Default::default()
+    };
+
+    match primitive {
+        Primitive::Family(family) => {
+            condition.kind = ConditionKind::Family;
+            condition.value = family;
+        }
+        Primitive::Proto(proto) => {
+            condition.kind = ConditionKind::Proto;
+            condition.value = proto;
+        }
+        Primitive::Host(dir, addr, prefix) => {
+            condition.kind = match dir {
+                Dir::Any => ConditionKind::Host,
+                Dir::Src => ConditionKind::SrcHost,
+                Dir::Dst => ConditionKind::DstHost,
+            };
+
+            let (bits, mask) = match addr {
+                IpAddr::V4(addr) => {
+                    let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                    (addr.to_bits() as u128, mask as u128)
+                }
+                IpAddr::V6(addr) => {
+                    let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                    (addr.to_bits(), mask)
+                }
+            };
+
+            condition.value = if addr.is_ipv4() {
+                FAMILY_IPV4
+            } else {
+                FAMILY_IPV6
+            };
+            condition.addr = [((bits & mask) >> 64) as u64, (bits & mask) as u64];
+            condition.mask = [(mask >> 64) as u64, mask as u64];
+        }
+        Primitive::Port(dir, port) => {
+            condition.kind = match dir {
+                Dir::Any => ConditionKind::Port,
+                Dir::Src => ConditionKind::SrcPort,
+                Dir::Dst => ConditionKind::DstPort,
+            };
+            condition.port = port;
+        }
+    }
+
+    condition
+}
+
+// Returns the content of the CAPTURE_FILTER map, an empty expression matches everything
+pub fn compile(expression: &str) -> Result<Vec<FilterCondition>, ParseError> {
+    let mut conditions =
+        vec![FilterCondition::default(); (MAX_FILTER_CLAUSES * MAX_FILTER_CONDITIONS) as usize];
+
+    let tokens = tokenize(expression);
+    if tokens.is_empty() {
+        return Ok(conditions);
+    }
+
+    let mut parser = Parser {
+        tokens,
+        index: 0,
+        end: expression.chars().count(),
+    };
+
+    let expr = parser.parse()?;
+
+    let clauses = to_dnf(&expr, false).map_err(|message| ParseError {
+        message,
+        position: 0,
+    })?;
+
+    for (i, clause) in clauses.iter().enumerate() {
+        for (j, (primitive, negate)) in clause.iter().enumerate() {
+            conditions[i * MAX_FILTER_CONDITIONS as usize + j] = to_condition(*primitive, *negate);
+        }
+    }
+
+    Ok(conditions)
+}
+
+#[derive(Debug)]
+pub struct ExpressionFilter {
+    pub input: Input,
+    pub error: Option<ParseError>,
+    pub applied_expression: String,
+    pub applied_conditions: Vec<FilterCondition>,
+}
+
+impl ExpressionFilter {
+    pub fn new(expression: String) -> Self {
+        let error = compile(&expression).err();
+        Self {
+            input: Input::new(expression),
+            error,
+            applied_expression: String::new(),
+            applied_conditions: compile("").unwrap(),
+        }
+    }
+
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) {
+        self.input.handle_event(&Event::Key(key_event));
+        self.error = compile(self.input.value()).err();
+    }
+
+    pub fn trigger(&mut self) {
+        self.input = Input::new(self.applied_expression.clone());
+        self.error = None;
+    }
+
+    pub fn apply(&mut self) {
+        if let Ok(conditions) = compile(self.input.value()) {
+            self.applied_expression = self.input.value().trim().to_string();
+            self.applied_conditions = conditions;
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, block: Rect, is_focused: bool, update: bool) {
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(25),
+                Constraint::Length(if update { 20 } else { 55 }),
+                Constraint::Fill(1),
+            ])
+            .flex(Flex::Center)
+            .split(block);
+
+        let title = if is_focused {
+            Text::from("Capture Filter 󱪤  ").bold()
+        } else {
+            Text::from("Capture Filter 󱪤  ")
+        };
+        frame.render_widget(title, layout[1]);
+
+        let (input_block, error_block) = {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Length(1)])
+                .split(layout[2]);
+            (chunks[0], chunks[1])
+        };
+
+        let value = self.input.value();
+        let scroll = self.input.visual_scroll(input_block.width as usize);
+
+        let line = match &self.error {
+            // Highlight from the first invalid token
+            Some(error) if !value.is_empty() => {
+                let (valid, invalid): (String, String) = (
+                    value.chars().take(error.position).collect(),
+                    value.chars().skip(error.position).collect(),
+                );
+                Line::from(vec![
+                    Span::from(valid),
+                    Span::styled(invalid, Style::default().red().underlined()),
+                ])
+            }
+            _ if value.is_empty() && !is_focused => Line::from("tcp and dst port 443").dark_gray(),
+            _ => Line::from(value),
+        };
+
+        frame.render_widget(Paragraph::new(line).scroll((0, scroll as u16)), input_block);
+
+        if let Some(error) = &self.error {
+            frame.render_widget(
+                Paragraph::new(Line::from(error.message.clone()).red()),
+                error_block,
+            );
+        }
+
+        if is_focused {
+            let cursor = self.input.visual_cursor().saturating_sub(scroll) as u16;
+            frame.set_cursor_position((input_block.x + cursor, input_block.y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Conditions of each clause, up to the first `None` kind
+    fn clauses(conditions: &[FilterCondition]) -> Vec<Vec<FilterCondition>> {
+        conditions
+            .chunks(MAX_FILTER_CONDITIONS as usize)
+            .map(|clause| {
+                clause
+                    .iter()
+                    .take_while(|condition| condition.kind != ConditionKind::None)
+                    .copied()
+                    .collect::<Vec<_>>()
+            })
+            .filter(|clause| !clause.is_empty())
+            .collect()
+    }
+
+    fn kinds(clause: &[FilterCondition]) -> Vec<(ConditionKind, bool)> {
+        clause
+            .iter()
+            .map(|condition| (condition.kind, condition.negate == 1))
+            .collect()
+    }
+
+    #[test]
+    fn empty_expression() {
+        let conditions = compile("  ").unwrap();
+        assert_eq!(
+            conditions.len(),
+            (MAX_FILTER_CLAUSES * MAX_FILTER_CONDITIONS) as usize
+        );
+        assert!(clauses(&conditions).is_empty());
+    }
+
+    #[test]
+    fn protocol_shorthand() {
+        let clauses = clauses(&compile("tcp port 443").unwrap());
+        assert_eq!(clauses.len(), 1);
+        assert_eq!(
+            kinds(&clauses[0]),
+            vec![(ConditionKind::Proto, false), (ConditionKind::Port, false)]
+        );
+        assert_eq!(clauses[0][0].value, 6);
+        assert_eq!(clauses[0][1].port, 443);
+    }
+
+    #[test]
+    fn disjunction() {
+        let clauses = clauses(&compile("tcp or udp || icmp").unwrap());
+        let protocols: Vec<u8> = clauses.iter().map(|clause| clause[0].value).collect();
+        assert_eq!(protocols, vec![6, 17, 1]);
+    }
+
+    #[test]
+    fn negation_is_pushed_down() {
+        // not (a and b) == not a or not b
+        let clauses = clauses(&compile("not (tcp and dst port 22)").unwrap());
+        assert_eq!(clauses.len(), 2);
+        assert_eq!(kinds(&clauses[0]), vec![(ConditionKind::Proto, true)]);
+        assert_eq!(kinds(&clauses[1]), vec![(ConditionKind::DstPort, true)]);
+
+        // not (a or b) == not a and not b
+        let clauses = clauses(&compile("!(tcp || udp)").unwrap());
+        assert_eq!(clauses.len(), 1);
+        assert_eq!(
+            kinds(&clauses[0]),
+            vec![(ConditionKind::Proto, true), (ConditionKind::Proto, true)]
+        );
+
+        let clauses = clauses(&compile("not not arp").unwrap());
+        assert_eq!(kinds(&clauses[0]), vec![(ConditionKind::Family, false)]);
+    }
+
+    #[test]
+    fn conjunction_is_distributed() {
+        let clauses = clauses(&compile("(tcp or udp) and (port 53 or port 443)").unwrap());
+        let expanded: Vec<(u8, u16)> = clauses
+            .iter()
+            .map(|clause| (clause[0].value, clause[1].port))
+            .collect();
+        assert_eq!(expanded, vec![(6, 53), (6, 443), (17, 53), (17, 443)]);
+    }
+
+    #[test]
+    fn masked_network() {
+        let clauses = clauses(&compile("src net 10.1.2.3/8").unwrap());
+        let condition = clauses[0][0];
+        assert_eq!(condition.kind, ConditionKind::SrcHost);
+        assert_eq!(condition.value, FAMILY_IPV4);
+        assert_eq!(condition.addr, [0, 0x0A00_0000]);
+        assert_eq!(condition.mask, [0, 0xFF00_0000]);
+
+        let clauses = clauses(&compile("host ::1").unwrap());
+        let condition = clauses[0][0];
+        assert_eq!(condition.kind, ConditionKind::Host);
+        assert_eq!(condition.value, FAMILY_IPV6);
+        assert_eq!(condition.addr, [0, 1]);
+        assert_eq!(condition.mask, [u64::MAX, u64::MAX]);
+
+        let clauses = clauses(&compile("net 0.0.0.0/0").unwrap());
+        assert_eq!(clauses[0][0].mask, [0, 0]);
+    }
+
+    #[test]
+    fn clauses_limit() {
+        let expression = (1..=MAX_FILTER_CLAUSES)
+            .map(|port| format!("port {port}"))
+            .collect::<Vec<_>>()
+            .join(" or ");
+        assert_eq!(
+            clauses(&compile(&expression).unwrap()).len(),
+            MAX_FILTER_CLAUSES as usize
+        );
+
+        // 2^4 alternatives
+        let error = compile(
+            "(tcp or udp) and (port 1 or port 2) and (port 3 or port 4) and (port 5 or port 6)",
+        )
+        .unwrap_err();
+        assert!(error.message.contains("alternatives"));
+    }
+
+    #[test]
+    fn conditions_limit() {
+        let expression = (1..=MAX_FILTER_CONDITIONS)
+            .map(|port| format!("port {port}"))
+            .collect::<Vec<_>>()
+            .join(" and ");
+        let clauses = clauses(&compile(&expression).unwrap());
+        assert_eq!(clauses[0].len(), MAX_FILTER_CONDITIONS as usize);
+
+        let error = compile(&format!("{expression} and tcp")).unwrap_err();
+        assert!(error.message.contains("conditions"));
+    }
+
+    #[test]
+    fn error_position() {
+        let error = compile("tcp and foo").unwrap_err();
+        assert_eq!(error.position, 8);
+
+        let error = compile("port 70000").unwrap_err();
+        assert_eq!(error.position, 5);
+
+        assert!(compile("net 10.0.0.0/33").is_err());
+        assert!(compile("(tcp").is_err());
+    }
+}
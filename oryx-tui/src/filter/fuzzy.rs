@@ -4,20 +4,22 @@ use std::{
     time::Duration,
 };
 
-use ratatui::{
-    style::{Style, Stylize},
-    text::{Line, Span},
-    widgets::{Cell, TableState},
-};
+use ratatui::widgets::TableState;
 use tui_input::Input;
 
 use crate::{app::TICK_RATE, packet::AppPacket, packet_store::PacketStore};
 
+use super::{display::DisplayFilter, expression::ParseError};
+
 #[derive(Debug, Clone, Default)]
 pub struct Fuzzy {
     enabled: bool,
     paused: bool,
     pub filter: Input,
+    pub error: Option<ParseError>,
+    // Expression the display filter was parsed from
+    pattern: String,
+    display_filter: DisplayFilter,
     pub packets: Vec<AppPacket>,
     pub scroll_state: TableState,
     pub packet_end_index: usize,
@@ -32,17 +34,26 @@ impl Fuzzy {
             let packets = packets.clone();
             move || {
                 let mut last_index = 0;
-                let mut pattern = String::new();
                 loop {
                     thread::sleep(Duration::from_millis(TICK_RATE));
                     let mut fuzzy = fuzzy.lock().unwrap();
 
                     if fuzzy.is_enabled() && !fuzzy.filter.value().is_empty() {
                         let current_pattern = fuzzy.filter.value().to_owned();
-                        if current_pattern != pattern {
-                            last_index += fuzzy.find(&packets);
-                            pattern = current_pattern;
-                        } else {
+                        if current_pattern != fuzzy.pattern {
+                            match DisplayFilter::parse(&current_pattern) {
+                                Ok(display_filter) => {
+                                    fuzzy.error = None;
+                                    fuzzy.display_filter = display_filter;
+                                    last_index = fuzzy.find(&packets);
+                                }
+                                Err(e) => {
+                                    fuzzy.error = Some(e);
+                                    fuzzy.packets.clear();
+                                }
+                            }
+                            fuzzy.pattern = current_pattern;
+                        } else if fuzzy.error.is_none() {
                             last_index += fuzzy.append(&packets, last_index);
                         }
                     }
@@ -96,10 +107,7 @@ impl Fuzzy {
         self.packets = Vec::new();
         packets
             .for_each(|p| {
-                if p.frame.payload.to_string().contains(self.filter.value())
-                    || p.pid
-                        .is_some_and(|v| v.to_string().contains(self.filter.value()))
-                {
+                if self.display_filter.matches(p) {
                     self.packets.push(*p);
                 }
                 Ok(())
@@ -111,10 +119,7 @@ impl Fuzzy {
     pub fn append(&mut self, packets: &PacketStore, last_index: usize) -> usize {
         packets
             .for_each_range(last_index.., |p| {
-                if p.frame.payload.to_string().contains(self.filter.value())
-                    | p.pid
-                        .is_some_and(|v| v.to_string().contains(self.filter.value()))
-                {
+                if self.display_filter.matches(p) {
                     self.packets.push(*p);
                 }
                 Ok(())
@@ -146,23 +151,3 @@ impl Fuzzy {
         self.paused
     }
 }
-
-pub fn highlight(pattern: &str, input: String) -> Cell<'_> {
-    if !pattern.is_empty() {
-        if input.contains(pattern) {
-            let splits = input.split(pattern);
-
-            let chunks = splits.into_iter().map(|c| Span::from(c.to_owned()));
-
-            let pattern = Span::styled(pattern.to_string(), Style::new().red().bold());
-
-            let v: Vec<Span> = itertools::intersperse(chunks, pattern).collect();
-
-            Cell::from(Line::from(v).centered())
-        } else {
-            Cell::from(Line::from(input).centered())
-        }
-    } else {
-        Cell::from(Line::from(input).centered())
-    }
-}
@@ -7,6 +7,7 @@ use crate::{
     app::{ActivePopup, App, AppResult},
     event::Event,
     filter::FocusedBlock,
+    notification::{Notification, NotificationLevel},
     section::{FocusedSection, stats::Stats},
 };
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
@@ -18,9 +19,30 @@ pub fn handle_key_events(
 ) -> AppResult<()> {
     // Start Phase
     if !app.start_sniffing {
+        // Typing the capture filter
+        if app.filter.focused_block == FocusedBlock::Expression
+            && !matches!(key_event.code, KeyCode::Enter | KeyCode::Esc)
+            && key_event.modifiers != KeyModifiers::CONTROL
+        {
+            app.filter.handle_key_events(key_event, false);
+            return Ok(());
+        }
+
         match key_event.code {
             KeyCode::Enter => {
-                if app.filter.focused_block == FocusedBlock::Apply {
+                if matches!(
+                    app.filter.focused_block,
+                    FocusedBlock::Apply | FocusedBlock::Expression
+                ) {
+                    if let Some(error) = &app.filter.expression.error {
+                        Notification::send(
+                            format!("Invalid capture filter: {error}"),
+                            NotificationLevel::Error,
+                            event_sender.clone(),
+                        )?;
+                        return Ok(());
+                    }
+
                     app.section.stats = Some(Stats::new(app.app_packets.clone()));
                     app.filter
                         .start(event_sender.clone(), app.app_packets.clone())?;
@@ -74,12 +96,29 @@ pub fn handle_key_events(
                         app.section.metrics.handle_popup_keys(key_event)?;
                         app.is_editing = false;
                     }
+                    ActivePopup::FirewallPolicy => {
+                        app.section
+                            .firewall
+                            .handle_policy_popup_keys(key_event, event_sender.clone())?;
+                    }
                     _ => {}
                 }
             }
             KeyCode::Enter => match popup {
                 ActivePopup::UpdateFilters => {
-                    if app.filter.focused_block == FocusedBlock::Apply {
+                    if matches!(
+                        app.filter.focused_block,
+                        FocusedBlock::Apply | FocusedBlock::Expression
+                    ) {
+                        if let Some(error) = &app.filter.expression.error {
+                            Notification::send(
+                                format!("Invalid capture filter: {error}"),
+                                NotificationLevel::Error,
+                                event_sender.clone(),
+                            )?;
+                            return Ok(());
+                        }
+
                         app.filter.apply();
                         app.filter.sync()?;
                         app.active_popup = None;
@@ -102,6 +141,19 @@ pub fn handle_key_events(
                         app.is_editing = false;
                     }
                 }
+                ActivePopup::ExportCapture => {
+                    app.section.inspection.export(
+                        &app.filter.interface.selected_interface.name,
+                        event_sender.clone(),
+                    )?;
+                    app.active_popup = None;
+                }
+                ActivePopup::FirewallPolicy => {
+                    app.section
+                        .firewall
+                        .handle_policy_popup_keys(key_event, event_sender.clone())?;
+                    app.active_popup = None;
+                }
                 _ => {}
             },
 
@@ -117,6 +169,14 @@ pub fn handle_key_events(
                 ActivePopup::NewMetricExplorer => {
                     app.section.metrics.handle_popup_keys(key_event)?;
                 }
+                ActivePopup::ExportCapture => {
+                    app.section.inspection.handle_export_popup_keys(key_event);
+                }
+                ActivePopup::FirewallPolicy => {
+                    app.section
+                        .firewall
+                        .handle_policy_popup_keys(key_event, event_sender.clone())?;
+                }
                 _ => {}
             },
         }
@@ -140,13 +200,22 @@ pub fn handle_key_events(
         }
 
         KeyCode::Char('f') => {
-            app.active_popup = Some(ActivePopup::UpdateFilters);
-            app.filter.trigger();
+            if app.capture_file.is_some() {
+                Notification::send(
+                    "Filters are not available when reading a capture file",
+                    NotificationLevel::Warning,
+                    event_sender.clone(),
+                )?;
+            } else {
+                app.active_popup = Some(ActivePopup::UpdateFilters);
+                app.filter.trigger();
+            }
         }
 
         KeyCode::Char('r') => {
             if key_event.modifiers == KeyModifiers::CONTROL {
                 app.filter.terminate();
+                app.section.alert.terminate();
                 thread::sleep(Duration::from_millis(150));
                 event_sender.send(Event::Reset)?;
             }
@@ -188,6 +257,53 @@ pub fn handle_key_events(
             }
         }
 
+        KeyCode::Char('g') => {
+            if app.section.focused_section == FocusedSection::Firewall
+                && app
+                    .section
+                    .handle_keys(key_event, event_sender.clone())
+                    .is_ok()
+            {
+                app.is_editing = true;
+                app.active_popup = Some(ActivePopup::NewFirewallRule);
+            }
+        }
+
+        KeyCode::Char('b') | KeyCode::Char('B') => {
+            if app.section.block(key_event, event_sender.clone()).is_ok() {
+                app.is_editing = true;
+                app.active_popup = Some(ActivePopup::NewFirewallRule);
+            }
+        }
+
+        KeyCode::Char('s') => {
+            if app.section.focused_section == FocusedSection::Inspection {
+                if app
+                    .section
+                    .handle_keys(key_event, event_sender.clone())
+                    .is_ok()
+                {
+                    app.active_popup = Some(ActivePopup::ExportCapture);
+                }
+            } else {
+                app.section.handle_keys(key_event, event_sender.clone())?;
+            }
+        }
+
+        KeyCode::Char('p') => {
+            if app.section.focused_section == FocusedSection::Firewall {
+                if app
+                    .section
+                    .handle_keys(key_event, event_sender.clone())
+                    .is_ok()
+                {
+                    app.active_popup = Some(ActivePopup::FirewallPolicy);
+                }
+            } else {
+                app.section.handle_keys(key_event, event_sender.clone())?;
+            }
+        }
+
         KeyCode::Char('i') => {
             if app.section.inspection.can_show_popup() {
                 app.active_popup = Some(ActivePopup::PacketInfos);
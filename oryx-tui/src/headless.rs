@@ -0,0 +1,296 @@
+use std::{
+    io::{self, BufWriter, Write},
+    net::IpAddr,
+    path::Path,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::ArgMatches;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    app::{AppResult, cli_filter},
+    capture_file::CaptureFile,
+    event::Event,
+    filter::IoChannels,
+    packet::{
+        AppPacket, NetworkPacket,
+        link::ArpType,
+        network::{IpPacket, icmp::IcmpPacket, ip::IpProto},
+        transport::TcpPacket,
+    },
+    packet_store::PacketStore,
+    section::firewall::FirewallStats,
+};
+
+// Interval between two reads of the packet store
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static STOP: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn stop(_signal: libc::c_int) {
+    STOP.store(true, Ordering::Relaxed);
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Text,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "text" => Ok(Self::Text),
+            _ => Err(format!("Unknown output format {s}")),
+        }
+    }
+}
+
+pub fn run(cli_args: &ArgMatches) -> AppResult<()> {
+    let output_format = cli_args
+        .get_one::<String>("output")
+        .map(|format| OutputFormat::from_str(format).unwrap())
+        .unwrap_or(OutputFormat::Text);
+
+    let max_packets = cli_args.get_one::<usize>("count").copied();
+    let max_duration = cli_args
+        .get_one::<u64>("duration")
+        .map(|seconds| Duration::from_secs(*seconds));
+
+    let packets = PacketStore::new();
+
+    // The TUI sections are not needed to print the packets
+    let capture_file = match cli_args.get_one::<String>("read") {
+        Some(path) => Some(CaptureFile::read(Path::new(path), &packets)?),
+        None => None,
+    };
+
+    let is_live = capture_file.is_none();
+
+    let mut filter = cli_filter(cli_args, IoChannels::new(), FirewallStats::default());
+
+    if is_live {
+        if cli_args.get_one::<String>("interface").is_none() {
+            return Err("An interface (--interface) or a capture file (--read) is required".into());
+        }
+
+        unsafe {
+            libc::signal(libc::SIGINT, stop as *const () as libc::sighandler_t);
+            libc::signal(libc::SIGTERM, stop as *const () as libc::sighandler_t);
+        }
+
+        let (sender, receiver) = kanal::unbounded();
+
+        // There is no UI to show the notifications
+        thread::spawn(move || {
+            while let Ok(event) = receiver.recv() {
+                if let Event::Notification(notification) = event {
+                    eprintln!("{}", notification.message);
+                }
+            }
+        });
+
+        filter.start(sender, packets.clone())?;
+    }
+
+    let mut stdout = BufWriter::new(io::stdout().lock());
+    let start = Instant::now();
+    let mut index = 0;
+
+    loop {
+        // Checked before reading so the packets received in the meantime are still written
+        let is_done = !is_live
+            || STOP.load(Ordering::Relaxed)
+            || max_duration.is_some_and(|duration| start.elapsed() >= duration);
+
+        let end = match max_packets {
+            Some(max) => packets.len().min(max),
+            None => packets.len(),
+        };
+
+        let written = packets.for_each_range(index..end, |app_packet| {
+            match output_format {
+                OutputFormat::Json => writeln!(stdout, "{}", to_json(app_packet))?,
+                OutputFormat::Text => writeln!(stdout, "{}", to_text(app_packet))?,
+            }
+            Ok(())
+        });
+
+        // stdout closed, e.g. piped to `head`
+        let Ok(written) = written else {
+            break;
+        };
+        index += written;
+
+        if stdout.flush().is_err() {
+            break;
+        }
+
+        // Written packets are not read again
+        packets.discard_archives_before(index);
+        packets.discard_raw_bytes();
+
+        if is_done || max_packets.is_some_and(|max| index >= max) {
+            break;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    if is_live {
+        filter.terminate();
+        thread::sleep(Duration::from_millis(110));
+    }
+
+    Ok(())
+}
+
+fn tcp_flags(p: &TcpPacket) -> String {
+    let mut flags = String::new();
+    for (flag, symbol) in [
+        (p.syn, 'S'),
+        (p.fin, 'F'),
+        (p.psh, 'P'),
+        (p.rst, 'R'),
+        (p.urg, 'U'),
+        (p.ece, 'E'),
+        (p.cwr, 'W'),
+        (p.ack, '.'),
+    ] {
+        if flag == 1 {
+            flags.push(symbol);
+        }
+    }
+    flags
+}
+
+fn to_json(app_packet: &AppPacket) -> Value {
+    let mut packet = Map::new();
+
+    packet.insert("timestamp".into(), json!(app_packet.timestamp.to_rfc3339()));
+    packet.insert(
+        "direction".into(),
+        json!(app_packet.direction.to_string().to_lowercase()),
+    );
+    packet.insert("pid".into(), json!(app_packet.pid));
+
+    let (src_ip, dst_ip, proto): (IpAddr, IpAddr, IpProto) = match app_packet.frame.payload {
+        NetworkPacket::Arp(arp_packet) => {
+            packet.insert("protocol".into(), json!("arp"));
+            packet.insert(
+                "arp_type".into(),
+                json!(match arp_packet.arp_type {
+                    ArpType::Request => "request",
+                    ArpType::Reply => "reply",
+                }),
+            );
+            packet.insert("src_mac".into(), json!(arp_packet.src_mac.to_string()));
+            packet.insert("dst_mac".into(), json!(arp_packet.dst_mac.to_string()));
+            packet.insert("src_ip".into(), json!(arp_packet.src_ip));
+            packet.insert("dst_ip".into(), json!(arp_packet.dst_ip));
+            return Value::Object(packet);
+        }
+        NetworkPacket::Ip(IpPacket::V4(p)) => (p.src_ip.into(), p.dst_ip.into(), p.proto),
+        NetworkPacket::Ip(IpPacket::V6(p)) => (p.src_ip.into(), p.dst_ip.into(), p.proto),
+    };
+
+    packet.insert("src_ip".into(), json!(src_ip));
+    packet.insert("dst_ip".into(), json!(dst_ip));
+
+    match proto {
+        IpProto::Tcp(p) => {
+            packet.insert("protocol".into(), json!("tcp"));
+            packet.insert("src_port".into(), json!(p.src_port));
+            packet.insert("dst_port".into(), json!(p.dst_port));
+            packet.insert("flags".into(), json!(tcp_flags(&p)));
+            packet.insert("seq".into(), json!(p.seq));
+            packet.insert("ack".into(), json!(p.ack_seq));
+            packet.insert("window".into(), json!(p.window));
+        }
+        IpProto::Udp(p) => {
+            packet.insert("protocol".into(), json!("udp"));
+            packet.insert("src_port".into(), json!(p.src_port));
+            packet.insert("dst_port".into(), json!(p.dst_port));
+            packet.insert("length".into(), json!(p.length));
+        }
+        IpProto::Sctp(p) => {
+            packet.insert("protocol".into(), json!("sctp"));
+            packet.insert("src_port".into(), json!(p.src_port));
+            packet.insert("dst_port".into(), json!(p.dst_port));
+        }
+        IpProto::Icmp(IcmpPacket::V4(p)) => {
+            packet.insert("protocol".into(), json!("icmpv4"));
+            packet.insert("icmp_type".into(), json!(p.icmp_type.to_string()));
+            packet.insert("icmp_code".into(), json!(p.code));
+        }
+        IpProto::Icmp(IcmpPacket::V6(p)) => {
+            packet.insert("protocol".into(), json!("icmpv6"));
+            packet.insert("icmp_type".into(), json!(p.icmp_type.to_string()));
+            packet.insert("icmp_code".into(), json!(p.code));
+        }
+    }
+
+    Value::Object(packet)
+}
+
+// Similar to the tcpdump output
+fn to_text(app_packet: &AppPacket) -> String {
+    let prefix = format!(
+        "{} {:7}",
+        app_packet.timestamp.format("%H:%M:%S%.6f"),
+        app_packet.direction.to_string()
+    );
+
+    let suffix = match app_packet.pid {
+        Some(pid) => format!(" (pid {pid})"),
+        None => String::new(),
+    };
+
+    let (family, src_ip, dst_ip, proto): (&str, IpAddr, IpAddr, IpProto) = match app_packet
+        .frame
+        .payload
+    {
+        NetworkPacket::Arp(p) => {
+            let infos = match p.arp_type {
+                ArpType::Request => format!("Request who-has {} tell {}", p.dst_ip, p.src_ip),
+                ArpType::Reply => format!("Reply {} is-at {}", p.src_ip, p.src_mac),
+            };
+            return format!("{prefix} ARP, {infos}{suffix}");
+        }
+        NetworkPacket::Ip(IpPacket::V4(p)) => ("IP", p.src_ip.into(), p.dst_ip.into(), p.proto),
+        NetworkPacket::Ip(IpPacket::V6(p)) => ("IP6", p.src_ip.into(), p.dst_ip.into(), p.proto),
+    };
+
+    let infos = match proto {
+        IpProto::Tcp(p) => format!(
+            "{src_ip}.{} > {dst_ip}.{}: Flags [{}], seq {}, ack {}, win {}",
+            p.src_port,
+            p.dst_port,
+            tcp_flags(&p),
+            p.seq,
+            p.ack_seq,
+            p.window
+        ),
+        IpProto::Udp(p) => format!(
+            "{src_ip}.{} > {dst_ip}.{}: UDP, length {}",
+            p.src_port, p.dst_port, p.length
+        ),
+        IpProto::Sctp(p) => format!("{src_ip}.{} > {dst_ip}.{}: SCTP", p.src_port, p.dst_port),
+        IpProto::Icmp(IcmpPacket::V4(p)) => {
+            format!("{src_ip} > {dst_ip}: ICMP {}, code {}", p.icmp_type, p.code)
+        }
+        IpProto::Icmp(IcmpPacket::V6(p)) => format!(
+            "{src_ip} > {dst_ip}: ICMP6 {}, code {}",
+            p.icmp_type, p.code
+        ),
+    };
+
+    format!("{prefix} {family} {infos}{suffix}")
+}
@@ -42,26 +42,68 @@ impl Help {
                 ),
                 (Cell::from("f").bold(), "Update the applied filters"),
                 (Cell::from("ctrl + r").bold(), "Reset the app"),
-                (
-                    Cell::from("ctrl + s").bold(),
-                    "Export the capture to ~/oryx/capture file",
-                ),
                 (Cell::from(""), ""),
                 (Cell::from("## Inspection").bold().yellow(), ""),
                 (
                     Cell::from("i").bold(),
                     "Show more infos about the selected packet",
                 ),
-                (Cell::from("/").bold(), "Start fuzzy finding"),
+                (Cell::from("/").bold(), "Filter the packets"),
+                (
+                    Cell::from("s").bold(),
+                    "Export the capture to ~/oryx as text or pcapng",
+                ),
+                (
+                    Cell::from("b").bold(),
+                    "Block the remote host of the selected packet",
+                ),
+                (
+                    Cell::from("B").bold(),
+                    "Block the remote host on the destination port of the selected packet",
+                ),
+                (Cell::from(""), ""),
+                (Cell::from("## Flows").bold().yellow(), ""),
+                (Cell::from("h or l").bold(), "Change the sort column"),
+                (Cell::from("s").bold(), "Toggle the sort order"),
+                (
+                    Cell::from("Enter").bold(),
+                    "Inspect the packets of the selected flow",
+                ),
+                (Cell::from(""), ""),
+                (Cell::from("## Stats").bold().yellow(), ""),
+                (Cell::from("b").bold(), "Block the selected top address"),
+                (Cell::from(""), ""),
+                (Cell::from("## Alerts").bold().yellow(), ""),
+                (Cell::from("a").bold(), "Acknowledge the selected incident"),
+                (
+                    Cell::from("d").bold(),
+                    "Dismiss the selected incident from the history",
+                ),
+                (
+                    Cell::from("b").bold(),
+                    "Block the top source of the selected incident",
+                ),
+                (
+                    Cell::from("B").bold(),
+                    "Block all the sources of the history as a rule group",
+                ),
                 (Cell::from(""), ""),
                 (Cell::from("## Firewall").bold().yellow(), ""),
                 (Cell::from("n").bold(), "Add new firewall rule"),
+                (
+                    Cell::from("g").bold(),
+                    "Add a rule on a list of addresses imported from a file",
+                ),
                 (Cell::from("e").bold(), "Edit a firewall rule"),
                 (
                     Cell::from("s").bold(),
                     "Save firewall rules to ~/oryx/firewall.json ",
                 ),
                 (Cell::from("Space").bold(), "Toggle firewall rule status"),
+                (
+                    Cell::from("p").bold(),
+                    "Set the ingress and egress default policies",
+                ),
                 (Cell::from("Enter").bold(), "Create or Save a firewall rule"),
             ],
         }
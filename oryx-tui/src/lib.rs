@@ -31,3 +31,7 @@ pub mod dns;
 pub mod cli;
 
 pub mod packet_store;
+
+pub mod capture_file;
+
+pub mod headless;
@@ -9,6 +9,7 @@ use oryx_tui::{
     cli,
     event::{Event, EventHandler},
     handler::handle_key_events,
+    headless,
     tui::Tui,
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
@@ -18,12 +19,27 @@ fn main() -> AppResult<()> {
 
     let cli_args = cli::cli().get_matches();
 
-    if unsafe { libc::geteuid() } != 0 {
+    // Reading a capture file does not require loading the eBPF programs
+    if !cli_args.contains_id("read") && unsafe { libc::geteuid() } != 0 {
         eprintln!("This program must be run as root");
         std::process::exit(1);
     }
 
-    let mut app = App::new(&cli_args);
+    if cli_args.get_flag("headless") {
+        if let Err(e) = headless::run(&cli_args) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let mut app = match App::new(&cli_args) {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
 
     let backend = CrosstermBackend::new(io::stdout());
     let terminal = Terminal::new(backend)?;
@@ -41,23 +57,31 @@ fn main() -> AppResult<()> {
         )))?;
     }
 
-    while app.running {
-        tui.draw(&mut app)?;
-        match tui.events.next()? {
-            Event::Tick => app.tick(),
-            Event::Key(key_event) => {
-                handle_key_events(key_event, &mut app, tui.events.sender.clone())?
+    let result = (|| -> AppResult<()> {
+        while app.running {
+            tui.draw(&mut app)?;
+            match tui.events.next()? {
+                Event::Tick => app.tick(tui.events.sender.clone())?,
+                Event::Key(key_event) => {
+                    handle_key_events(key_event, &mut app, tui.events.sender.clone())?
+                }
+                Event::Notification(notification) => {
+                    app.notifications.push(notification);
+                }
+                Event::Firewall(event) => app
+                    .section
+                    .firewall
+                    .handle_event(event, tui.events.sender.clone())?,
+                Event::Reset => {
+                    app = App::new(&cli_args)?;
+                }
+                _ => {}
             }
-            Event::Notification(notification) => {
-                app.notifications.push(notification);
-            }
-            Event::Reset => {
-                app = App::new(&cli_args);
-            }
-            _ => {}
         }
-    }
+        Ok(())
+    })();
 
+    // Restores the terminal on errors too
     tui.exit()?;
-    Ok(())
+    result
 }
@@ -14,6 +14,8 @@ use network_types::{eth::EthHdr, icmp::Icmp, ip::IpHdr};
 use oryx_common::{ProtoHdr, RawFrame, RawPacket};
 use transport::{SctpPacket, TcpPacket, UdpPacket};
 
+use crate::packet_store::RawBytesRef;
+
 use crate::packet::network::{
     icmp::icmpv4::Icmpv4Packet, icmp::icmpv6::Icmpv6Packet, ip::ipv4::Ipv4Packet,
     ip::ipv6::Ipv6Packet,
@@ -25,6 +27,8 @@ pub struct AppPacket {
     pub direction: TrafficDirection,
     pub pid: Option<u32>,
     pub timestamp: DateTime<Utc>,
+    // Captured bytes, only available when a snaplen is set
+    pub raw: Option<RawBytesRef>,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -123,7 +123,7 @@ impl Display for ArpType {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct MacAddr(pub [u8; 6]);
 
 impl Display for MacAddr {
@@ -101,6 +101,27 @@ impl From<u8> for IcmpType {
     }
 }
 
+impl From<IcmpType> for u8 {
+    fn from(value: IcmpType) -> Self {
+        match value {
+            IcmpType::EchoReply => 0,
+            IcmpType::DestinationUnreachable => 3,
+            IcmpType::RedirectMessage => 5,
+            IcmpType::EchoRequest => 8,
+            IcmpType::RouterAdvertisement => 9,
+            IcmpType::RouterSolicitation => 10,
+            IcmpType::TimeExceeded => 11,
+            IcmpType::BadIPheader => 12,
+            IcmpType::Timestamp => 13,
+            IcmpType::TimestampReply => 14,
+            IcmpType::ExtendedEchoRequest => 42,
+            IcmpType::ExtendedEchoReply => 43,
+            // Source Quench
+            IcmpType::Deprecated => 4,
+        }
+    }
+}
+
 impl Display for IcmpType {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
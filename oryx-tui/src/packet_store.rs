@@ -12,6 +12,8 @@ const BUFFER_SIZE: usize = 32 * 1024;
 // Stack buffer size max should be as large as possible without causing stack overflow in all operation systems
 // for fallback when read range is too large we switch to thread local buffer with BUFFER_SIZE capacity
 const STACK_BUFFER_SIZE_MAX: usize = 4 * 1024;
+// Raw bytes of the packets are appended to chunks of RAW_BYTES_CHUNK_SIZE
+const RAW_BYTES_CHUNK_SIZE: usize = 4 * 1024 * 1024;
 const STACK_BUFFER_SIZE: usize = if STACK_BUFFER_SIZE_MAX < BUFFER_SIZE {
     STACK_BUFFER_SIZE_MAX
 } else {
@@ -30,6 +32,16 @@ pub struct PacketStoreInner {
     latest: CacheGuard<RwLock<Vec<AppPacket>>>,
     // Old packets stored here in chunks of BUFFER_SIZE
     archives: CacheGuard<RwLock<Vec<Arc<Vec<AppPacket>>>>>,
+    // Captured bytes of the packets when a snaplen is set
+    raw_bytes: CacheGuard<RwLock<Vec<Vec<u8>>>>,
+}
+
+// Location of the captured bytes of a packet in the store
+#[derive(Debug, Copy, Clone)]
+pub struct RawBytesRef {
+    chunk: u32,
+    offset: u32,
+    len: u16,
 }
 
 #[derive(Debug)]
@@ -73,6 +85,7 @@ impl PacketStore {
                 archives: CacheGuard::new(RwLock::new(Vec::new())),
                 archives_token: CacheGuard::new(AtomicUsize::new(0)),
                 length: CacheGuard::new(AtomicUsize::new(0)),
+                raw_bytes: CacheGuard::new(RwLock::new(Vec::new())),
             }),
         }
     }
@@ -95,6 +108,27 @@ impl PacketStore {
         }
     }
 
+    // Frees the archives holding only packets before `index`
+    #[inline]
+    pub fn discard_archives_before(&self, index: usize) {
+        let mut archives = self.archives.write().unwrap();
+        for archive in archives.iter_mut().take(index / BUFFER_SIZE) {
+            if !archive.is_empty() {
+                *archive = Arc::new(Vec::new());
+            }
+        }
+    }
+
+    // Frees the filled chunks of raw bytes, the references to them become invalid
+    #[inline]
+    pub fn discard_raw_bytes(&self) {
+        let mut chunks = self.raw_bytes.write().unwrap();
+        let nb_chunks = chunks.len();
+        for chunk in chunks.iter_mut().take(nb_chunks.saturating_sub(1)) {
+            *chunk = Vec::new();
+        }
+    }
+
     #[inline]
     pub fn write(&self, packet: &AppPacket) {
         let mut latest = self.latest.write().unwrap();
@@ -111,6 +145,40 @@ impl PacketStore {
         self.length.fetch_add(1, Ordering::Relaxed);
     }
 
+    #[inline]
+    pub fn write_raw_bytes(&self, bytes: &[u8]) -> RawBytesRef {
+        let mut chunks = self.raw_bytes.write().unwrap();
+        if unlikely(
+            chunks
+                .last()
+                .is_none_or(|chunk| chunk.len() + bytes.len() > RAW_BYTES_CHUNK_SIZE),
+        ) {
+            chunks.push(Vec::with_capacity(RAW_BYTES_CHUNK_SIZE));
+        }
+
+        let chunk_index = chunks.len() - 1;
+        let chunk = &mut chunks[chunk_index];
+        let offset = chunk.len();
+        chunk.extend_from_slice(bytes);
+
+        RawBytesRef {
+            chunk: chunk_index as u32,
+            offset: offset as u32,
+            len: bytes.len() as u16,
+        }
+    }
+
+    #[inline]
+    pub fn raw_bytes(&self, raw: RawBytesRef) -> Option<Vec<u8>> {
+        let chunks = self.raw_bytes.read().unwrap();
+        let start = raw.offset as usize;
+        let end = start + raw.len as usize;
+        chunks
+            .get(raw.chunk as usize)
+            .and_then(|chunk| chunk.get(start..end))
+            .map(|bytes| bytes.to_vec())
+    }
+
     #[inline]
     pub fn archive_at(&self, index: usize) -> (Option<Arc<Vec<AppPacket>>>, usize) {
         let archive = self.archives.read().unwrap();
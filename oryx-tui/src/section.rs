@@ -1,15 +1,22 @@
 pub mod alert;
 pub mod firewall;
+pub mod flows;
 pub mod inspection;
 pub mod metrics;
+pub mod processes;
 pub mod stats;
 
-use alert::Alert;
+use alert::{Alert, hook::AlertHook, response::AutoBlock};
+use chrono::Local;
 use crossterm::event::{KeyCode, KeyEvent};
-use firewall::{Firewall, FirewallSignal};
+use firewall::{
+    Firewall, FirewallCapacity, FirewallSignal, FirewallStats, draft::RuleDraft, group::RuleGroup,
+};
+use flows::Flows;
 
 use inspection::Inspection;
 use metrics::Metrics;
+use processes::Processes;
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
@@ -22,13 +29,18 @@ use stats::Stats;
 use crate::{
     app::{ActivePopup, AppResult},
     event::Event,
+    export::ExportFormat,
     filter::IoChannels,
+    notification::{Notification, NotificationLevel},
+    packet::direction::TrafficDirection,
     packet_store::PacketStore,
 };
 
 #[derive(Debug, PartialEq)]
 pub enum FocusedSection {
     Inspection,
+    Flows,
+    Processes,
     Stats,
     Metrics,
     Alerts,
@@ -39,6 +51,8 @@ pub enum FocusedSection {
 pub struct Section {
     pub focused_section: FocusedSection,
     pub inspection: Inspection,
+    pub flows: Flows,
+    pub processes: Processes,
     pub stats: Option<Stats>,
     pub metrics: Metrics,
     pub alert: Alert,
@@ -46,14 +60,31 @@ pub struct Section {
 }
 
 impl Section {
-    pub fn new(packets: PacketStore, firewall_chans: IoChannels<FirewallSignal>) -> Self {
+    pub fn new(
+        packets: PacketStore,
+        firewall_chans: IoChannels<FirewallSignal>,
+        firewall_stats: FirewallStats,
+        firewall_capacity: FirewallCapacity,
+        alert_hooks: Vec<AlertHook>,
+        auto_block: Option<AutoBlock>,
+        export_format: ExportFormat,
+        is_offline: bool,
+    ) -> Self {
         Self {
             focused_section: FocusedSection::Inspection,
-            inspection: Inspection::new(packets.clone()),
+            inspection: Inspection::new(packets.clone(), export_format),
+            flows: Flows::new(packets.clone()),
+            processes: Processes::new(packets.clone(), is_offline),
             stats: None,
             metrics: Metrics::new(packets.clone()),
-            alert: Alert::new(packets.clone()),
-            firewall: Firewall::new(firewall_chans.ingress.sender, firewall_chans.egress.sender),
+            alert: Alert::new(packets.clone(), alert_hooks, auto_block, is_offline),
+            firewall: Firewall::new(
+                firewall_chans.ingress.sender,
+                firewall_chans.egress.sender,
+                firewall_stats,
+                firewall_capacity,
+                is_offline,
+            ),
         }
     }
     fn title_span(&self, header_section: FocusedSection) -> Span<'_> {
@@ -69,6 +100,26 @@ impl Section {
                     Span::from("  Inspection 󰏖   ").fg(Color::DarkGray)
                 }
             }
+            FocusedSection::Flows => {
+                if is_focused {
+                    Span::styled(
+                        "  Flows 󰓅   ",
+                        Style::default().bg(Color::Green).fg(Color::White).bold(),
+                    )
+                } else {
+                    Span::from("  Flows 󰓅   ").fg(Color::DarkGray)
+                }
+            }
+            FocusedSection::Processes => {
+                if is_focused {
+                    Span::styled(
+                        "  Processes    ",
+                        Style::default().bg(Color::Green).fg(Color::White).bold(),
+                    )
+                } else {
+                    Span::from("  Processes    ").fg(Color::DarkGray)
+                }
+            }
             FocusedSection::Stats => {
                 if is_focused {
                     Span::styled(
@@ -132,7 +183,7 @@ impl Section {
                 ]),
                 Some(ActivePopup::NewFirewallRule) => Line::from(vec![
                     Span::from("j,k,,").bold(),
-                    Span::from(" Toggle Direction"),
+                    Span::from(" Toggle Direction/Action"),
                     Span::from(" | "),
                     Span::from("󱊷 ").bold(),
                     Span::from(" Discard"),
@@ -150,6 +201,35 @@ impl Section {
                     Span::from("↲").bold(),
                     Span::from(" Run"),
                 ]),
+                Some(ActivePopup::ExportCapture) => Line::from(vec![
+                    Span::from("k,").bold(),
+                    Span::from("  Up"),
+                    Span::from(" | "),
+                    Span::from("j,").bold(),
+                    Span::from("  Down"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Discard"),
+                    Span::from(" | "),
+                    Span::from("↲").bold(),
+                    Span::from(" Export"),
+                ]),
+                Some(ActivePopup::FirewallPolicy) => Line::from(vec![
+                    Span::from("k,").bold(),
+                    Span::from("  Up"),
+                    Span::from(" | "),
+                    Span::from("j,").bold(),
+                    Span::from("  Down"),
+                    Span::from(" | "),
+                    Span::from("󱁐").bold(),
+                    Span::from(" Toggle Policy"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Discard"),
+                    Span::from(" | "),
+                    Span::from("↲").bold(),
+                    Span::from(" Confirm"),
+                ]),
                 Some(ActivePopup::PacketInfos) | Some(ActivePopup::Help) => Line::from(vec![
                     Span::from("󱊷 ").bold(),
                     Span::from(" Discard Popup").bold(),
@@ -163,11 +243,14 @@ impl Section {
                         Span::from("  Down"),
                         Span::from(" | "),
                         Span::from("/").bold(),
-                        Span::from(" Search"),
+                        Span::from(" Filter"),
                         Span::from(" | "),
                         Span::from("i").bold(),
                         Span::from(" Infos"),
                         Span::from(" | "),
+                        Span::from("b,B").bold(),
+                        Span::from(" Block Host/Port"),
+                        Span::from(" | "),
                         Span::from("s").bold(),
                         Span::from(" Save"),
                         Span::from(" | "),
@@ -180,6 +263,28 @@ impl Section {
                         Span::from("⇄").bold(),
                         Span::from(" Nav"),
                     ]),
+                    FocusedSection::Flows => Line::from(vec![
+                        Span::from("k,").bold(),
+                        Span::from("  Up"),
+                        Span::from(" | "),
+                        Span::from("j,").bold(),
+                        Span::from("  Down"),
+                        Span::from(" | "),
+                        Span::from("h,l").bold(),
+                        Span::from(" Sort Column"),
+                        Span::from(" | "),
+                        Span::from("s").bold(),
+                        Span::from(" Sort Order"),
+                        Span::from(" | "),
+                        Span::from("↲").bold(),
+                        Span::from(" Inspect"),
+                        Span::from(" | "),
+                        Span::from("f").bold(),
+                        Span::from(" Filters"),
+                        Span::from(" | "),
+                        Span::from("⇄").bold(),
+                        Span::from(" Nav"),
+                    ]),
                     FocusedSection::Firewall => Line::from(vec![
                         Span::from("k,").bold(),
                         Span::from("  Up"),
@@ -190,6 +295,9 @@ impl Section {
                         Span::from("n").bold(),
                         Span::from(" New"),
                         Span::from(" | "),
+                        Span::from("g").bold(),
+                        Span::from(" Import"),
+                        Span::from(" | "),
                         Span::from("d").bold(),
                         Span::from(" Delete"),
                         Span::from(" | "),
@@ -202,6 +310,22 @@ impl Section {
                         Span::from("󱁐 ").bold(),
                         Span::from(" Toggle"),
                         Span::from(" | "),
+                        Span::from("p").bold(),
+                        Span::from(" Policy"),
+                        Span::from(" | "),
+                        Span::from("f").bold(),
+                        Span::from(" Filters"),
+                        Span::from(" | "),
+                        Span::from("⇄").bold(),
+                        Span::from(" Nav"),
+                    ]),
+                    FocusedSection::Processes => Line::from(vec![
+                        Span::from("k,").bold(),
+                        Span::from("  Up"),
+                        Span::from(" | "),
+                        Span::from("j,").bold(),
+                        Span::from("  Down"),
+                        Span::from(" | "),
                         Span::from("f").bold(),
                         Span::from(" Filters"),
                         Span::from(" | "),
@@ -227,6 +351,47 @@ impl Section {
                         Span::from("⇄").bold(),
                         Span::from(" Nav"),
                     ]),
+                    FocusedSection::Stats => Line::from(vec![
+                        Span::from("k,").bold(),
+                        Span::from("  Up"),
+                        Span::from(" | "),
+                        Span::from("j,").bold(),
+                        Span::from("  Down"),
+                        Span::from(" | "),
+                        Span::from("b").bold(),
+                        Span::from(" Block"),
+                        Span::from(" | "),
+                        Span::from("f").bold(),
+                        Span::from(" Filters"),
+                        Span::from(" | "),
+                        Span::from("⇄").bold(),
+                        Span::from(" Nav"),
+                    ]),
+                    FocusedSection::Alerts => Line::from(vec![
+                        Span::from("k,").bold(),
+                        Span::from("  Up"),
+                        Span::from(" | "),
+                        Span::from("j,").bold(),
+                        Span::from("  Down"),
+                        Span::from(" | "),
+                        Span::from("a").bold(),
+                        Span::from(" Acknowledge"),
+                        Span::from(" | "),
+                        Span::from("d").bold(),
+                        Span::from(" Dismiss"),
+                        Span::from(" | "),
+                        Span::from("b").bold(),
+                        Span::from(" Block"),
+                        Span::from(" | "),
+                        Span::from("B").bold(),
+                        Span::from(" Block All"),
+                        Span::from(" | "),
+                        Span::from("f").bold(),
+                        Span::from(" Filters"),
+                        Span::from(" | "),
+                        Span::from("⇄").bold(),
+                        Span::from(" Nav"),
+                    ]),
                     _ => Line::from(vec![
                         Span::from("f").bold(),
                         Span::from(" Filters"),
@@ -254,6 +419,8 @@ impl Section {
                 .title({
                     Line::from(vec![
                         self.title_span(FocusedSection::Inspection),
+                        self.title_span(FocusedSection::Flows),
+                        self.title_span(FocusedSection::Processes),
                         self.title_span(FocusedSection::Stats),
                         self.title_span(FocusedSection::Metrics),
                         self.title_span(FocusedSection::Alerts),
@@ -292,6 +459,8 @@ impl Section {
 
         match self.focused_section {
             FocusedSection::Inspection => self.inspection.render(frame, section_block),
+            FocusedSection::Flows => self.flows.render(frame, section_block),
+            FocusedSection::Processes => self.processes.render(frame, section_block),
             FocusedSection::Stats => {
                 if let Some(stats) = &self.stats {
                     stats.render(frame, section_block, network_interface)
@@ -310,7 +479,9 @@ impl Section {
     ) -> AppResult<()> {
         match key_event.code {
             KeyCode::Tab => match self.focused_section {
-                FocusedSection::Inspection => self.focused_section = FocusedSection::Stats,
+                FocusedSection::Inspection => self.focused_section = FocusedSection::Flows,
+                FocusedSection::Flows => self.focused_section = FocusedSection::Processes,
+                FocusedSection::Processes => self.focused_section = FocusedSection::Stats,
                 FocusedSection::Stats => self.focused_section = FocusedSection::Metrics,
                 FocusedSection::Metrics => self.focused_section = FocusedSection::Alerts,
                 FocusedSection::Alerts => self.focused_section = FocusedSection::Firewall,
@@ -319,7 +490,9 @@ impl Section {
 
             KeyCode::BackTab => match self.focused_section {
                 FocusedSection::Inspection => self.focused_section = FocusedSection::Firewall,
-                FocusedSection::Stats => self.focused_section = FocusedSection::Inspection,
+                FocusedSection::Flows => self.focused_section = FocusedSection::Inspection,
+                FocusedSection::Processes => self.focused_section = FocusedSection::Flows,
+                FocusedSection::Stats => self.focused_section = FocusedSection::Processes,
                 FocusedSection::Metrics => self.focused_section = FocusedSection::Stats,
                 FocusedSection::Alerts => self.focused_section = FocusedSection::Metrics,
                 FocusedSection::Firewall => self.focused_section = FocusedSection::Alerts,
@@ -332,10 +505,94 @@ impl Section {
                 FocusedSection::Firewall => self
                     .firewall
                     .handle_keys(key_event, notification_sender.clone())?,
+                FocusedSection::Flows => {
+                    if key_event.code == KeyCode::Enter {
+                        // Show the packets of the selected conversation
+                        if let Some(flow) = self.flows.selected_flow() {
+                            self.inspection.filter(flow.display_filter());
+                            self.focused_section = FocusedSection::Inspection;
+                        }
+                    } else {
+                        self.flows.handle_keys(key_event);
+                    }
+                }
+                FocusedSection::Processes => self.processes.handle_keys(key_event),
                 FocusedSection::Metrics => self.metrics.handle_keys(key_event),
-                _ => {}
+                FocusedSection::Stats => {
+                    if let Some(stats) = &mut self.stats {
+                        stats.handle_keys(key_event);
+                    }
+                }
+                FocusedSection::Alerts => self
+                    .alert
+                    .handle_keys(key_event, notification_sender.clone())?,
             },
         }
         Ok(())
     }
+
+    // `b` blocks the selected address, `B` the selected port in Inspection
+    // and all the sources in Alerts, in a prefilled rule popup
+    pub fn block(
+        &mut self,
+        key_event: KeyEvent,
+        notification_sender: kanal::Sender<Event>,
+    ) -> AppResult<()> {
+        let block_all = key_event.code == KeyCode::Char('B');
+
+        let draft = match self.focused_section {
+            FocusedSection::Inspection => self
+                .inspection
+                .selected_packet()
+                .and_then(|packet| RuleDraft::from_packet(&packet, block_all)),
+            FocusedSection::Stats if !block_all => self
+                .stats
+                .as_ref()
+                .and_then(|stats| stats.selected_address())
+                .map(|ip| RuleDraft::block_remote(ip, TrafficDirection::Egress)),
+            FocusedSection::Alerts if block_all => {
+                let sources = self.alert.sources();
+                if sources.is_empty() {
+                    None
+                } else {
+                    let name = format!("alerts-{}", Local::now().format("%Y%m%d-%H%M%S"));
+                    let source = match RuleGroup::save(&name, &sources) {
+                        Ok(source) => source,
+                        Err(e) => {
+                            Notification::send(
+                                format!("Failed to save the alert sources. {e}"),
+                                NotificationLevel::Error,
+                                notification_sender,
+                            )?;
+                            return Err(e);
+                        }
+                    };
+                    Some(RuleDraft::block_group(
+                        "Block the alert sources",
+                        &source,
+                        TrafficDirection::Ingress,
+                    ))
+                }
+            }
+            FocusedSection::Alerts => self
+                .alert
+                .selected_source()
+                .map(|ip| RuleDraft::block_remote(ip, TrafficDirection::Ingress)),
+            _ => None,
+        };
+
+        let Some(draft) = draft else {
+            let message = match self.focused_section {
+                FocusedSection::Inspection => "Scroll to the packet to block first",
+                _ => "Nothing to block",
+            };
+            Notification::send(message, NotificationLevel::Warning, notification_sender)?;
+            return Err("Nothing to block".into());
+        };
+
+        self.firewall.draft_rule(draft, notification_sender)?;
+        self.focused_section = FocusedSection::Firewall;
+
+        Ok(())
+    }
 }
@@ -1,140 +1,296 @@
-mod threat;
+pub mod history;
+pub mod hook;
+pub mod response;
+pub mod threat;
 
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use crossterm::event::{KeyCode, KeyEvent};
+use itertools::Itertools;
+use log::error;
 use ratatui::{
     Frame,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Flex, Layout, Rect},
     style::{Color, Style, Stylize},
-    text::{Span, Text},
-    widgets::WidgetRef,
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Row, Table, TableState},
 };
 use std::{
     net::IpAddr,
-    sync::{Arc, RwLock},
-    thread,
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
     time::Duration,
 };
 
 use crate::{
-    packet::{
-        NetworkPacket,
-        direction::TrafficDirection,
-        network::{IpPacket, ip::IpProto},
-    },
+    app::AppResult,
+    event::Event,
+    notification::{Notification, NotificationLevel},
     packet_store::PacketStore,
-    section::alert::threat::synflood::SynFlood,
+    section::firewall::IpNet,
 };
 
-use rustc_hash::FxHashMap as HashMap;
+use history::{History, IncidentStatus};
+use hook::{AlertHook, spawn_hooks};
+use response::{AutoBlock, BlockRequest, is_allowed};
+use threat::Detectors;
 
-use std::fmt::Debug;
+// Duration over which the detectors count the packets
+const ALERT_WINDOW: Duration = Duration::from_secs(10);
 
-pub trait Threat: Send + Sync + Debug + WidgetRef {}
+// Sources listed in the history table
+const MAX_LISTED_SOURCES: usize = 3;
 
-const WIN_SIZE: usize = 100_000;
+// Sources waiting for the auto block, the oldest are dropped
+const MAX_BLOCK_REQUESTS: usize = 1024;
 
 #[derive(Debug)]
 pub struct Alert {
     pub flash_count: usize,
-    pub threats: Arc<RwLock<Vec<Box<dyn Threat>>>>,
+    pub history: Arc<RwLock<History>>,
+    // Sources flagged for the auto block, applied by the firewall
+    block_requests: Arc<Mutex<Vec<BlockRequest>>>,
+    // Sources never blocked, from `--auto-block-allow`
+    allow_list: Vec<IpNet>,
+    // Stops the detectors and the hooks before a reset, the next app logs to the same file
+    terminate: Arc<AtomicBool>,
+    detectors: Option<JoinHandle<()>>,
+    state: TableState,
 }
 
 impl Alert {
-    pub fn new(packets: PacketStore) -> Self {
-        let ret_threats: Arc<RwLock<Vec<Box<dyn Threat>>>> = Arc::new(RwLock::new(Vec::new()));
-        let threats = ret_threats.clone();
-
-        thread::spawn(move || {
-            let mut last_index = 0;
-            let mut counting_index = 0;
-            let mut count = 0usize;
-            loop {
-                thread::sleep(Duration::from_secs(5));
-                // Phase 1: waiting for enough samples
-                counting_index += packets
-                    .for_each_range(last_index + counting_index.., |packet| {
-                        if packet.direction == TrafficDirection::Ingress {
-                            count += 1;
+    pub fn new(
+        packets: PacketStore,
+        hooks: Vec<AlertHook>,
+        auto_block: Option<AutoBlock>,
+        is_offline: bool,
+    ) -> Self {
+        // The replay of a capture file is not logged
+        let history = Arc::new(RwLock::new(if is_offline {
+            History::default()
+        } else {
+            History::load()
+        }));
+        let block_requests = Arc::new(Mutex::new(Vec::new()));
+        let allow_list = auto_block
+            .as_ref()
+            .map(|auto_block| auto_block.allow_list.clone())
+            .unwrap_or_default();
+
+        let terminate = Arc::new(AtomicBool::new(false));
+        // Not for the old traffic of a capture file
+        let hooks_sender = if is_offline {
+            None
+        } else {
+            spawn_hooks(hooks, terminate.clone())
+        };
+
+        let detectors = thread::spawn({
+            let history = history.clone();
+            let block_requests = block_requests.clone();
+            let terminate = terminate.clone();
+            move || {
+                let mut detectors = Detectors::default();
+                let mut last_index = 0;
+                // In packet time, a capture file is ingested at once
+                let mut window_start: Option<DateTime<Utc>> = None;
+                let window = TimeDelta::from_std(ALERT_WINDOW).unwrap();
+
+                loop {
+                    thread::sleep(Duration::from_secs(1));
+
+                    if terminate.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    // Findings with the end of their window
+                    let mut evaluations = Vec::new();
+
+                    let nb_packets = packets
+                        .for_each_range(last_index.., |app_packet| {
+                            let start = *window_start.get_or_insert(app_packet.timestamp);
+                            if app_packet.timestamp >= start + window {
+                                evaluations
+                                    .push((detectors.evaluate(ALERT_WINDOW), start + window));
+                                window_start = Some(app_packet.timestamp);
+                            }
+                            detectors.ingest(app_packet);
+                            Ok(())
+                        })
+                        .unwrap();
+                    last_index += nb_packets;
+
+                    // Closed without a packet on a quiet network or at the end of a capture file
+                    if let Some(start) = window_start {
+                        let is_over = if is_offline {
+                            nb_packets == 0
+                        } else {
+                            Utc::now() >= start + window
+                        };
+                        if is_over {
+                            evaluations.push((detectors.evaluate(ALERT_WINDOW), start + window));
+                            window_start = None;
                         }
-                        Ok(())
-                    })
-                    .unwrap();
-                if count < WIN_SIZE {
-                    threats.write().unwrap().clear();
-                    continue;
-                }
-                // clear counters
-                count = 0;
-                counting_index = 0;
-
-                // Phase 2: start statistics
-                let mut nb_syn_packets = 0;
-                let mut syn_flood_map: HashMap<IpAddr, usize> = HashMap::default();
-                syn_flood_map.reserve(128);
-
-                last_index += packets
-                    .for_each_range(last_index.., |app_packet| {
-                        if let NetworkPacket::Ip(ip_packet) = app_packet.frame.payload {
-                            match ip_packet {
-                                IpPacket::V4(ipv4_packet) => {
-                                    if let IpProto::Tcp(tcp_packet) = ipv4_packet.proto
-                                        && tcp_packet.syn == 1
-                                    {
-                                        nb_syn_packets += 1;
-                                        if let Some(count) =
-                                            syn_flood_map.get_mut(&IpAddr::V4(ipv4_packet.src_ip))
-                                        {
-                                            *count += 1;
-                                        } else {
-                                            syn_flood_map.insert(IpAddr::V4(ipv4_packet.src_ip), 1);
-                                        }
-                                    }
-                                }
-                                IpPacket::V6(ipv6_packet) => {
-                                    if let IpProto::Tcp(tcp_packet) = ipv6_packet.proto
-                                        && tcp_packet.syn == 1
-                                    {
-                                        nb_syn_packets += 1;
-                                        if let Some(count) =
-                                            syn_flood_map.get_mut(&IpAddr::V6(ipv6_packet.src_ip))
-                                        {
-                                            *count += 1;
-                                        } else {
-                                            syn_flood_map.insert(IpAddr::V6(ipv6_packet.src_ip), 1);
-                                        }
-                                    }
-                                }
+                    }
+
+                    for (findings, window_end) in evaluations {
+                        if let Some(auto_block) = &auto_block {
+                            let mut pending = block_requests.lock().unwrap();
+                            for request in auto_block.requests(&findings) {
+                                // Replaces the pending request of the source
+                                pending.retain(|pending: &BlockRequest| pending.ip != request.ip);
+                                pending.push(request);
+                            }
+                            let nb_requests = pending.len();
+                            pending.drain(..nb_requests.saturating_sub(MAX_BLOCK_REQUESTS));
+                        }
+                        let alerted =
+                            history
+                                .write()
+                                .unwrap()
+                                .record(&findings, ALERT_WINDOW, window_end);
+                        if let Some(hooks_sender) = &hooks_sender
+                            && !alerted.is_empty()
+                        {
+                            let findings = findings
+                                .into_iter()
+                                .filter(|finding| alerted.contains(&finding.threat))
+                                .collect();
+                            if let Err(e) = hooks_sender.send(findings) {
+                                error!("Failed to run the alert hooks. {e}");
                             }
                         }
-                        Ok(())
-                    })
-                    .unwrap();
-                threats.write().unwrap().clear();
-
-                // 90% of incoming packets
-                if (nb_syn_packets as f64 / WIN_SIZE as f64) > 0.95 {
-                    let syn_flood = Box::new(SynFlood { map: syn_flood_map });
-                    threats.write().unwrap().push(syn_flood);
+                    }
                 }
             }
         });
 
         Self {
-            threats: ret_threats,
+            history,
+            block_requests,
+            allow_list,
+            terminate,
+            detectors: Some(detectors),
             flash_count: 1,
+            state: TableState::default(),
+        }
+    }
+
+    // Waits for the detectors to finish the window they are recording
+    pub fn terminate(&mut self) {
+        self.terminate.store(true, Ordering::Relaxed);
+        if let Some(detectors) = self.detectors.take() {
+            let _ = detectors.join();
         }
     }
 
     pub fn check(&mut self) {
-        if !self.threats.read().unwrap().is_empty() {
+        // Until all the new incidents are acknowledged
+        if self.history.read().unwrap().has_new() {
             self.flash_count += 1;
         } else {
             self.flash_count = 1;
         }
     }
 
-    pub fn render(&self, frame: &mut Frame, block: Rect) {
-        let threats = self.threats.read().unwrap();
-        if threats.is_empty() {
+    pub fn take_block_requests(&self) -> Vec<BlockRequest> {
+        std::mem::take(&mut *self.block_requests.lock().unwrap())
+    }
+
+    // Offending addresses of the ongoing incidents, except the allowed ones
+    pub fn sources(&self) -> Vec<IpAddr> {
+        let now = Utc::now();
+        self.history
+            .read()
+            .unwrap()
+            .visible()
+            .iter()
+            .filter(|incident| incident.is_open(now))
+            .flat_map(|incident| incident.sources.iter().map(|(ip, _)| *ip))
+            .filter(|ip| !is_allowed(*ip, &self.allow_list))
+            .unique()
+            .collect()
+    }
+
+    pub fn selected_source(&self) -> Option<IpAddr> {
+        self.history
+            .read()
+            .unwrap()
+            .visible()
+            .get(self.state.selected()?)
+            .and_then(|incident| incident.top_source())
+    }
+
+    fn set_selected_status(&mut self, status: IncidentStatus) -> AppResult<()> {
+        let mut history = self.history.write().unwrap();
+        let Some(id) = self
+            .state
+            .selected()
+            .and_then(|i| history.visible().get(i).map(|incident| incident.id))
+        else {
+            return Ok(());
+        };
+
+        history.set_status(id, status)
+    }
+
+    pub fn handle_keys(
+        &mut self,
+        key_event: KeyEvent,
+        sender: kanal::Sender<Event>,
+    ) -> AppResult<()> {
+        let nb_incidents = self.history.read().unwrap().visible().len();
+        if nb_incidents == 0 {
+            self.state.select(None);
+            return Ok(());
+        }
+
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                let i = match self.state.selected() {
+                    Some(i) => (i + 1).min(nb_incidents - 1),
+                    None => 0,
+                };
+                self.state.select(Some(i));
+            }
+
+            KeyCode::Char('k') | KeyCode::Up => {
+                let i = match self.state.selected() {
+                    Some(i) => i.saturating_sub(1),
+                    None => 0,
+                };
+                self.state.select(Some(i));
+            }
+
+            KeyCode::Char('a') => {
+                if let Err(e) = self.set_selected_status(IncidentStatus::Acknowledged) {
+                    Notification::send(e.to_string(), NotificationLevel::Error, sender)?;
+                }
+            }
+
+            KeyCode::Char('d') => {
+                if let Err(e) = self.set_selected_status(IncidentStatus::Dismissed) {
+                    Notification::send(e.to_string(), NotificationLevel::Error, sender)?;
+                }
+                // The next incident takes its place
+                if let Some(i) = self.state.selected() {
+                    self.state
+                        .select(Some(i.min(nb_incidents.saturating_sub(2))));
+                }
+            }
+
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, block: Rect) {
+        let history = self.history.read().unwrap();
+        let incidents = history.visible();
+        if incidents.is_empty() {
             let text_block = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
@@ -153,35 +309,100 @@ impl Alert {
             return;
         }
 
-        let syn_flood_block = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Length(10), Constraint::Fill(1)])
-            .flex(ratatui::layout::Flex::SpaceBetween)
-            .margin(2)
-            .split(block)[0];
-
-        let syn_flood_block = Layout::default()
-            .direction(Direction::Vertical)
+        let incidents_block = Layout::default()
+            .direction(Direction::Horizontal)
             .constraints([
                 Constraint::Fill(1),
-                Constraint::Max(60),
+                Constraint::Max(180),
                 Constraint::Fill(1),
             ])
             .flex(ratatui::layout::Flex::SpaceBetween)
             .margin(2)
-            .split(syn_flood_block)[1];
+            .split(block)[1];
 
-        // FIX: why ?
-        // frame.render_widget_ref(x, syn_flood_block);
-        for threat in threats.iter() {
-            threat.render_ref(syn_flood_block, frame.buffer_mut());
-        }
+        let widths = [
+            Constraint::Length(10),
+            Constraint::Max(20),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(10),
+            Constraint::Max(40),
+            Constraint::Length(12),
+            Constraint::Fill(1),
+        ];
+
+        let format_time = |time: DateTime<Utc>| {
+            time.with_timezone(&Local)
+                .format("%m-%d %H:%M:%S")
+                .to_string()
+        };
+
+        let rows = incidents.iter().map(|incident| {
+            let sources = if incident.sources.is_empty() {
+                "-".to_string()
+            } else {
+                incident
+                    .sources
+                    .iter()
+                    .take(MAX_LISTED_SOURCES)
+                    .map(|(ip, _)| ip.to_string())
+                    .join(", ")
+            };
+
+            let (status, style) = match incident.status {
+                IncidentStatus::New => ("New", Style::new().bold()),
+                _ => ("Acknowledged", Style::new().dim()),
+            };
+
+            Row::new(vec![
+                Line::from(incident.severity.to_string())
+                    .centered()
+                    .fg(incident.severity.color())
+                    .bold(),
+                Line::from(incident.threat.clone()).centered().bold(),
+                Line::from(format_time(incident.first_seen)).centered(),
+                Line::from(format_time(incident.last_seen)).centered(),
+                Line::from(format!("{:.1}/s", incident.peak_rate)).centered(),
+                Line::from(sources).centered(),
+                Line::from(status).centered(),
+                Line::from(incident.details.clone()).centered(),
+            ])
+            .style(style)
+        });
+
+        let table = Table::new(rows, widths)
+            .column_spacing(2)
+            .flex(Flex::SpaceBetween)
+            .header(
+                Row::new(vec![
+                    Line::from("Severity").centered(),
+                    Line::from("Threat").centered(),
+                    Line::from("First seen").centered(),
+                    Line::from("Last seen").centered(),
+                    Line::from("Peak").centered(),
+                    Line::from("Top sources").centered(),
+                    Line::from("Status").centered(),
+                    Line::from("Details").centered(),
+                ])
+                .style(Style::new().bold())
+                .bottom_margin(1),
+            )
+            .row_highlight_style(Style::new().bg(Color::DarkGray))
+            .block(
+                Block::new()
+                    .title(" Alert history ")
+                    .borders(Borders::all())
+                    .border_style(Style::new().yellow())
+                    .title_alignment(Alignment::Center),
+            );
+
+        frame.render_stateful_widget(table, incidents_block, &mut self.state);
     }
 
     pub fn title_span(&self, is_focused: bool) -> Span<'_> {
-        let threats = self.threats.read().unwrap();
+        let is_alerting = self.history.read().unwrap().has_new();
         if is_focused {
-            if !threats.is_empty() {
+            if is_alerting {
                 if self.flash_count.is_multiple_of(12) {
                     Span::from("  Alert 󰐼   ").fg(Color::White).bg(Color::Red)
                 } else {
@@ -193,7 +414,7 @@ impl Alert {
                     Style::default().bg(Color::Green).fg(Color::White).bold(),
                 )
             }
-        } else if !threats.is_empty() {
+        } else if is_alerting {
             if self.flash_count.is_multiple_of(12) {
                 Span::from("  Alert 󰐼   ").fg(Color::White).bg(Color::Red)
             } else {
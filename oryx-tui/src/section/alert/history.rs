@@ -0,0 +1,308 @@
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    net::IpAddr,
+    os::unix::fs::chown,
+    path::PathBuf,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::AppResult;
+
+use super::threat::{Finding, Severity};
+
+// A threat seen again within this delay continues its incident
+const INCIDENT_TIMEOUT: Duration = Duration::from_secs(300);
+
+// Incidents kept in memory and in the log, the most recent ones
+const MAX_INCIDENTS: usize = 500;
+
+// Lines appended to the log before it is rewritten with the last states only
+const MAX_APPENDED_LINES: usize = 10 * MAX_INCIDENTS;
+
+// Sources kept for each incident
+const MAX_SOURCES: usize = 10;
+
+fn log_file() -> PathBuf {
+    dirs::home_dir().unwrap().join("oryx").join("alerts.jsonl")
+}
+
+mod timestamp {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        time: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&time.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        DateTime::parse_from_rfc3339(&value)
+            .map(|time| time.with_timezone(&Utc))
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IncidentStatus {
+    New,
+    Acknowledged,
+    Dismissed,
+}
+
+// Consecutive findings of a threat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Incident {
+    pub id: Uuid,
+    pub threat: String,
+    // The highest of the findings
+    pub severity: Severity,
+    #[serde(with = "timestamp")]
+    pub first_seen: DateTime<Utc>,
+    #[serde(with = "timestamp")]
+    pub last_seen: DateTime<Utc>,
+    // Highest count per second over a window, all the sources together
+    pub peak_rate: f64,
+    // Offending addresses with their counts, the largest first
+    pub sources: Vec<(IpAddr, usize)>,
+    // Details of the most severe finding of the last window
+    pub details: String,
+    pub status: IncidentStatus,
+}
+
+impl Incident {
+    fn new(threat: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            threat: threat.to_string(),
+            severity: Severity::Low,
+            first_seen: now,
+            last_seen: now,
+            peak_rate: 0.0,
+            sources: Vec::new(),
+            details: String::new(),
+            status: IncidentStatus::New,
+        }
+    }
+
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        self.status != IncidentStatus::Dismissed
+            && (now - self.last_seen).to_std().unwrap_or_default() < INCIDENT_TIMEOUT
+    }
+
+    // Whether the severity rose
+    fn update(&mut self, findings: &[&Finding], window: Duration, now: DateTime<Utc>) -> bool {
+        let severity = self.severity;
+        let count: usize = findings.iter().map(|finding| finding.count).sum();
+
+        self.last_seen = now;
+        self.peak_rate = self.peak_rate.max(count as f64 / window.as_secs_f64());
+
+        if let Some(finding) = findings.iter().max_by_key(|finding| finding.severity) {
+            self.severity = self.severity.max(finding.severity);
+            self.details = finding.details.clone();
+        }
+
+        for finding in findings {
+            let Some(ip) = finding.source else {
+                continue;
+            };
+            match self.sources.iter_mut().find(|(source, _)| *source == ip) {
+                Some((_, source_count)) => *source_count += finding.count,
+                None => self.sources.push((ip, finding.count)),
+            }
+        }
+
+        self.sources.sort_by(|(_, a), (_, b)| b.cmp(a));
+        self.sources.truncate(MAX_SOURCES);
+
+        self.severity > severity
+    }
+
+    pub fn top_source(&self) -> Option<IpAddr> {
+        self.sources.first().map(|(ip, _)| *ip)
+    }
+}
+
+// Incidents of the previous and current sessions, logged to `~/oryx/alerts.jsonl`.
+// Each change appends the incident, the last line of an id is its state.
+#[derive(Debug, Default)]
+pub struct History {
+    pub incidents: Vec<Incident>,
+    // The incidents of a capture file are not logged
+    is_persistent: bool,
+    // Lines appended since the log was rewritten
+    appended_lines: usize,
+}
+
+impl History {
+    pub fn load() -> Self {
+        let mut history = Self {
+            is_persistent: true,
+            ..Default::default()
+        };
+
+        let Ok(content) = fs::read_to_string(log_file()) else {
+            info!("Alert log not found");
+            return history;
+        };
+
+        let mut incidents: HashMap<Uuid, Incident> = HashMap::new();
+        for incident in content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Incident>(line).ok())
+        {
+            incidents.insert(incident.id, incident);
+        }
+
+        history.incidents = incidents.into_values().collect();
+        history.truncate();
+
+        if let Err(e) = history.compact() {
+            error!("Failed to rewrite the alert log. {e}");
+        }
+
+        info!("Alert log loaded");
+        history
+    }
+
+    // Keeps the most recent incidents
+    fn truncate(&mut self) {
+        if self.incidents.len() <= MAX_INCIDENTS {
+            return;
+        }
+
+        self.incidents.sort_by_key(|incident| incident.last_seen);
+        let nb_incidents = self.incidents.len();
+        self.incidents.drain(..nb_incidents - MAX_INCIDENTS);
+    }
+
+    // Rewrites the log with the last state of each incident
+    fn compact(&mut self) -> AppResult<()> {
+        let mut content = String::new();
+        for incident in &self.incidents {
+            content.push_str(&serde_json::to_string(incident)?);
+            content.push('\n');
+        }
+
+        let user_uid = unsafe { libc::geteuid() };
+
+        let log_file = log_file();
+        let new_log_file = log_file.with_extension("jsonl.new");
+        fs::write(&new_log_file, content)?;
+        chown(&new_log_file, Some(user_uid), Some(user_uid))?;
+        fs::rename(new_log_file, log_file)?;
+
+        self.appended_lines = 0;
+
+        Ok(())
+    }
+
+    fn append(&mut self, index: usize) -> AppResult<()> {
+        if !self.is_persistent {
+            return Ok(());
+        }
+
+        let user_uid = unsafe { libc::geteuid() };
+
+        let oryx_dir = dirs::home_dir().unwrap().join("oryx");
+        if !oryx_dir.exists() {
+            fs::create_dir(&oryx_dir)?;
+            chown(&oryx_dir, Some(user_uid), Some(user_uid))?;
+        }
+
+        let log_file = log_file();
+        let is_new_file = !log_file.exists();
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_file)?;
+        if is_new_file {
+            chown(&log_file, Some(user_uid), Some(user_uid))?;
+        }
+        writeln!(file, "{}", serde_json::to_string(&self.incidents[index])?)?;
+
+        self.appended_lines += 1;
+
+        Ok(())
+    }
+
+    // Merges the findings of a window ending at `now`, in packet time, into the open
+    // incidents of their threats, returns the threats of the new and escalated incidents
+    pub fn record(
+        &mut self,
+        findings: &[Finding],
+        window: Duration,
+        now: DateTime<Utc>,
+    ) -> Vec<&'static str> {
+        let mut alerted = Vec::new();
+
+        for (threat, findings) in findings.iter().into_group_map_by(|finding| finding.threat) {
+            let (index, is_new) = match self
+                .incidents
+                .iter()
+                .position(|incident| incident.threat == threat && incident.is_open(now))
+            {
+                Some(index) => (index, false),
+                None => {
+                    self.incidents.push(Incident::new(threat, now));
+                    (self.incidents.len() - 1, true)
+                }
+            };
+
+            if self.incidents[index].update(&findings, window, now) || is_new {
+                alerted.push(threat);
+            }
+
+            if let Err(e) = self.append(index) {
+                error!("Failed to write the alert log. {e}");
+            }
+        }
+
+        self.truncate();
+        if self.appended_lines > MAX_APPENDED_LINES
+            && let Err(e) = self.compact()
+        {
+            error!("Failed to rewrite the alert log. {e}");
+        }
+
+        alerted
+    }
+
+    // Not dismissed, the most recent first
+    pub fn visible(&self) -> Vec<&Incident> {
+        self.incidents
+            .iter()
+            .filter(|incident| incident.status != IncidentStatus::Dismissed)
+            .sorted_by(|a, b| b.last_seen.cmp(&a.last_seen))
+            .collect()
+    }
+
+    pub fn has_new(&self) -> bool {
+        self.incidents
+            .iter()
+            .any(|incident| incident.status == IncidentStatus::New)
+    }
+
+    pub fn set_status(&mut self, id: Uuid, status: IncidentStatus) -> AppResult<()> {
+        if let Some(index) = self.incidents.iter().position(|incident| incident.id == id) {
+            self.incidents[index].status = status;
+            self.append(index)?;
+        }
+        Ok(())
+    }
+}
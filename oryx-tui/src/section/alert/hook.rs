@@ -0,0 +1,226 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    os::unix::net::UnixDatagram,
+    path::PathBuf,
+    process::{Command, Stdio},
+    str::FromStr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use chrono::Utc;
+use log::error;
+use serde::Serialize;
+
+use crate::app::AppResult;
+
+use super::threat::{Finding, Severity};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Plain HTTP endpoint, e.g. `http://127.0.0.1:8080/alerts`
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl FromStr for Webhook {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let url = s
+            .strip_prefix("http://")
+            .ok_or(format!("Only http:// URLs are supported: {s}"))?;
+
+        let (authority, path) = match url.find('/') {
+            Some(i) => url.split_at(i),
+            None => (url, "/"),
+        };
+
+        let (host, port) = match authority.rsplit_once(':') {
+            // Not the colons of an IPv6 address
+            Some((host, port)) if !port.ends_with(']') => (
+                host,
+                port.parse::<u16>()
+                    .map_err(|_| format!("Invalid port {port}"))?,
+            ),
+            _ => (authority, 80),
+        };
+
+        if host.is_empty() {
+            return Err(format!("Missing host in {s}"));
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+impl Webhook {
+    // The port is implied for 80 only
+    fn authority(&self) -> String {
+        if self.port == 80 {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    fn post(&self, body: &str) -> AppResult<()> {
+        let addr = (self.host.trim_matches(['[', ']']), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or(format!("Can not resolve {}", self.host))?;
+
+        let mut stream = TcpStream::connect_timeout(&addr, WEBHOOK_TIMEOUT)?;
+        stream.set_read_timeout(Some(WEBHOOK_TIMEOUT))?;
+        stream.set_write_timeout(Some(WEBHOOK_TIMEOUT))?;
+
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            self.path,
+            self.authority(),
+            body.len()
+        )?;
+
+        // e.g. `HTTP/1.1 200 OK`
+        let mut status_line = [0u8; 12];
+        stream.read_exact(&mut status_line)?;
+        let status = String::from_utf8_lossy(&status_line[9..12]).to_string();
+        if !status.starts_with('2') {
+            return Err(format!("Webhook answered with the status {status}").into());
+        }
+
+        Ok(())
+    }
+}
+
+// Run for each new or escalated incident, on top of the flashing Alert tab
+#[derive(Debug, Clone)]
+pub enum AlertHook {
+    // Receives the finding as JSON on stdin
+    Exec(PathBuf),
+    // Through `/dev/log`, also read by journald
+    Syslog,
+    // Receives the finding as JSON
+    Webhook(Webhook),
+}
+
+#[derive(Serialize)]
+struct AlertEvent<'a> {
+    time: String,
+    #[serde(flatten)]
+    finding: &'a Finding,
+}
+
+fn syslog_level(severity: Severity) -> i32 {
+    match severity {
+        Severity::Critical => libc::LOG_CRIT,
+        Severity::High => libc::LOG_ERR,
+        Severity::Medium => libc::LOG_WARNING,
+        Severity::Low => libc::LOG_NOTICE,
+    }
+}
+
+impl AlertHook {
+    fn run(&self, finding: &Finding, payload: &str) -> AppResult<()> {
+        match self {
+            AlertHook::Exec(path) => {
+                let mut child = Command::new(path)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()?;
+
+                // Waited for even if it does not read its stdin, no zombie left
+                let written = match child.stdin.take() {
+                    Some(mut stdin) => stdin.write_all(payload.as_bytes()),
+                    None => Ok(()),
+                };
+
+                let status = child.wait()?;
+                written?;
+                if !status.success() {
+                    return Err(format!("{} exited with {status}", path.display()).into());
+                }
+            }
+
+            AlertHook::Syslog => {
+                let source = finding
+                    .source
+                    .map(|ip| format!(" from {ip}"))
+                    .unwrap_or_default();
+
+                let message = format!(
+                    "<{}>oryx[{}]: {} ({}){source}: {}",
+                    libc::LOG_DAEMON | syslog_level(finding.severity),
+                    std::process::id(),
+                    finding.threat,
+                    finding.severity,
+                    finding.details
+                );
+
+                UnixDatagram::unbound()?.send_to(message.as_bytes(), "/dev/log")?;
+            }
+
+            AlertHook::Webhook(webhook) => webhook.post(payload)?,
+        }
+
+        Ok(())
+    }
+}
+
+// In the background, a slow hook does not delay the detectors. Stops once
+// `terminate` is set or the detectors are gone
+pub fn spawn_hooks(
+    hooks: Vec<AlertHook>,
+    terminate: Arc<AtomicBool>,
+) -> Option<kanal::Sender<Vec<Finding>>> {
+    if hooks.is_empty() {
+        return None;
+    }
+
+    let (sender, receiver) = kanal::unbounded::<Vec<Finding>>();
+
+    thread::spawn(move || {
+        while let Ok(findings) = receiver.recv() {
+            if terminate.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let time = Utc::now().to_rfc3339();
+
+            for finding in &findings {
+                let payload = match serde_json::to_string(&AlertEvent {
+                    time: time.clone(),
+                    finding,
+                }) {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        error!("Failed to serialize the finding. {e}");
+                        continue;
+                    }
+                };
+
+                for hook in &hooks {
+                    if let Err(e) = hook.run(finding, &payload) {
+                        error!("Alert hook failed. {e}");
+                    }
+                }
+            }
+        }
+    });
+
+    Some(sender)
+}
@@ -0,0 +1,101 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    net::IpAddr,
+    os::unix::fs::chown,
+};
+
+use chrono::Utc;
+use itertools::Itertools;
+
+use crate::{
+    app::AppResult,
+    section::firewall::{IpNet, ttl::RuleTtl},
+};
+
+use super::threat::{Finding, Severity};
+
+// Opt-in blocking of the sources flagged by the detectors, for the unattended hosts
+#[derive(Debug, Clone)]
+pub struct AutoBlock {
+    pub min_severity: Severity,
+    // Lifetime of the block rules
+    pub ttl: RuleTtl,
+    // Never blocked
+    pub allow_list: Vec<IpNet>,
+}
+
+// Source to block with the threat that flagged it
+#[derive(Debug, Clone)]
+pub struct BlockRequest {
+    pub ip: IpAddr,
+    pub threat: &'static str,
+    pub severity: Severity,
+    pub ttl: RuleTtl,
+}
+
+// Loopback or in the allow list
+pub fn is_allowed(ip: IpAddr, allow_list: &[IpNet]) -> bool {
+    ip.is_loopback()
+        || allow_list
+            .iter()
+            .any(|subnet| subnet.contains(&IpNet::from(ip)))
+}
+
+impl AutoBlock {
+    // The most severe finding of each source, they come sorted by severity
+    pub fn requests(&self, findings: &[Finding]) -> Vec<BlockRequest> {
+        findings
+            .iter()
+            .filter(|finding| finding.severity >= self.min_severity)
+            .filter_map(|finding| {
+                let ip = finding
+                    .source
+                    .filter(|ip| !is_allowed(*ip, &self.allow_list))?;
+                Some(BlockRequest {
+                    ip,
+                    threat: finding.threat,
+                    severity: finding.severity,
+                    ttl: self.ttl,
+                })
+            })
+            .unique_by(|request| request.ip)
+            .collect()
+    }
+}
+
+impl BlockRequest {
+    // Appends the block to `~/oryx/autoblock.log`
+    pub fn log(&self) -> AppResult<()> {
+        let user_uid = unsafe { libc::geteuid() };
+
+        let oryx_dir = dirs::home_dir().unwrap().join("oryx");
+        if !oryx_dir.exists() {
+            fs::create_dir(&oryx_dir)?;
+            chown(&oryx_dir, Some(user_uid), Some(user_uid))?;
+        }
+
+        let log_file = oryx_dir.join("autoblock.log");
+        let is_new_file = !log_file.exists();
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_file)?;
+        if is_new_file {
+            chown(&log_file, Some(user_uid), Some(user_uid))?;
+        }
+
+        writeln!(
+            file,
+            "{} blocked {} for {}: {} ({})",
+            Utc::now().to_rfc3339(),
+            self.ip,
+            self.ttl,
+            self.threat,
+            self.severity
+        )?;
+
+        Ok(())
+    }
+}
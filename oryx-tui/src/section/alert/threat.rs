@@ -1 +1,133 @@
+pub mod amplification;
+pub mod arpspoof;
+pub mod icmpflood;
+pub mod portscan;
 pub mod synflood;
+
+use core::fmt::Display;
+use std::{fmt::Debug, net::IpAddr, str::FromStr, time::Duration};
+
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::packet::AppPacket;
+
+use amplification::UdpAmplification;
+use arpspoof::ArpSpoof;
+use icmpflood::IcmpFlood;
+use portscan::PortScan;
+use synflood::SynFlood;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    // How far above its threshold a rate is
+    pub fn from_rate(rate: f64, threshold: f64) -> Self {
+        match rate / threshold {
+            ratio if ratio >= 100.0 => Severity::Critical,
+            ratio if ratio >= 10.0 => Severity::High,
+            ratio if ratio >= 2.0 => Severity::Medium,
+            _ => Severity::Low,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Severity::Low => Color::Blue,
+            Severity::Medium => Color::Yellow,
+            Severity::High => Color::LightRed,
+            Severity::Critical => Color::Red,
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "low" => Ok(Severity::Low),
+            "medium" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(format!("Unknown severity {s}")),
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Low => write!(f, "Low"),
+            Severity::Medium => write!(f, "Medium"),
+            Severity::High => write!(f, "High"),
+            Severity::Critical => write!(f, "Critical"),
+        }
+    }
+}
+
+// Attack detected in a window
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub threat: &'static str,
+    pub severity: Severity,
+    // Offending address, if any
+    pub source: Option<IpAddr>,
+    // Number of offending packets in the window
+    pub count: usize,
+    pub details: String,
+}
+
+pub trait Threat: Send + Debug {
+    // Called on each captured packet
+    fn ingest(&mut self, app_packet: &AppPacket);
+
+    // Called at the end of each window, which starts over
+    fn evaluate(&mut self, window: Duration) -> Vec<Finding>;
+}
+
+// Every packet goes through all the detectors
+#[derive(Debug)]
+pub struct Detectors {
+    threats: Vec<Box<dyn Threat>>,
+}
+
+impl Default for Detectors {
+    fn default() -> Self {
+        Self {
+            threats: vec![
+                Box::new(SynFlood::default()),
+                Box::new(IcmpFlood::default()),
+                Box::new(UdpAmplification::default()),
+                Box::new(PortScan::default()),
+                Box::new(ArpSpoof::default()),
+            ],
+        }
+    }
+}
+
+impl Detectors {
+    pub fn ingest(&mut self, app_packet: &AppPacket) {
+        for threat in self.threats.iter_mut() {
+            threat.ingest(app_packet);
+        }
+    }
+
+    // The most severe first
+    pub fn evaluate(&mut self, window: Duration) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = self
+            .threats
+            .iter_mut()
+            .flat_map(|threat| threat.evaluate(window))
+            .collect();
+
+        findings.sort_by(|a, b| b.severity.cmp(&a.severity).then(b.count.cmp(&a.count)));
+        findings
+    }
+}
@@ -0,0 +1,130 @@
+use std::{net::IpAddr, time::Duration};
+
+use rustc_hash::FxHashMap as HashMap;
+
+use crate::{
+    packet::{
+        AppPacket, NetworkPacket,
+        direction::TrafficDirection,
+        network::{IpPacket, ip::IpProto},
+    },
+    section::flows::format_bytes,
+};
+
+use super::{Finding, Severity, Threat};
+
+// Services answering a small request with a much larger response
+const REFLECTOR_PORTS: [(u16, &str); 4] = [
+    (53, "DNS"),
+    (123, "NTP"),
+    (1900, "SSDP"),
+    (11211, "Memcached"),
+];
+
+// Bytes per second received from one reflector
+const AMPLIFIED_RATE: f64 = 100_000.0;
+
+// Received bytes for each byte of request sent to the reflector.
+// The responses to our own requests stay below it.
+const AMPLIFICATION_FACTOR: f64 = 20.0;
+
+#[derive(Debug, Default)]
+struct ReflectorTraffic {
+    packets: usize,
+    received: usize,
+    sent: usize,
+}
+
+// Responses of the reflectors to the requests spoofed with our address
+#[derive(Debug, Default)]
+pub struct UdpAmplification {
+    // By reflector address and service port
+    reflectors: HashMap<(IpAddr, u16), ReflectorTraffic>,
+}
+
+fn service(port: u16) -> Option<&'static str> {
+    REFLECTOR_PORTS
+        .iter()
+        .find(|(reflector_port, _)| *reflector_port == port)
+        .map(|(_, name)| *name)
+}
+
+impl Threat for UdpAmplification {
+    fn ingest(&mut self, app_packet: &AppPacket) {
+        let NetworkPacket::Ip(ip_packet) = app_packet.frame.payload else {
+            return;
+        };
+
+        let (src_ip, dst_ip, proto) = match ip_packet {
+            IpPacket::V4(ipv4_packet) => (
+                IpAddr::V4(ipv4_packet.src_ip),
+                IpAddr::V4(ipv4_packet.dst_ip),
+                ipv4_packet.proto,
+            ),
+            IpPacket::V6(ipv6_packet) => (
+                IpAddr::V6(ipv6_packet.src_ip),
+                IpAddr::V6(ipv6_packet.dst_ip),
+                ipv6_packet.proto,
+            ),
+        };
+
+        let IpProto::Udp(udp_packet) = proto else {
+            return;
+        };
+
+        match app_packet.direction {
+            TrafficDirection::Ingress if service(udp_packet.src_port).is_some() => {
+                let traffic = self
+                    .reflectors
+                    .entry((src_ip, udp_packet.src_port))
+                    .or_default();
+                traffic.packets += 1;
+                traffic.received += udp_packet.length as usize;
+            }
+            TrafficDirection::Egress if service(udp_packet.dst_port).is_some() => {
+                self.reflectors
+                    .entry((dst_ip, udp_packet.dst_port))
+                    .or_default()
+                    .sent += udp_packet.length as usize;
+            }
+            _ => {}
+        }
+    }
+
+    fn evaluate(&mut self, window: Duration) -> Vec<Finding> {
+        let reflectors = std::mem::take(&mut self.reflectors);
+
+        reflectors
+            .into_iter()
+            .filter_map(|((ip, port), traffic)| {
+                let rate = traffic.received as f64 / window.as_secs_f64();
+                if rate < AMPLIFIED_RATE
+                    || (traffic.received as f64) < AMPLIFICATION_FACTOR * traffic.sent as f64
+                {
+                    return None;
+                }
+
+                let requests = if traffic.sent == 0 {
+                    "no requests sent".to_string()
+                } else {
+                    format!(
+                        "{:.0}x the requests",
+                        traffic.received as f64 / traffic.sent as f64
+                    )
+                };
+
+                Some(Finding {
+                    threat: "UDP Amplification",
+                    severity: Severity::from_rate(rate, AMPLIFIED_RATE),
+                    source: Some(ip),
+                    count: traffic.packets,
+                    details: format!(
+                        "{} responses, {}/s, {requests}",
+                        service(port).unwrap_or_default(),
+                        format_bytes(rate as usize)
+                    ),
+                })
+            })
+            .collect()
+    }
+}
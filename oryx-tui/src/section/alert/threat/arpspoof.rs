@@ -0,0 +1,190 @@
+use std::{collections::HashSet, fs, net::Ipv4Addr, time::Duration};
+
+use chrono::{DateTime, Local, Utc};
+use itertools::Itertools;
+use rustc_hash::FxHashMap as HashMap;
+
+use crate::packet::{
+    AppPacket, NetworkPacket,
+    link::{ArpPacket, ArpType, MacAddr},
+};
+
+use super::{Finding, Severity, Threat};
+
+// Gratuitous ARP packets per second of an address
+const GRATUITOUS_RATE: f64 = 1.0;
+
+// Events listed in the timeline of a finding
+const MAX_TIMELINE_EVENTS: usize = 4;
+
+// IPv4 gateway of the default route
+fn default_gateway() -> Option<Ipv4Addr> {
+    let routes = fs::read_to_string("/proc/net/route").ok()?;
+
+    // Iface Destination Gateway ..., in hexadecimal and host byte order
+    routes.lines().skip(1).find_map(|line| {
+        let mut fields = line.split_whitespace().skip(1);
+        match (fields.next(), fields.next()) {
+            (Some("00000000"), Some(gateway)) => u32::from_str_radix(gateway, 16)
+                .ok()
+                .map(|gateway| Ipv4Addr::from(gateway.to_ne_bytes())),
+            _ => None,
+        }
+    })
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format("%H:%M:%S").to_string()
+}
+
+#[derive(Debug, Default)]
+struct WindowEvents {
+    // (time, old MAC, new MAC) of each address
+    mac_changes: HashMap<Ipv4Addr, Vec<(DateTime<Utc>, MacAddr, MacAddr)>>,
+    gratuitous: HashMap<Ipv4Addr, usize>,
+    gateway_macs: HashSet<MacAddr>,
+    // ARP packets with a sender MAC different from the Ethernet source
+    forged: HashMap<Ipv4Addr, (MacAddr, MacAddr)>,
+}
+
+// Changes in the IP to MAC bindings announced on the link
+#[derive(Debug)]
+pub struct ArpSpoof {
+    // Kept across the windows
+    bindings: HashMap<Ipv4Addr, MacAddr>,
+    gateway: Option<Ipv4Addr>,
+    window: WindowEvents,
+}
+
+impl Default for ArpSpoof {
+    fn default() -> Self {
+        Self {
+            bindings: HashMap::default(),
+            gateway: default_gateway(),
+            window: WindowEvents::default(),
+        }
+    }
+}
+
+impl ArpSpoof {
+    fn learn(&mut self, arp_packet: &ArpPacket, time: DateTime<Utc>) {
+        let (ip, mac) = (arp_packet.src_ip, arp_packet.src_mac);
+
+        // Probes, the sender has no address yet
+        if ip.is_unspecified() {
+            return;
+        }
+
+        if Some(ip) == self.gateway {
+            self.window.gateway_macs.insert(mac);
+        }
+
+        if let Some(old_mac) = self.bindings.insert(ip, mac)
+            && old_mac != mac
+        {
+            self.window
+                .mac_changes
+                .entry(ip)
+                .or_default()
+                .push((time, old_mac, mac));
+        }
+    }
+}
+
+impl Threat for ArpSpoof {
+    fn ingest(&mut self, app_packet: &AppPacket) {
+        let NetworkPacket::Arp(arp_packet) = app_packet.frame.payload else {
+            return;
+        };
+
+        let eth_src = MacAddr(app_packet.frame.header.src_addr);
+        if eth_src != arp_packet.src_mac {
+            self.window
+                .forged
+                .insert(arp_packet.src_ip, (arp_packet.src_mac, eth_src));
+        }
+
+        // Announces its own address
+        let is_gratuitous = arp_packet.src_ip == arp_packet.dst_ip;
+        if is_gratuitous {
+            *self.window.gratuitous.entry(arp_packet.src_ip).or_default() += 1;
+        }
+
+        // The requests also update the caches of their targets
+        if matches!(arp_packet.arp_type, ArpType::Reply) || is_gratuitous {
+            self.learn(&arp_packet, app_packet.timestamp);
+        }
+    }
+
+    fn evaluate(&mut self, window: Duration) -> Vec<Finding> {
+        let events = std::mem::take(&mut self.window);
+        let mut findings = Vec::new();
+
+        if events.gateway_macs.len() > 1
+            && let Some(gateway) = self.gateway
+        {
+            findings.push(Finding {
+                threat: "ARP Spoofing",
+                severity: Severity::Critical,
+                source: None,
+                count: events.gateway_macs.len(),
+                details: format!(
+                    "Gateway {gateway} claimed by {}",
+                    events.gateway_macs.iter().join(", ")
+                ),
+            });
+        }
+
+        for (ip, changes) in events.mac_changes {
+            let severity = if Some(ip) == self.gateway {
+                Severity::Critical
+            } else {
+                Severity::High
+            };
+
+            let timeline = changes
+                .iter()
+                .rev()
+                .take(MAX_TIMELINE_EVENTS)
+                .rev()
+                .map(|(time, old_mac, new_mac)| {
+                    format!("{old_mac} -> {new_mac} at {}", format_time(*time))
+                })
+                .join(", ");
+
+            findings.push(Finding {
+                threat: "ARP Spoofing",
+                severity,
+                source: None,
+                count: changes.len(),
+                details: format!("MAC of {ip} changed: {timeline}"),
+            });
+        }
+
+        for (ip, count) in events.gratuitous {
+            let rate = count as f64 / window.as_secs_f64();
+            if rate >= GRATUITOUS_RATE {
+                findings.push(Finding {
+                    threat: "ARP Spoofing",
+                    severity: Severity::from_rate(rate, GRATUITOUS_RATE),
+                    source: None,
+                    count,
+                    details: format!("Gratuitous ARP flood for {ip}, {rate:.1}/s"),
+                });
+            }
+        }
+
+        for (ip, (arp_mac, eth_mac)) in events.forged {
+            findings.push(Finding {
+                threat: "ARP Spoofing",
+                // Also sent by the proxies and the bridges
+                severity: Severity::Medium,
+                source: None,
+                count: 1,
+                details: format!("ARP for {ip} with sender {arp_mac} sent by {eth_mac}"),
+            });
+        }
+
+        findings
+    }
+}
@@ -0,0 +1,101 @@
+use std::{net::IpAddr, time::Duration};
+
+use rustc_hash::FxHashMap as HashMap;
+
+use crate::{
+    packet::{
+        AppPacket, NetworkPacket,
+        direction::TrafficDirection,
+        network::{
+            IpPacket,
+            icmp::{IcmpPacket, icmpv4, icmpv6},
+            ip::IpProto,
+        },
+    },
+    section::flows::format_bytes,
+};
+
+use super::{Finding, Severity, Threat};
+
+// Echo requests per second of a source, a ping sends one
+const ECHO_RATE: f64 = 20.0;
+
+#[derive(Debug, Default)]
+struct SourceCounts {
+    requests: usize,
+    bytes: usize,
+}
+
+// Echo requests sent faster than any ping
+#[derive(Debug, Default)]
+pub struct IcmpFlood {
+    sources: HashMap<IpAddr, SourceCounts>,
+}
+
+fn is_echo_request(icmp_packet: &IcmpPacket) -> bool {
+    match icmp_packet {
+        IcmpPacket::V4(icmp_packet) => {
+            matches!(icmp_packet.icmp_type, icmpv4::IcmpType::EchoRequest)
+        }
+        IcmpPacket::V6(icmp_packet) => {
+            matches!(icmp_packet.icmp_type, icmpv6::IcmpType::EchoRequest)
+        }
+    }
+}
+
+impl Threat for IcmpFlood {
+    fn ingest(&mut self, app_packet: &AppPacket) {
+        if app_packet.direction != TrafficDirection::Ingress {
+            return;
+        }
+
+        let NetworkPacket::Ip(ip_packet) = app_packet.frame.payload else {
+            return;
+        };
+
+        let (src_ip, proto, length) = match ip_packet {
+            IpPacket::V4(ipv4_packet) => (
+                IpAddr::V4(ipv4_packet.src_ip),
+                ipv4_packet.proto,
+                ipv4_packet.total_length,
+            ),
+            IpPacket::V6(ipv6_packet) => (
+                IpAddr::V6(ipv6_packet.src_ip),
+                ipv6_packet.proto,
+                ipv6_packet.payload_length,
+            ),
+        };
+
+        if let IpProto::Icmp(icmp_packet) = proto
+            && is_echo_request(&icmp_packet)
+        {
+            let counts = self.sources.entry(src_ip).or_default();
+            counts.requests += 1;
+            counts.bytes += length as usize;
+        }
+    }
+
+    fn evaluate(&mut self, window: Duration) -> Vec<Finding> {
+        let sources = std::mem::take(&mut self.sources);
+
+        sources
+            .into_iter()
+            .filter_map(|(ip, counts)| {
+                let rate = counts.requests as f64 / window.as_secs_f64();
+                if rate < ECHO_RATE {
+                    return None;
+                }
+
+                let bytes_rate = (counts.bytes as f64 / window.as_secs_f64()) as usize;
+
+                Some(Finding {
+                    threat: "ICMP Flood",
+                    severity: Severity::from_rate(rate, ECHO_RATE),
+                    source: Some(ip),
+                    count: counts.requests,
+                    details: format!("{rate:.0} echo requests/s, {}/s", format_bytes(bytes_rate)),
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,191 @@
+use std::{collections::HashSet, net::IpAddr, time::Duration};
+
+use itertools::Itertools;
+use rustc_hash::FxHashMap as HashMap;
+
+use crate::packet::{
+    AppPacket, NetworkPacket,
+    direction::TrafficDirection,
+    network::{IpPacket, ip::IpProto},
+    transport::TcpPacket,
+};
+
+use super::{Finding, Severity, Threat};
+
+// Distinct ports probed on one host within a window
+const VERTICAL_PORTS: usize = 20;
+
+// Distinct hosts probed on one port within a window
+const HORIZONTAL_HOSTS: usize = 10;
+
+// Distinct ports probed with FIN, NULL or Xmas packets within a window
+const STEALTH_PORTS: usize = 3;
+
+// Ports listed in the details of a finding
+const MAX_LISTED_PORTS: usize = 8;
+
+#[derive(Debug, Default)]
+struct Probes {
+    // Ports probed on each host
+    hosts: HashMap<IpAddr, HashSet<u16>>,
+    // Ports probed with each kind of stealth packet
+    stealth: HashMap<&'static str, HashSet<u16>>,
+}
+
+// Connection attempts to many ports or hosts, and the packets probing the closed ports
+// without opening a connection
+#[derive(Debug, Default)]
+pub struct PortScan {
+    sources: HashMap<IpAddr, Probes>,
+}
+
+// Packets that no TCP stack sends, answered by a RST on the closed ports
+fn stealth_kind(tcp_packet: &TcpPacket) -> Option<&'static str> {
+    let other_flags = tcp_packet.syn | tcp_packet.ack | tcp_packet.rst;
+
+    match (tcp_packet.fin, tcp_packet.psh, tcp_packet.urg, other_flags) {
+        (1, 0, 0, 0) => Some("FIN"),
+        (0, 0, 0, 0) => Some("NULL"),
+        (1, 1, 1, 0) => Some("Xmas"),
+        _ => None,
+    }
+}
+
+// e.g. `20-25,80,443`
+fn format_ports(ports: &HashSet<u16>) -> String {
+    let ranges: Vec<(u16, u16)> = ports.iter().sorted().fold(Vec::new(), |mut ranges, port| {
+        match ranges.last_mut() {
+            Some((_, end)) if end.checked_add(1) == Some(*port) => *end = *port,
+            _ => ranges.push((*port, *port)),
+        }
+        ranges
+    });
+
+    let mut ports = ranges
+        .iter()
+        .take(MAX_LISTED_PORTS)
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{start}-{end}")
+            }
+        })
+        .join(",");
+
+    if ranges.len() > MAX_LISTED_PORTS {
+        ports.push_str(",...");
+    }
+    ports
+}
+
+impl Threat for PortScan {
+    fn ingest(&mut self, app_packet: &AppPacket) {
+        if app_packet.direction != TrafficDirection::Ingress {
+            return;
+        }
+
+        let NetworkPacket::Ip(ip_packet) = app_packet.frame.payload else {
+            return;
+        };
+
+        let (src_ip, dst_ip, proto) = match ip_packet {
+            IpPacket::V4(ipv4_packet) => (
+                IpAddr::V4(ipv4_packet.src_ip),
+                IpAddr::V4(ipv4_packet.dst_ip),
+                ipv4_packet.proto,
+            ),
+            IpPacket::V6(ipv6_packet) => (
+                IpAddr::V6(ipv6_packet.src_ip),
+                IpAddr::V6(ipv6_packet.dst_ip),
+                ipv6_packet.proto,
+            ),
+        };
+
+        let IpProto::Tcp(tcp_packet) = proto else {
+            return;
+        };
+
+        if tcp_packet.syn == 1 && tcp_packet.ack == 0 {
+            self.sources
+                .entry(src_ip)
+                .or_default()
+                .hosts
+                .entry(dst_ip)
+                .or_default()
+                .insert(tcp_packet.dst_port);
+        } else if let Some(kind) = stealth_kind(&tcp_packet) {
+            self.sources
+                .entry(src_ip)
+                .or_default()
+                .stealth
+                .entry(kind)
+                .or_default()
+                .insert(tcp_packet.dst_port);
+        }
+    }
+
+    fn evaluate(&mut self, _window: Duration) -> Vec<Finding> {
+        let sources = std::mem::take(&mut self.sources);
+        let mut findings = Vec::new();
+
+        for (src_ip, probes) in sources {
+            // Vertical, many ports on one host
+            for (host, ports) in &probes.hosts {
+                if ports.len() >= VERTICAL_PORTS {
+                    findings.push(Finding {
+                        threat: "Port Scan",
+                        severity: Severity::from_rate(ports.len() as f64, VERTICAL_PORTS as f64),
+                        source: Some(src_ip),
+                        count: ports.len(),
+                        details: format!(
+                            "Vertical, {} ports on {host}: {}",
+                            ports.len(),
+                            format_ports(ports)
+                        ),
+                    });
+                }
+            }
+
+            // Horizontal, one port on many hosts
+            let hosts_by_port = probes
+                .hosts
+                .values()
+                .flatten()
+                .counts()
+                .into_iter()
+                .filter(|(_, nb_hosts)| *nb_hosts >= HORIZONTAL_HOSTS);
+
+            for (port, nb_hosts) in hosts_by_port {
+                findings.push(Finding {
+                    threat: "Port Scan",
+                    severity: Severity::from_rate(nb_hosts as f64, HORIZONTAL_HOSTS as f64),
+                    source: Some(src_ip),
+                    count: nb_hosts,
+                    details: format!("Horizontal, port {port} on {nb_hosts} hosts"),
+                });
+            }
+
+            for (kind, ports) in &probes.stealth {
+                if ports.len() >= STEALTH_PORTS {
+                    // Crafted packets, never sent by accident
+                    let severity = Severity::from_rate(ports.len() as f64, STEALTH_PORTS as f64)
+                        .max(Severity::Medium);
+                    findings.push(Finding {
+                        threat: "Port Scan",
+                        severity,
+                        source: Some(src_ip),
+                        count: ports.len(),
+                        details: format!(
+                            "{kind} scan, {} ports: {}",
+                            ports.len(),
+                            format_ports(ports)
+                        ),
+                    });
+                }
+            }
+        }
+
+        findings
+    }
+}
@@ -1,61 +1,77 @@
-use std::net::IpAddr;
-
-use ratatui::{
-    buffer::Buffer,
-    layout::{Alignment, Constraint, Flex, Rect},
-    style::{Style, Stylize},
-    text::Line,
-    widgets::{Block, Borders, Row, Table},
-};
+use std::{net::IpAddr, time::Duration};
 
 use rustc_hash::FxHashMap as HashMap;
 
-use crate::section::alert::Threat;
+use crate::packet::{
+    AppPacket, NetworkPacket,
+    direction::TrafficDirection,
+    network::{IpPacket, ip::IpProto},
+};
+
+use super::{Finding, Severity, Threat};
+
+// SYN packets per second of a source
+const SYN_RATE: f64 = 50.0;
+
+// Share of the SYN packets in the TCP packets of a source.
+// The legitimate clients complete their handshakes.
+const SYN_RATIO: f64 = 0.8;
+
+#[derive(Debug, Default)]
+struct SourceCounts {
+    syn: usize,
+    tcp: usize,
+}
 
-#[derive(Debug)]
+// Connection attempts without handshake completion
+#[derive(Debug, Default)]
 pub struct SynFlood {
-    pub map: HashMap<IpAddr, usize>,
+    sources: HashMap<IpAddr, SourceCounts>,
 }
 
-impl Threat for SynFlood {}
-
-impl ratatui::widgets::WidgetRef for SynFlood {
-    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
-        let mut ips: Vec<(IpAddr, usize)> = { self.map.clone().into_iter().collect() };
-
-        ips.sort_by(|a, b| b.1.cmp(&a.1));
-
-        ips.retain(|(_, count)| *count > 10_000);
-
-        let top_3_ips = ips.into_iter().take(3);
-
-        let widths = [Constraint::Min(30), Constraint::Min(20)];
-
-        let rows = top_3_ips.map(|(ip, count)| {
-            Row::new(vec![
-                Line::from(ip.to_string()).centered().bold(),
-                Line::from(count.to_string()).centered(),
-            ])
-        });
-        let table = Table::new(rows, widths)
-            .column_spacing(2)
-            .flex(Flex::SpaceBetween)
-            .header(
-                Row::new(vec![
-                    Line::from("IP Address").centered(),
-                    Line::from("Number of SYN packets").centered(),
-                ])
-                .style(Style::new().bold())
-                .bottom_margin(1),
-            )
-            .block(
-                Block::new()
-                    .title(" SYN Flood Attack ")
-                    .borders(Borders::all())
-                    .border_style(Style::new().yellow())
-                    .title_alignment(Alignment::Center),
-            );
-
-        table.render_ref(area, buf);
+impl Threat for SynFlood {
+    fn ingest(&mut self, app_packet: &AppPacket) {
+        if app_packet.direction != TrafficDirection::Ingress {
+            return;
+        }
+
+        let NetworkPacket::Ip(ip_packet) = app_packet.frame.payload else {
+            return;
+        };
+
+        let (src_ip, proto) = match ip_packet {
+            IpPacket::V4(ipv4_packet) => (IpAddr::V4(ipv4_packet.src_ip), ipv4_packet.proto),
+            IpPacket::V6(ipv6_packet) => (IpAddr::V6(ipv6_packet.src_ip), ipv6_packet.proto),
+        };
+
+        if let IpProto::Tcp(tcp_packet) = proto {
+            let counts = self.sources.entry(src_ip).or_default();
+            counts.tcp += 1;
+            if tcp_packet.syn == 1 && tcp_packet.ack == 0 {
+                counts.syn += 1;
+            }
+        }
+    }
+
+    fn evaluate(&mut self, window: Duration) -> Vec<Finding> {
+        let sources = std::mem::take(&mut self.sources);
+
+        sources
+            .into_iter()
+            .filter_map(|(ip, counts)| {
+                let rate = counts.syn as f64 / window.as_secs_f64();
+                if rate < SYN_RATE || (counts.syn as f64) < SYN_RATIO * counts.tcp as f64 {
+                    return None;
+                }
+
+                Some(Finding {
+                    threat: "SYN Flood",
+                    severity: Severity::from_rate(rate, SYN_RATE),
+                    source: Some(ip),
+                    count: counts.syn,
+                    details: format!("{rate:.0} SYN/s"),
+                })
+            })
+            .collect()
     }
 }
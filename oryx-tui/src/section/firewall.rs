@@ -1,84 +1,496 @@
+pub mod draft;
+pub mod group;
+pub mod protocol;
+pub mod rate;
+pub mod ttl;
+
+use chrono::{DateTime, Utc};
 use core::fmt::Display;
 use crossterm::event::{Event, KeyCode, KeyEvent};
+use itertools::Itertools;
 use log::{error, info};
-use oryx_common::MAX_FIREWALL_RULES;
+use oryx_common::MAX_SUBNET_ENTRIES;
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Flex, Layout, Margin, Rect},
     style::{Color, Style, Stylize},
-    text::{Line, Text},
+    text::{Line, Span, Text},
     widgets::{Block, Borders, Cell, Clear, HighlightSpacing, Padding, Row, Table, TableState},
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json;
-use std::{fs, net::IpAddr, num::ParseIntError, os::unix::fs::chown, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    os::unix::fs::chown,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
 use tui_input::{Input, backend::crossterm::EventHandler};
 use uuid;
 
-use crate::{app::AppResult, notification::Notification, packet::direction::TrafficDirection};
+use crate::{
+    app::AppResult, ebpf::firewall::subnet_entries, filter::expression::parse_net,
+    notification::Notification, packet::direction::TrafficDirection,
+};
+
+use super::{alert::response::BlockRequest, flows::format_bytes};
+use draft::RuleDraft;
+use group::RuleGroup;
+use protocol::RuleProtocol;
+use rate::RateLimit;
+use ttl::RuleTtl;
 
 #[derive(Debug, Clone)]
 pub enum FirewallSignal {
     Rule(FirewallRule),
+    Policy(FirewallPolicy),
     Kill,
 }
 
+// Changes made to the rules by the firewall threads
+#[derive(Debug, Clone)]
+pub enum FirewallEvent {
+    // Disabled past its expiry
+    Expired(uuid::Uuid),
+    // Does not fit in the eBPF maps, with the reason
+    Rejected(uuid::Uuid, String),
+}
+
+// Applied to the IP packets not matching any rule of a direction
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FirewallPolicy {
+    #[default]
+    Accept,
+    Deny,
+}
+
+impl Display for FirewallPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FirewallPolicy::Accept => write!(f, "Accept"),
+            FirewallPolicy::Deny => write!(f, "Deny"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum RuleAction {
+    #[default]
+    Block,
+    Allow,
+    // Drops the packets of each remote address over the rate
+    Limit(RateLimit),
+}
+
+impl Display for RuleAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleAction::Block => write!(f, "Block"),
+            RuleAction::Allow => write!(f, "Allow"),
+            RuleAction::Limit(rate) => write!(f, "Limit {rate}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "SavedFirewallRule")]
 pub struct FirewallRule {
-    id: uuid::Uuid,
+    pub id: uuid::Uuid,
     name: String,
     pub enabled: bool,
-    pub ip: IpAddr,
-    pub port: BlockedPort,
+    pub local: RuleEndpoint,
+    pub remote: RuleEndpoint,
+    pub protocol: RuleProtocol,
+    direction: TrafficDirection,
+    pub action: RuleAction,
+    pub ttl: Option<RuleTtl>,
+    // Replaces the remote address
+    pub group: Option<RuleGroup>,
+    // Set when a rule with a TTL is enabled
+    #[serde(skip)]
+    pub expires_at: Option<DateTime<Utc>>,
+    // Created by the auto block, removed on expiry and never saved
+    #[serde(skip)]
+    automatic: bool,
+}
+
+impl FirewallRule {
+    fn applies_to(&self, is_ipv4: bool) -> bool {
+        match (self.local.ip, self.protocol) {
+            (Some(ip), _) => ip.addr().is_ipv4() == is_ipv4,
+            (None, RuleProtocol::Icmp(_)) => is_ipv4,
+            (None, RuleProtocol::Icmpv6(_)) => !is_ipv4,
+            (None, _) => true,
+        }
+    }
+
+    // Remote subnets matched by the rule, /0 for any remote address
+    pub fn remote_subnets(&self) -> Vec<IpNet> {
+        let subnets = match (&self.group, self.remote.ip) {
+            (Some(group), _) => group.subnets.clone(),
+            (None, Some(ip)) => vec![ip],
+            (None, None) => vec![
+                IpNet::new(Ipv4Addr::UNSPECIFIED.into(), 0),
+                IpNet::new(Ipv6Addr::UNSPECIFIED.into(), 0),
+            ],
+        };
+
+        subnets
+            .into_iter()
+            .filter(|subnet| self.applies_to(subnet.addr().is_ipv4()))
+            .collect()
+    }
+
+    fn remote_to_string(&self) -> String {
+        match &self.group {
+            Some(group) => format!("@{} ({})", group.name(), group.subnets.len()),
+            None => self.remote.to_string(),
+        }
+    }
+}
+
+// The rules saved before the local matcher only had the remote `ip` and `port`
+#[derive(Deserialize)]
+struct SavedFirewallRule {
+    id: uuid::Uuid,
+    name: String,
+    enabled: bool,
+    #[serde(default)]
+    local: RuleEndpoint,
+    remote: Option<RuleEndpoint>,
+    ip: Option<IpNet>,
+    port: Option<BlockedPort>,
+    #[serde(default)]
+    protocol: RuleProtocol,
     direction: TrafficDirection,
+    #[serde(default)]
+    action: RuleAction,
+    #[serde(default)]
+    ttl: Option<RuleTtl>,
+    #[serde(default)]
+    group: Option<RuleGroup>,
+}
+
+impl From<SavedFirewallRule> for FirewallRule {
+    fn from(rule: SavedFirewallRule) -> Self {
+        Self {
+            id: rule.id,
+            name: rule.name,
+            enabled: rule.enabled,
+            local: rule.local,
+            remote: rule.remote.unwrap_or(RuleEndpoint {
+                ip: rule.ip,
+                port: rule.port.unwrap_or(BlockedPort::All),
+            }),
+            protocol: rule.protocol,
+            direction: rule.direction,
+            action: rule.action,
+            ttl: rule.ttl,
+            group: rule.group,
+            expires_at: None,
+            automatic: false,
+        }
+    }
+}
+
+// The local or remote side of the packets matched by a rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleEndpoint {
+    // None -> any address
+    pub ip: Option<IpNet>,
+    pub port: BlockedPort,
+}
+
+impl Default for RuleEndpoint {
+    fn default() -> Self {
+        Self {
+            ip: None,
+            port: BlockedPort::All,
+        }
+    }
+}
+
+impl RuleEndpoint {
+    pub fn overlaps(&self, other: &RuleEndpoint) -> bool {
+        self.ip == other.ip && self.port.overlaps(&other.port)
+    }
+}
+
+impl Display for RuleEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.ip, &self.port) {
+            (None, BlockedPort::All) => write!(f, "*"),
+            (Some(ip), BlockedPort::All) => write!(f, "{ip}"),
+            (None, port) => write!(f, "*:{port}"),
+            (Some(ip), port) if ip.addr().is_ipv6() => write!(f, "[{ip}]:{port}"),
+            (Some(ip), port) => write!(f, "{ip}:{port}"),
+        }
+    }
+}
+
+impl FromStr for RuleEndpoint {
+    type Err = String;
+
+    // e.g. `*`, `10.0.0.0/8`, `:22`, `*:8000-8100`, `192.168.1.1:80,443`, `[fe80::/10]:22`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        let (ip, port) = if let Some(rest) = s.strip_prefix('[') {
+            let (ip, rest) = rest
+                .split_once(']')
+                .ok_or(format!("Invalid endpoint {s}"))?;
+            match rest {
+                "" => (ip, None),
+                _ => (
+                    ip,
+                    Some(
+                        rest.strip_prefix(':')
+                            .ok_or(format!("Invalid endpoint {s}"))?,
+                    ),
+                ),
+            }
+        } else if s.matches(':').count() == 1 {
+            let (ip, port) = s.split_once(':').unwrap();
+            (ip, Some(port))
+        } else {
+            // An IPv6 address without port
+            (s, None)
+        };
+
+        let ip = match ip.trim() {
+            "" if port.is_none() => return Err("Empty endpoint".to_string()),
+            "" | "*" => None,
+            ip => Some(IpNet::from_str(ip)?),
+        };
+
+        let port = match port {
+            Some(port) => BlockedPort::from_str(port)?,
+            None => BlockedPort::All,
+        };
+
+        Ok(Self { ip, port })
+    }
+}
+
+// An address or a subnet in the CIDR notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    pub fn new(addr: IpAddr, prefix: u8) -> Self {
+        // Clear the host bits
+        let addr = match addr {
+            IpAddr::V4(addr) => {
+                let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                IpAddr::V4((addr.to_bits() & mask).into())
+            }
+            IpAddr::V6(addr) => {
+                let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                IpAddr::V6((addr.to_bits() & mask).into())
+            }
+        };
+        Self { addr, prefix }
+    }
+
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    pub fn contains(&self, other: &IpNet) -> bool {
+        self.addr.is_ipv4() == other.addr.is_ipv4()
+            && self.prefix <= other.prefix
+            && IpNet::new(other.addr, self.prefix).addr == self.addr
+    }
+
+    fn is_host(&self) -> bool {
+        match self.addr {
+            IpAddr::V4(_) => self.prefix == 32,
+            IpAddr::V6(_) => self.prefix == 128,
+        }
+    }
+}
+
+impl From<IpAddr> for IpNet {
+    fn from(addr: IpAddr) -> Self {
+        match addr {
+            IpAddr::V4(_) => Self { addr, prefix: 32 },
+            IpAddr::V6(_) => Self { addr, prefix: 128 },
+        }
+    }
+}
+
+impl Display for IpNet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_host() {
+            write!(f, "{}", self.addr)
+        } else {
+            write!(f, "{}/{}", self.addr, self.prefix)
+        }
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = parse_net(s).ok_or(format!("Invalid subnet {s}"))?;
+        Ok(IpNet::new(addr, prefix))
+    }
+}
+
+// Stored as a string, compatible with the rules saved as a plain address
+impl Serialize for IpNet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for IpNet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        IpNet::from_str(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+// Packets matched by a rule, updated by the firewall threads
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RuleStats {
+    pub packets: u64,
+    pub bytes: u64,
+    pub last_hit: Option<DateTime<Utc>>,
+}
+
+pub type FirewallStats = Arc<Mutex<HashMap<uuid::Uuid, RuleStats>>>;
+
+// Size of the eBPF maps of each direction
+#[derive(Debug, Clone, Copy)]
+pub struct FirewallCapacity {
+    // Enabled rules, and remote subnets of each family
+    pub rules: u32,
+    // Port ranges of all the subnets
+    pub entries: u32,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum BlockedPort {
     Single(u16),
+    // Inclusive
+    Range(u16, u16),
+    // Comma separated singles and ranges
+    List(Vec<BlockedPort>),
     All,
 }
 
+impl BlockedPort {
+    // Inclusive port ranges
+    pub fn ranges(&self) -> Vec<(u16, u16)> {
+        match self {
+            BlockedPort::Single(port) => vec![(*port, *port)],
+            BlockedPort::Range(start, end) => vec![(*start, *end)],
+            BlockedPort::List(ports) => ports.iter().flat_map(|port| port.ranges()).collect(),
+            BlockedPort::All => vec![(0, u16::MAX)],
+        }
+    }
+
+    pub fn overlaps(&self, other: &BlockedPort) -> bool {
+        let other_ranges = other.ranges();
+        self.ranges().iter().any(|(start, end)| {
+            other_ranges
+                .iter()
+                .any(|(other_start, other_end)| start <= other_end && other_start <= end)
+        })
+    }
+}
+
 impl Display for BlockedPort {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             BlockedPort::Single(p) => write!(f, "{p}"),
+            BlockedPort::Range(start, end) => write!(f, "{start}-{end}"),
+            BlockedPort::List(ports) => write!(f, "{}", ports.iter().join(",")),
             BlockedPort::All => write!(f, "*"),
         }
     }
 }
 
 impl FromStr for BlockedPort {
-    type Err = ParseIntError;
+    type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s == "*" {
-            Ok(BlockedPort::All)
+        if s.trim() == "*" {
+            return Ok(BlockedPort::All);
+        }
+
+        let parse_port =
+            |port: &str| u16::from_str(port.trim()).map_err(|_| format!("Invalid port {port}"));
+
+        let mut ports = s
+            .split(',')
+            .map(|port| match port.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (parse_port(start)?, parse_port(end)?);
+                    if start > end {
+                        Err(format!("Invalid range {port}"))
+                    } else if start == end {
+                        Ok(BlockedPort::Single(start))
+                    } else {
+                        Ok(BlockedPort::Range(start, end))
+                    }
+                }
+                None => Ok(BlockedPort::Single(parse_port(port)?)),
+            })
+            .collect::<Result<Vec<BlockedPort>, String>>()?;
+
+        if ports.len() == 1 {
+            Ok(ports.remove(0))
         } else {
-            Ok(BlockedPort::Single(u16::from_str(s)?))
+            Ok(BlockedPort::List(ports))
         }
     }
 }
 
 impl Display for FirewallRule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {}", self.ip, self.port)
+        write!(
+            f,
+            "{} local {} remote {}",
+            self.protocol,
+            self.local,
+            self.remote_to_string()
+        )
     }
 }
 #[derive(Debug, Clone, PartialEq)]
 pub enum FocusedInput {
     Name,
-    Ip,
-    Port,
+    Local,
+    Remote,
+    Protocol,
+    Ttl,
     Direction,
+    Action,
+    Rate,
 }
 
 #[derive(Debug, Clone)]
 struct UserInput {
     id: Option<uuid::Uuid>,
     name: UserInputField,
-    ip: UserInputField,
-    port: UserInputField,
+    local: UserInputField,
+    remote: UserInputField,
+    protocol: UserInputField,
+    ttl: UserInputField,
+    rate: UserInputField,
     direction: TrafficDirection,
+    action: RuleAction,
     focus_input: FocusedInput,
 }
 
@@ -93,9 +505,13 @@ impl UserInput {
         Self {
             id: None,
             name: UserInputField::default(),
-            ip: UserInputField::default(),
-            port: UserInputField::default(),
+            local: UserInputField::default(),
+            remote: UserInputField::default(),
+            protocol: UserInputField::default(),
+            ttl: UserInputField::default(),
+            rate: UserInputField::default(),
             direction: TrafficDirection::Ingress,
+            action: RuleAction::Block,
             focus_input: FocusedInput::Name,
         }
     }
@@ -107,30 +523,171 @@ impl UserInput {
         }
     }
 
-    fn validate_ip(&mut self) {
-        self.ip.error = None;
-        if self.ip.field.value().is_empty() {
-            self.ip.error = Some("Required field.".to_string());
-        } else if IpAddr::from_str(self.ip.field.value()).is_err() {
-            self.ip.error = Some("Invalid IP Address.".to_string());
+    fn validate_endpoint(endpoint: &mut UserInputField) {
+        endpoint.error = None;
+        if endpoint.field.value().is_empty() {
+            endpoint.error = Some("Required field.".to_string());
+        } else {
+            match RuleEndpoint::from_str(endpoint.field.value()) {
+                Ok(endpoint_value)
+                    if endpoint_value.port.ranges().len() > MAX_SUBNET_ENTRIES as usize =>
+                {
+                    endpoint.error = Some("Too many ports.".to_string());
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    endpoint.error = Some("Invalid address or port.".to_string());
+                }
+            }
+        }
+    }
+
+    // `@path` in the remote field imports a list of addresses
+    fn group_source(&self) -> Option<PathBuf> {
+        let source = self.remote.field.value().trim().strip_prefix('@')?;
+        match source.strip_prefix("~/") {
+            Some(source) => dirs::home_dir().map(|home| home.join(source)),
+            None => Some(PathBuf::from(source)),
+        }
+    }
+
+    fn group(&self) -> Option<RuleGroup> {
+        self.group_source()
+            .and_then(|source| RuleGroup::import(source).ok())
+    }
+
+    // Any remote address for the groups, their subnets are matched instead
+    fn remote_endpoint(&self) -> Result<RuleEndpoint, String> {
+        match self.group_source() {
+            Some(_) => Ok(RuleEndpoint::default()),
+            None => RuleEndpoint::from_str(self.remote.field.value()),
+        }
+    }
+
+    fn validate_endpoints(&mut self) {
+        Self::validate_endpoint(&mut self.local);
+        match self.group_source() {
+            Some(source) if source.as_os_str().is_empty() => {
+                self.remote.error = Some("Required file path.".to_string());
+            }
+            Some(source) => {
+                self.remote.error = RuleGroup::import(source).err();
+            }
+            None => Self::validate_endpoint(&mut self.remote),
+        }
+
+        let (Ok(local), Ok(remote)) = (
+            RuleEndpoint::from_str(self.local.field.value()),
+            self.remote_endpoint(),
+        ) else {
+            return;
+        };
+
+        if self.remote.error.is_some() {
+            return;
+        }
+
+        if let (Some(local_ip), Some(remote_ip)) = (local.ip, remote.ip) {
+            if local_ip.addr().is_ipv4() != remote_ip.addr().is_ipv4() {
+                self.remote.error = Some("Mixed IPv4 and IPv6.".to_string());
+                return;
+            }
+        }
+
+        // One entry per pair of port ranges in the eBPF maps
+        if local.port.ranges().len() * remote.port.ranges().len() > MAX_SUBNET_ENTRIES as usize {
+            self.remote.error = Some("Too many ports.".to_string());
+        }
+    }
+
+    fn validate_protocol(&mut self) {
+        self.protocol.error = None;
+
+        let endpoints = [
+            RuleEndpoint::from_str(self.local.field.value()),
+            self.remote_endpoint(),
+        ];
+        let has_ip = |is_ipv4: bool| {
+            endpoints.iter().any(|endpoint| {
+                endpoint.as_ref().is_ok_and(|endpoint| {
+                    endpoint.ip.is_some_and(|ip| ip.addr().is_ipv4() == is_ipv4)
+                })
+            })
+        };
+        let has_ports = endpoints.iter().any(|endpoint| {
+            endpoint
+                .as_ref()
+                .is_ok_and(|endpoint| endpoint.port != BlockedPort::All)
+        });
+
+        match RuleProtocol::from_str(self.protocol.field.value()) {
+            Ok(RuleProtocol::Icmp(_)) | Ok(RuleProtocol::Icmpv6(_)) if has_ports => {
+                self.protocol.error = Some("ICMP has no ports.".to_string());
+            }
+            Ok(RuleProtocol::Icmp(_)) => {
+                if has_ip(false) {
+                    self.protocol.error = Some("Use icmpv6 for IPv6.".to_string());
+                }
+            }
+            Ok(RuleProtocol::Icmpv6(_)) => {
+                if has_ip(true) {
+                    self.protocol.error = Some("Use icmp for IPv4.".to_string());
+                }
+            }
+            Ok(_) => {}
+            Err(_) => {
+                self.protocol.error = Some("Invalid protocol.".to_string());
+            }
         }
     }
 
-    fn validate_port(&mut self) {
-        self.port.error = None;
-        if self.port.field.value().is_empty() {
-            self.port.error = Some("Required field.".to_string());
-        } else if BlockedPort::from_str(self.port.field.value()).is_err() {
-            self.port.error = Some("Invalid Port number.".to_string());
+    fn validate_ttl(&mut self) {
+        self.ttl.error = None;
+        // Optional
+        if !self.ttl.field.value().trim().is_empty()
+            && RuleTtl::from_str(self.ttl.field.value()).is_err()
+        {
+            self.ttl.error = Some("Invalid duration, e.g. 15m.".to_string());
+        }
+    }
+
+    fn ttl(&self) -> Option<RuleTtl> {
+        RuleTtl::from_str(self.ttl.field.value()).ok()
+    }
+
+    fn validate_rate(&mut self) {
+        self.rate.error = None;
+        // Only used by the Limit action
+        if matches!(self.action, RuleAction::Limit(_))
+            && RateLimit::from_str(self.rate.field.value()).is_err()
+        {
+            self.rate.error = Some("Invalid rate, e.g. 100pps.".to_string());
+        }
+    }
+
+    fn action(&self) -> RuleAction {
+        match self.action {
+            RuleAction::Limit(_) => {
+                RuleAction::Limit(RateLimit::from_str(self.rate.field.value()).unwrap())
+            }
+            action => action,
         }
     }
 
     fn validate(&mut self) -> AppResult<()> {
         self.validate_name();
-        self.validate_ip();
-        self.validate_port();
-
-        if self.name.error.is_some() || self.ip.error.is_some() || self.port.error.is_some() {
+        self.validate_endpoints();
+        self.validate_protocol();
+        self.validate_ttl();
+        self.validate_rate();
+
+        if self.name.error.is_some()
+            || self.local.error.is_some()
+            || self.remote.error.is_some()
+            || self.protocol.error.is_some()
+            || self.ttl.error.is_some()
+            || self.rate.error.is_some()
+        {
             return Err("Valdidation Error".into());
         }
         Ok(())
@@ -168,18 +725,36 @@ impl UserInput {
                         }
                     })
                     .fg(Color::Black),
-                Cell::from(self.ip.field.to_string())
+                Cell::from(self.local.field.to_string())
+                    .bg({
+                        if self.focus_input == FocusedInput::Local {
+                            Color::Gray
+                        } else {
+                            Color::DarkGray
+                        }
+                    })
+                    .fg(Color::Black),
+                Cell::from(self.remote.field.to_string())
+                    .bg({
+                        if self.focus_input == FocusedInput::Remote {
+                            Color::Gray
+                        } else {
+                            Color::DarkGray
+                        }
+                    })
+                    .fg(Color::Black),
+                Cell::from(self.protocol.field.to_string())
                     .bg({
-                        if self.focus_input == FocusedInput::Ip {
+                        if self.focus_input == FocusedInput::Protocol {
                             Color::Gray
                         } else {
                             Color::DarkGray
                         }
                     })
                     .fg(Color::Black),
-                Cell::from(self.port.field.to_string())
+                Cell::from(self.ttl.field.to_string())
                     .bg({
-                        if self.focus_input == FocusedInput::Port {
+                        if self.focus_input == FocusedInput::Ttl {
                             Color::Gray
                         } else {
                             Color::DarkGray
@@ -195,12 +770,37 @@ impl UserInput {
                         }
                     })
                     .fg(Color::Black),
+                Cell::from(match self.action {
+                    RuleAction::Limit(_) => "Limit".to_string(),
+                    action => action.to_string(),
+                })
+                .bg({
+                    if self.focus_input == FocusedInput::Action {
+                        Color::Gray
+                    } else {
+                        Color::DarkGray
+                    }
+                })
+                .fg(Color::Black),
+                Cell::from(self.rate.field.to_string())
+                    .bg({
+                        if self.focus_input == FocusedInput::Rate {
+                            Color::Gray
+                        } else {
+                            Color::DarkGray
+                        }
+                    })
+                    .fg(Color::Black),
             ]),
             Row::new(vec![
                 Cell::new(""),
                 Cell::new(""),
                 Cell::new(""),
                 Cell::new(""),
+                Cell::new(""),
+                Cell::new(""),
+                Cell::new(""),
+                Cell::new(""),
             ]),
             Row::new(vec![
                 Cell::from({
@@ -212,7 +812,7 @@ impl UserInput {
                 })
                 .red(),
                 Cell::from({
-                    if let Some(error) = &self.ip.error {
+                    if let Some(error) = &self.local.error {
                         error.to_string()
                     } else {
                         String::new()
@@ -220,31 +820,64 @@ impl UserInput {
                 })
                 .red(),
                 Cell::from({
-                    if let Some(error) = &self.port.error {
+                    if let Some(error) = &self.remote.error {
                         error.to_string()
                     } else {
                         String::new()
                     }
                 })
                 .red(),
+                Cell::from({
+                    if let Some(error) = &self.protocol.error {
+                        error.to_string()
+                    } else {
+                        String::new()
+                    }
+                })
+                .red(),
+                Cell::from({
+                    if let Some(error) = &self.ttl.error {
+                        error.to_string()
+                    } else {
+                        String::new()
+                    }
+                })
+                .red(),
+                Cell::new(""),
                 Cell::new(""),
+                Cell::from({
+                    if let Some(error) = &self.rate.error {
+                        error.to_string()
+                    } else {
+                        String::new()
+                    }
+                })
+                .red(),
             ]),
         ];
 
         let widths = [
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
+            Constraint::Ratio(1, 8),
+            Constraint::Ratio(1, 8),
+            Constraint::Ratio(1, 8),
+            Constraint::Ratio(1, 8),
+            Constraint::Ratio(1, 8),
+            Constraint::Ratio(1, 8),
+            Constraint::Ratio(1, 8),
+            Constraint::Ratio(1, 8),
         ];
 
         let table = Table::new(rows, widths)
             .header(
                 Row::new(vec![
                     Line::from("Name").centered(),
-                    Line::from("IP").centered(),
-                    Line::from("Port").centered(),
+                    Line::from("Local").centered(),
+                    Line::from("Remote").centered(),
+                    Line::from("Protocol").centered(),
+                    Line::from("Expiry").centered(),
                     Line::from("Direction").centered(),
+                    Line::from("Action").centered(),
+                    Line::from("Rate").centered(),
                 ])
                 .style(Style::new().bold())
                 .bottom_margin(1),
@@ -266,30 +899,34 @@ impl UserInput {
         frame.render_widget(Clear, block);
         frame.render_widget(table, block);
 
+        // Start of the column `index` out of 8
+        let column_x = |index: u16| block.x + 2 + index * (block.width - 2) / 8;
+        let cursor_y = block.y + 4;
+
         match self.focus_input {
             FocusedInput::Name => {
                 let content_width = self.name.field.visual_cursor() as u16;
-                let cursor_x = block.x + 2 + content_width;
-                let cursor_y = block.y + 4;
-                frame.set_cursor_position((cursor_x, cursor_y));
+                frame.set_cursor_position((column_x(0) + content_width, cursor_y));
             }
-            FocusedInput::Ip => {
-                let content_width = self.ip.field.visual_cursor() as u16;
-                let mut cursor_x = block.x + block.width / 4 + 1 + content_width;
-                if !block.width.is_multiple_of(2) {
-                    cursor_x += 1;
-                }
-                let cursor_y = block.y + 4;
-                frame.set_cursor_position((cursor_x, cursor_y));
+            FocusedInput::Local => {
+                let content_width = self.local.field.visual_cursor() as u16;
+                frame.set_cursor_position((column_x(1) + content_width, cursor_y));
             }
-            FocusedInput::Port => {
-                let content_width = self.port.field.visual_cursor() as u16;
-                let mut cursor_x = block.x + block.width / 2 + 1 + content_width;
-                if !block.width.is_multiple_of(2) {
-                    cursor_x += 1;
-                }
-                let cursor_y = block.y + 4;
-                frame.set_cursor_position((cursor_x, cursor_y));
+            FocusedInput::Remote => {
+                let content_width = self.remote.field.visual_cursor() as u16;
+                frame.set_cursor_position((column_x(2) + content_width, cursor_y));
+            }
+            FocusedInput::Protocol => {
+                let content_width = self.protocol.field.visual_cursor() as u16;
+                frame.set_cursor_position((column_x(3) + content_width, cursor_y));
+            }
+            FocusedInput::Ttl => {
+                let content_width = self.ttl.field.visual_cursor() as u16;
+                frame.set_cursor_position((column_x(4) + content_width, cursor_y));
+            }
+            FocusedInput::Rate => {
+                let content_width = self.rate.field.visual_cursor() as u16;
+                frame.set_cursor_position((column_x(7) + content_width, cursor_y));
             }
             _ => {}
         };
@@ -304,20 +941,137 @@ impl From<FirewallRule> for UserInput {
                 field: Input::from(rule.name),
                 error: None,
             },
-            ip: UserInputField {
-                field: Input::from(rule.ip.to_string()),
+            local: UserInputField {
+                field: Input::from(rule.local.to_string()),
+                error: None,
+            },
+            remote: UserInputField {
+                field: Input::from(match rule.group {
+                    Some(group) => format!("@{}", group.source.display()),
+                    None => rule.remote.to_string(),
+                }),
                 error: None,
             },
-            port: UserInputField {
-                field: Input::from(rule.port.to_string()),
+            protocol: UserInputField {
+                field: Input::from(rule.protocol.to_string()),
+                error: None,
+            },
+            ttl: UserInputField {
+                field: Input::from(rule.ttl.map(|ttl| ttl.to_string()).unwrap_or_default()),
+                error: None,
+            },
+            rate: UserInputField {
+                field: Input::from(match rule.action {
+                    RuleAction::Limit(rate) => rate.to_string(),
+                    _ => String::new(),
+                }),
                 error: None,
             },
             direction: rule.direction,
+            action: rule.action,
             focus_input: FocusedInput::Name,
         }
     }
 }
 
+// Policies being edited, applied once confirmed
+#[derive(Debug, Clone)]
+struct PolicyInput {
+    ingress: FirewallPolicy,
+    egress: FirewallPolicy,
+    focused_direction: TrafficDirection,
+}
+
+impl PolicyInput {
+    fn toggle(&mut self) {
+        let policy = match self.focused_direction {
+            TrafficDirection::Ingress => &mut self.ingress,
+            TrafficDirection::Egress => &mut self.egress,
+        };
+        *policy = match policy {
+            FirewallPolicy::Accept => FirewallPolicy::Deny,
+            FirewallPolicy::Deny => FirewallPolicy::Accept,
+        };
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Fill(1),
+            ])
+            .flex(ratatui::layout::Flex::SpaceBetween)
+            .split(frame.area());
+
+        let block = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(50),
+                Constraint::Fill(1),
+            ])
+            .flex(ratatui::layout::Flex::SpaceBetween)
+            .split(layout[1])[1];
+
+        let rows = [
+            (TrafficDirection::Ingress, self.ingress),
+            (TrafficDirection::Egress, self.egress),
+        ]
+        .map(|(direction, policy)| {
+            Row::new(vec![
+                Line::from(direction.to_string()).centered().bold(),
+                match policy {
+                    FirewallPolicy::Accept => Line::from("Accept").centered().green(),
+                    FirewallPolicy::Deny => Line::from("Deny").centered().red(),
+                },
+                match policy {
+                    FirewallPolicy::Accept => Line::from("Pass unless blocked"),
+                    FirewallPolicy::Deny => Line::from("Drop unless allowed"),
+                },
+            ])
+        });
+
+        let mut state = TableState::default().with_selected(match self.focused_direction {
+            TrafficDirection::Ingress => 0,
+            TrafficDirection::Egress => 1,
+        });
+
+        let widths = [
+            Constraint::Length(10),
+            Constraint::Length(8),
+            Constraint::Fill(1),
+        ];
+
+        let table = Table::new(rows, widths)
+            .column_spacing(2)
+            .row_highlight_style(Style::default().bg(Color::DarkGray))
+            .header(
+                Row::new(vec![
+                    Line::from("Direction").centered().blue(),
+                    Line::from("Policy").centered().blue(),
+                    Line::from("IP packets").blue(),
+                ])
+                .style(Style::new().bold())
+                .bottom_margin(1),
+            )
+            .block(
+                Block::default()
+                    .title(" Firewall Policy ")
+                    .bold()
+                    .title_alignment(ratatui::layout::Alignment::Center)
+                    .borders(Borders::all())
+                    .border_type(ratatui::widgets::BorderType::Thick)
+                    .border_style(Style::default().green())
+                    .padding(Padding::uniform(1)),
+            );
+
+        frame.render_widget(Clear, block);
+        frame.render_stateful_widget(table, block, &mut state);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Firewall {
     rules: Vec<FirewallRule>,
@@ -325,12 +1079,22 @@ pub struct Firewall {
     user_input: Option<UserInput>,
     ingress_sender: kanal::Sender<FirewallSignal>,
     egress_sender: kanal::Sender<FirewallSignal>,
+    stats: FirewallStats,
+    ingress_policy: FirewallPolicy,
+    egress_policy: FirewallPolicy,
+    policy_input: Option<PolicyInput>,
+    capacity: FirewallCapacity,
+    // No eBPF programs are loaded when reading a capture file
+    is_disabled: bool,
 }
 
 impl Firewall {
     pub fn new(
         ingress_sender: kanal::Sender<FirewallSignal>,
         egress_sender: kanal::Sender<FirewallSignal>,
+        stats: FirewallStats,
+        capacity: FirewallCapacity,
+        is_disabled: bool,
     ) -> Self {
         let rules_list: Vec<FirewallRule> = match Self::load_saved_rules() {
             Ok(saved_rules) => saved_rules,
@@ -346,6 +1110,12 @@ impl Firewall {
             user_input: None,
             ingress_sender,
             egress_sender,
+            stats,
+            ingress_policy: FirewallPolicy::default(),
+            egress_policy: FirewallPolicy::default(),
+            policy_input: None,
+            capacity,
+            is_disabled,
         }
     }
 
@@ -353,10 +1123,96 @@ impl Firewall {
         self.user_input = Some(UserInput::new());
     }
 
+    pub fn import_group(&mut self) {
+        self.user_input = Some(UserInput {
+            remote: UserInputField {
+                field: Input::from("@"),
+                error: None,
+            },
+            ..UserInput::new()
+        });
+    }
+
+    // Opens the rule popup with a rule built from another section
+    pub fn draft_rule(
+        &mut self,
+        draft: RuleDraft,
+        sender: kanal::Sender<crate::event::Event>,
+    ) -> AppResult<()> {
+        if self.is_disabled {
+            Notification::send(
+                "The firewall is disabled",
+                crate::notification::NotificationLevel::Warning,
+                sender,
+            )?;
+            return Err("The firewall is disabled".into());
+        }
+
+        let field = |value: String| UserInputField {
+            field: Input::from(value),
+            error: None,
+        };
+
+        self.user_input = Some(UserInput {
+            name: field(draft.name),
+            local: field(draft.local),
+            remote: field(draft.remote),
+            protocol: field(draft.protocol.to_string()),
+            direction: draft.direction,
+            ..UserInput::new()
+        });
+
+        Ok(())
+    }
+
+    // Each remote subnet of the enabled rules takes an entry in the tries of its direction,
+    // pointing to the port ranges of its rules and of the subnets containing it
+    fn capacity_error(&self, direction: TrafficDirection) -> Option<String> {
+        let rules: Vec<(&FirewallRule, u16)> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.enabled && rule.direction == direction)
+            .map(|rule| (rule, 0))
+            .collect();
+
+        if rules.len() > self.capacity.rules as usize {
+            return Some(format!("more than {} enabled rules", self.capacity.rules));
+        }
+
+        let subnets = subnet_entries(&rules);
+
+        let ipv4_subnets = subnets
+            .keys()
+            .filter(|subnet| subnet.addr().is_ipv4())
+            .count();
+        if ipv4_subnets.max(subnets.len() - ipv4_subnets) > self.capacity.rules as usize {
+            return Some(format!("more than {} remote subnets", self.capacity.rules));
+        }
+
+        if let Some((subnet, entries)) = subnets
+            .iter()
+            .find(|(_, entries)| entries.len() > MAX_SUBNET_ENTRIES as usize)
+        {
+            return Some(format!(
+                "{} port ranges on {subnet}, the limit is {MAX_SUBNET_ENTRIES}",
+                entries.len()
+            ));
+        }
+
+        let nb_entries: usize = subnets.values().map(Vec::len).sum();
+        (nb_entries > self.capacity.entries as usize).then(|| {
+            format!(
+                "{nb_entries} port ranges, the limit is {}",
+                self.capacity.entries
+            )
+        })
+    }
+
     pub fn save_rules(&mut self) -> AppResult<()> {
         info!("Saving Firewall Rules");
 
-        let json = serde_json::to_string(&self.rules)?;
+        let rules: Vec<&FirewallRule> = self.rules.iter().filter(|rule| !rule.automatic).collect();
+        let json = serde_json::to_string(&rules)?;
 
         let user_uid = unsafe { libc::geteuid() };
 
@@ -396,31 +1252,26 @@ impl Firewall {
     }
 
     fn validate_duplicate_rules(rules: &[FirewallRule], user_input: &UserInput) -> AppResult<()> {
-        if let Some(exiting_rule_with_same_ip) = rules.iter().find(|rule| {
-            rule.ip == IpAddr::from_str(user_input.ip.field.value()).unwrap()
-                && rule.direction == user_input.direction
+        let new_local = RuleEndpoint::from_str(user_input.local.field.value()).unwrap();
+        let new_remote = user_input.remote_endpoint().unwrap();
+        let new_group = user_input.group_source();
+        let new_protocol = RuleProtocol::from_str(user_input.protocol.field.value()).unwrap();
+
+        if rules.iter().any(|rule| {
+            rule.direction == user_input.direction
                 && match user_input.id {
                     Some(uuid) => rule.id != uuid,
                     None => true,
                 }
-        }) {
-            let new_port = BlockedPort::from_str(user_input.port.field.value()).unwrap();
-
-            if exiting_rule_with_same_ip.port == new_port {
-                return Err("Rule validation error".into());
-            }
-
-            match exiting_rule_with_same_ip.port {
-                BlockedPort::Single(_) => {
-                    if new_port == BlockedPort::All {
-                        return Err("Rule validation error".into());
-                    }
-                }
-
-                BlockedPort::All => {
-                    return Err("Rule validation error".into());
+                && rule.protocol.overlaps(&new_protocol)
+                && rule.local.overlaps(&new_local)
+                && match (&rule.group, &new_group) {
+                    (None, None) => rule.remote.overlaps(&new_remote),
+                    (Some(group), Some(source)) => group.source == *source,
+                    _ => false,
                 }
-            }
+        }) {
+            return Err("Rule validation error".into());
         }
 
         Ok(())
@@ -445,11 +1296,239 @@ impl Firewall {
         });
     }
 
+    fn remove_automatic_rule(&mut self, index: usize) {
+        let rule = self.rules.remove(index);
+        self.stats.lock().unwrap().remove(&rule.id);
+
+        if let Some(i) = self.state.selected() {
+            self.state
+                .select((!self.rules.is_empty()).then(|| i.min(self.rules.len() - 1)));
+        }
+    }
+
+    // Mirrors the changes made by the firewall threads
+    pub fn handle_event(
+        &mut self,
+        event: FirewallEvent,
+        sender: kanal::Sender<crate::event::Event>,
+    ) -> AppResult<()> {
+        match event {
+            FirewallEvent::Expired(rule_id) => {
+                let now = Utc::now();
+
+                // Not when enabled again with a later expiry in the meantime
+                let Some(index) = self.rules.iter().position(|rule| {
+                    rule.id == rule_id
+                        && rule.enabled
+                        && rule.expires_at.is_some_and(|expires_at| expires_at <= now)
+                }) else {
+                    return Ok(());
+                };
+
+                let rule = &mut self.rules[index];
+                rule.enabled = false;
+                rule.expires_at = None;
+
+                info!("Firewall rule {} expired", rule.name);
+                Notification::send(
+                    format!("Firewall rule {} expired", rule.name),
+                    crate::notification::NotificationLevel::Info,
+                    sender,
+                )?;
+
+                if rule.automatic {
+                    self.remove_automatic_rule(index);
+                }
+            }
+
+            FirewallEvent::Rejected(rule_id, reason) => {
+                let Some(index) = self
+                    .rules
+                    .iter()
+                    .position(|rule| rule.id == rule_id && rule.enabled)
+                else {
+                    return Ok(());
+                };
+
+                let rule = &mut self.rules[index];
+                rule.enabled = false;
+                rule.expires_at = None;
+
+                Notification::send(
+                    format!("Firewall rule {} not applied: {reason}", rule.name),
+                    crate::notification::NotificationLevel::Error,
+                    sender,
+                )?;
+
+                if rule.automatic {
+                    self.remove_automatic_rule(index);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Blocks an ingress source flagged by the detectors until the TTL expires
+    pub fn auto_block(
+        &mut self,
+        request: &BlockRequest,
+        sender: kanal::Sender<crate::event::Event>,
+    ) -> AppResult<()> {
+        if self.is_disabled {
+            return Ok(());
+        }
+
+        let remote = RuleEndpoint {
+            ip: Some(IpNet::from(request.ip)),
+            port: BlockedPort::All,
+        };
+        let expires_at = request.ttl.expires_at();
+
+        // Still attacking, the block is extended
+        if let Some(rule) = self
+            .rules
+            .iter_mut()
+            .find(|rule| rule.automatic && rule.enabled && rule.remote == remote)
+        {
+            rule.expires_at = expires_at;
+            self.ingress_sender
+                .send(FirewallSignal::Rule(rule.clone()))?;
+            return Ok(());
+        }
+
+        self.rules.push(FirewallRule {
+            id: uuid::Uuid::new_v4(),
+            name: format!("Auto {} {}", request.threat, request.ip),
+            enabled: true,
+            local: RuleEndpoint::default(),
+            remote,
+            protocol: RuleProtocol::Any,
+            direction: TrafficDirection::Ingress,
+            action: RuleAction::Block,
+            ttl: Some(request.ttl),
+            group: None,
+            expires_at,
+            automatic: true,
+        });
+
+        if let Some(error) = self.capacity_error(TrafficDirection::Ingress) {
+            self.rules.pop();
+            info!(
+                "Firewall capacity reached, {error}, {} not blocked",
+                request.ip
+            );
+            Notification::send(
+                format!("Firewall capacity reached, {} not blocked", request.ip),
+                crate::notification::NotificationLevel::Warning,
+                sender,
+            )?;
+            return Ok(());
+        }
+
+        let rule = self.rules.last().unwrap();
+        self.ingress_sender
+            .send(FirewallSignal::Rule(rule.clone()))?;
+
+        info!(
+            "Blocked {} for {}: {} ({})",
+            request.ip, request.ttl, request.threat, request.severity
+        );
+        if let Err(e) = request.log() {
+            error!("Failed to write the auto block log. {e}");
+        }
+
+        Notification::send(
+            format!(
+                "{} blocked for {}: {}",
+                request.ip, request.ttl, request.threat
+            ),
+            crate::notification::NotificationLevel::Warning,
+            sender,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn handle_policy_popup_keys(
+        &mut self,
+        key_event: KeyEvent,
+        sender: kanal::Sender<crate::event::Event>,
+    ) -> AppResult<()> {
+        let Some(policy_input) = &mut self.policy_input else {
+            return Ok(());
+        };
+
+        match key_event.code {
+            KeyCode::Esc => {
+                self.policy_input = None;
+            }
+
+            KeyCode::Enter => {
+                if policy_input.ingress != self.ingress_policy {
+                    self.ingress_sender
+                        .send(FirewallSignal::Policy(policy_input.ingress))?;
+                    self.ingress_policy = policy_input.ingress;
+                }
+                if policy_input.egress != self.egress_policy {
+                    self.egress_sender
+                        .send(FirewallSignal::Policy(policy_input.egress))?;
+                    self.egress_policy = policy_input.egress;
+                }
+                info!(
+                    "Firewall policy: ingress {}, egress {}",
+                    self.ingress_policy, self.egress_policy
+                );
+                Notification::send(
+                    format!(
+                        "Firewall policy: Ingress {}, Egress {}",
+                        self.ingress_policy, self.egress_policy
+                    ),
+                    crate::notification::NotificationLevel::Info,
+                    sender,
+                )?;
+                self.policy_input = None;
+            }
+
+            KeyCode::Char('j') | KeyCode::Down => {
+                policy_input.focused_direction = TrafficDirection::Egress;
+            }
+
+            KeyCode::Char('k') | KeyCode::Up => {
+                policy_input.focused_direction = TrafficDirection::Ingress;
+            }
+
+            KeyCode::Char(' ') => policy_input.toggle(),
+
+            _ => {}
+        }
+
+        Ok(())
+    }
+
     pub fn handle_keys(
         &mut self,
         key_event: KeyEvent,
         sender: kanal::Sender<crate::event::Event>,
     ) -> AppResult<()> {
+        // The rules of a capture file can be browsed but not applied
+        if self.is_disabled
+            && self.user_input.is_none()
+            && matches!(key_event.code, KeyCode::Char('n' | 'e' | 'g' | 'p' | ' '))
+        {
+            Notification::send(
+                "The firewall is disabled",
+                crate::notification::NotificationLevel::Warning,
+                sender,
+            )?;
+
+            // The other keys open a popup
+            if key_event.code == KeyCode::Char(' ') {
+                return Ok(());
+            }
+            return Err("The firewall is disabled".into());
+        }
+
         if let Some(user_input) = &mut self.user_input {
             match key_event.code {
                 KeyCode::Esc => {
@@ -474,17 +1553,30 @@ impl Firewall {
                             let rule = self.rules.iter_mut().find(|rule| rule.id == id).unwrap();
 
                             rule.name = user_input.name.field.to_string();
-                            rule.ip = IpAddr::from_str(user_input.ip.field.value()).unwrap();
-                            rule.port =
-                                BlockedPort::from_str(user_input.port.field.value()).unwrap();
+                            rule.local =
+                                RuleEndpoint::from_str(user_input.local.field.value()).unwrap();
+                            rule.remote = user_input.remote_endpoint().unwrap();
+                            rule.group = user_input.group();
+                            rule.protocol =
+                                RuleProtocol::from_str(user_input.protocol.field.value()).unwrap();
+                            rule.ttl = user_input.ttl();
                             rule.direction = user_input.direction;
+                            rule.action = user_input.action();
                         } else {
                             let rule = FirewallRule {
                                 id: uuid::Uuid::new_v4(),
                                 name: user_input.name.field.to_string(),
-                                ip: IpAddr::from_str(user_input.ip.field.value()).unwrap(),
-                                port: BlockedPort::from_str(user_input.port.field.value()).unwrap(),
+                                local: RuleEndpoint::from_str(user_input.local.field.value())
+                                    .unwrap(),
+                                remote: user_input.remote_endpoint().unwrap(),
+                                group: user_input.group(),
+                                protocol: RuleProtocol::from_str(user_input.protocol.field.value())
+                                    .unwrap(),
                                 direction: user_input.direction,
+                                action: user_input.action(),
+                                ttl: user_input.ttl(),
+                                expires_at: None,
+                                automatic: false,
                                 enabled: false,
                             };
                             self.rules.push(rule);
@@ -496,10 +1588,16 @@ impl Firewall {
                 KeyCode::Tab => {
                     if let Some(user_input) = &mut self.user_input {
                         match user_input.focus_input {
-                            FocusedInput::Name => user_input.focus_input = FocusedInput::Ip,
-                            FocusedInput::Ip => user_input.focus_input = FocusedInput::Port,
-                            FocusedInput::Port => user_input.focus_input = FocusedInput::Direction,
-                            FocusedInput::Direction => user_input.focus_input = FocusedInput::Name,
+                            FocusedInput::Name => user_input.focus_input = FocusedInput::Local,
+                            FocusedInput::Local => user_input.focus_input = FocusedInput::Remote,
+                            FocusedInput::Remote => user_input.focus_input = FocusedInput::Protocol,
+                            FocusedInput::Protocol => user_input.focus_input = FocusedInput::Ttl,
+                            FocusedInput::Ttl => user_input.focus_input = FocusedInput::Direction,
+                            FocusedInput::Direction => {
+                                user_input.focus_input = FocusedInput::Action
+                            }
+                            FocusedInput::Action => user_input.focus_input = FocusedInput::Rate,
+                            FocusedInput::Rate => user_input.focus_input = FocusedInput::Name,
                         }
                     }
                 }
@@ -508,11 +1606,20 @@ impl Firewall {
                     FocusedInput::Name => {
                         user_input.name.field.handle_event(&Event::Key(key_event));
                     }
-                    FocusedInput::Ip => {
-                        user_input.ip.field.handle_event(&Event::Key(key_event));
+                    FocusedInput::Local => {
+                        user_input.local.field.handle_event(&Event::Key(key_event));
+                    }
+                    FocusedInput::Remote => {
+                        user_input.remote.field.handle_event(&Event::Key(key_event));
                     }
-                    FocusedInput::Port => {
-                        user_input.port.field.handle_event(&Event::Key(key_event));
+                    FocusedInput::Protocol => {
+                        user_input
+                            .protocol
+                            .field
+                            .handle_event(&Event::Key(key_event));
+                    }
+                    FocusedInput::Ttl => {
+                        user_input.ttl.field.handle_event(&Event::Key(key_event));
                     }
                     FocusedInput::Direction => match key_event.code {
                         KeyCode::Char('j') | KeyCode::Down => {
@@ -523,26 +1630,67 @@ impl Firewall {
                         }
                         _ => {}
                     },
+                    // Allow, Block then Limit
+                    FocusedInput::Action => match key_event.code {
+                        KeyCode::Char('j') | KeyCode::Down => {
+                            user_input.action = match user_input.action {
+                                RuleAction::Allow => RuleAction::Block,
+                                _ => RuleAction::Limit(RateLimit::default()),
+                            };
+                        }
+                        KeyCode::Char('k') | KeyCode::Up => {
+                            user_input.action = match user_input.action {
+                                RuleAction::Limit(_) => RuleAction::Block,
+                                _ => RuleAction::Allow,
+                            };
+                        }
+                        _ => {}
+                    },
+                    FocusedInput::Rate => {
+                        user_input.rate.field.handle_event(&Event::Key(key_event));
+                    }
                 },
             }
         } else {
             match key_event.code {
-                KeyCode::Char('n') => {
-                    if self.rules.len() == MAX_FIREWALL_RULES as usize {
-                        Notification::send(
-                            "Max rules reached",
-                            crate::notification::NotificationLevel::Warning,
-                            sender.clone(),
-                        )?;
-                        return Err("Can not edit enabled rule".into());
+                KeyCode::Char('n') | KeyCode::Char('g') => {
+                    if key_event.code == KeyCode::Char('g') {
+                        self.import_group();
+                    } else {
+                        self.add_rule();
                     }
-                    self.add_rule();
+                }
+
+                KeyCode::Char('p') => {
+                    self.policy_input = Some(PolicyInput {
+                        ingress: self.ingress_policy,
+                        egress: self.egress_policy,
+                        focused_direction: TrafficDirection::Ingress,
+                    });
                 }
 
                 KeyCode::Char(' ') => {
                     if let Some(index) = self.state.selected() {
+                        self.rules[index].enabled = !self.rules[index].enabled;
+
+                        let direction = self.rules[index].direction;
+                        if self.rules[index].enabled
+                            && let Some(error) = self.capacity_error(direction)
+                        {
+                            self.rules[index].enabled = false;
+                            Notification::send(
+                                format!("Firewall capacity reached, {error}"),
+                                crate::notification::NotificationLevel::Warning,
+                                sender.clone(),
+                            )?;
+                            return Ok(());
+                        }
+
                         let rule = &mut self.rules[index];
-                        rule.enabled = !rule.enabled;
+                        rule.expires_at = match rule.ttl {
+                            Some(ttl) if rule.enabled => ttl.expires_at(),
+                            _ => None,
+                        };
                         match rule.direction {
                             TrafficDirection::Ingress => {
                                 self.ingress_sender
@@ -605,6 +1753,7 @@ impl Firewall {
                                 .send(FirewallSignal::Rule(rule.clone()))?,
                         }
 
+                        self.stats.lock().unwrap().remove(&rule.id);
                         self.rules.remove(index);
                     }
                 }
@@ -646,8 +1795,50 @@ impl Firewall {
         Ok(())
     }
 
+    fn render_policy(&self, frame: &mut Frame, block: Rect) {
+        let policy = |policy: FirewallPolicy| match policy {
+            FirewallPolicy::Accept => Span::from("Accept").green().bold(),
+            FirewallPolicy::Deny => Span::from("Deny").red().bold(),
+        };
+
+        let text = Text::from(vec![
+            Line::from(vec![
+                Span::from("Policy  ").blue().bold(),
+                Span::from("Ingress "),
+                policy(self.ingress_policy),
+                Span::from("  Egress "),
+                policy(self.egress_policy),
+            ]),
+            Line::from(
+                "Most specific remote subnet first, allow, limit then block on the same subnet, \
+                 the first matching rule decides, otherwise the policy applies. ARP is not filtered.",
+            )
+            .dark_gray(),
+        ]);
+
+        frame.render_widget(text, block);
+    }
+
     pub fn render(&mut self, frame: &mut Frame, block: Rect) {
-        if self.rules.is_empty() {
+        let (policy_block, block) = {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(4), Constraint::Fill(1)])
+                .split(block);
+            (
+                chunks[0].inner(Margin {
+                    horizontal: 2,
+                    vertical: 1,
+                }),
+                chunks[1],
+            )
+        };
+
+        if !self.is_disabled {
+            self.render_policy(frame, policy_block);
+        }
+
+        if self.is_disabled || self.rules.is_empty() {
             let text_block = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
@@ -659,26 +1850,42 @@ impl Firewall {
                 .margin(2)
                 .split(block)[1];
 
-            let text = Text::from("No firewall rules defined. Press `n` to add a new one.")
-                .bold()
-                .centered();
+            let text = if self.is_disabled {
+                Text::from("The firewall is not available when reading a capture file.")
+            } else {
+                Text::from("No firewall rules defined. Press `n` to add a new one.")
+            }
+            .bold()
+            .centered();
             frame.render_widget(text, text_block);
             return;
         }
 
         let widths = [
+            Constraint::Max(30),
+            Constraint::Max(30),
             Constraint::Max(30),
             Constraint::Max(20),
-            Constraint::Length(10),
             Constraint::Length(14),
+            Constraint::Length(16),
             Constraint::Length(14),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
         ];
 
+        let now = Utc::now();
+
+        let stats = self.stats.lock().unwrap().clone();
+
         let rows = self.rules.iter().map(|rule| {
+            let rule_stats = stats.get(&rule.id).copied().unwrap_or_default();
             Row::new(vec![
                 Line::from(rule.name.clone()).centered().bold(),
-                Line::from(rule.ip.to_string()).centered().bold(),
-                Line::from(rule.port.to_string()).centered().bold(),
+                Line::from(rule.local.to_string()).centered().bold(),
+                Line::from(rule.remote_to_string()).centered().bold(),
+                Line::from(rule.protocol.to_string()).centered().bold(),
                 Line::from({
                     match rule.direction {
                         TrafficDirection::Ingress => String::from("Ingress 󰁅  "),
@@ -687,6 +1894,14 @@ impl Firewall {
                 })
                 .centered()
                 .bold(),
+                match rule.action {
+                    RuleAction::Block => Line::from("Block").centered().red().bold(),
+                    RuleAction::Allow => Line::from("Allow").centered().green().bold(),
+                    RuleAction::Limit(rate) => Line::from(format!("Limit {rate}"))
+                        .centered()
+                        .yellow()
+                        .bold(),
+                },
                 Line::from({
                     if rule.enabled {
                         "Enabled".to_string()
@@ -696,6 +1911,23 @@ impl Firewall {
                 })
                 .centered()
                 .bold(),
+                match (rule.expires_at, rule.ttl) {
+                    // Remaining time
+                    (Some(expires_at), _) => Line::from(
+                        RuleTtl((expires_at - now).to_std().unwrap_or_default()).to_string(),
+                    )
+                    .centered()
+                    .yellow(),
+                    (None, Some(ttl)) => Line::from(ttl.to_string()).centered().dark_gray(),
+                    (None, None) => Line::from("-").centered(),
+                },
+                Line::from(rule_stats.packets.to_string()).centered(),
+                Line::from(format_bytes(rule_stats.bytes as usize)).centered(),
+                Line::from(match rule_stats.last_hit {
+                    Some(last_hit) => last_hit.format("%H:%M:%S").to_string(),
+                    None => "-".to_string(),
+                })
+                .centered(),
             ])
         });
 
@@ -710,10 +1942,16 @@ impl Firewall {
             .header(
                 Row::new(vec![
                     Line::from("Name").centered().blue(),
-                    Line::from("IP").centered().blue(),
-                    Line::from("Port").centered().blue(),
+                    Line::from("Local").centered().blue(),
+                    Line::from("Remote").centered().blue(),
+                    Line::from("Protocol").centered().blue(),
                     Line::from("Direction").centered().blue(),
+                    Line::from("Action").centered().blue(),
                     Line::from("Status").centered().blue(),
+                    Line::from("Expires").centered().blue(),
+                    Line::from("Hits").centered().blue(),
+                    Line::from("Bytes").centered().blue(),
+                    Line::from("Last Hit").centered().blue(),
                 ])
                 .style(Style::new().bold())
                 .bottom_margin(1),
@@ -723,7 +1961,7 @@ impl Firewall {
             table,
             block.inner(Margin {
                 horizontal: 2,
-                vertical: 2,
+                vertical: 1,
             }),
             &mut self.state,
         );
@@ -734,4 +1972,10 @@ impl Firewall {
             user_input.render(frame);
         }
     }
+
+    pub fn render_policy_popup(&self, frame: &mut Frame) {
+        if let Some(policy_input) = &self.policy_input {
+            policy_input.render(frame);
+        }
+    }
 }
@@ -0,0 +1,102 @@
+use std::{net::IpAddr, path::Path};
+
+use crate::packet::{
+    AppPacket, NetworkPacket,
+    direction::TrafficDirection,
+    network::{IpPacket, ip::IpProto},
+};
+
+use super::{BlockedPort, IpNet, RuleEndpoint, protocol::RuleProtocol};
+
+// Block rule prefilled from the other sections, reviewed in the rule popup before saving
+#[derive(Debug, Clone)]
+pub struct RuleDraft {
+    pub name: String,
+    pub local: String,
+    pub remote: String,
+    pub protocol: RuleProtocol,
+    pub direction: TrafficDirection,
+}
+
+impl RuleDraft {
+    pub fn block_remote(ip: IpAddr, direction: TrafficDirection) -> Self {
+        Self {
+            name: format!("Block {ip}"),
+            local: RuleEndpoint::default().to_string(),
+            remote: IpNet::from(ip).to_string(),
+            protocol: RuleProtocol::Any,
+            direction,
+        }
+    }
+
+    pub fn block_group(name: &str, source: &Path, direction: TrafficDirection) -> Self {
+        Self {
+            name: name.to_string(),
+            local: RuleEndpoint::default().to_string(),
+            remote: format!("@{}", source.display()),
+            protocol: RuleProtocol::Any,
+            direction,
+        }
+    }
+
+    // The remote host of the packet, the source of the ingress packets and the destination
+    // of the egress ones. With `with_port`, only on the destination port of the packet.
+    pub fn from_packet(app_packet: &AppPacket, with_port: bool) -> Option<Self> {
+        let NetworkPacket::Ip(ip_packet) = app_packet.frame.payload else {
+            return None;
+        };
+
+        let (src_ip, dst_ip, proto) = match ip_packet {
+            IpPacket::V4(ipv4_packet) => (
+                IpAddr::V4(ipv4_packet.src_ip),
+                IpAddr::V4(ipv4_packet.dst_ip),
+                ipv4_packet.proto,
+            ),
+            IpPacket::V6(ipv6_packet) => (
+                IpAddr::V6(ipv6_packet.src_ip),
+                IpAddr::V6(ipv6_packet.dst_ip),
+                ipv6_packet.proto,
+            ),
+        };
+
+        let remote_ip = match app_packet.direction {
+            TrafficDirection::Ingress => src_ip,
+            TrafficDirection::Egress => dst_ip,
+        };
+
+        if !with_port {
+            return Some(Self::block_remote(remote_ip, app_packet.direction));
+        }
+
+        let (protocol, dst_port) = match proto {
+            IpProto::Tcp(tcp_packet) => (RuleProtocol::Tcp, tcp_packet.dst_port),
+            IpProto::Udp(udp_packet) => (RuleProtocol::Udp, udp_packet.dst_port),
+            IpProto::Sctp(sctp_packet) => (RuleProtocol::Sctp, sctp_packet.dst_port),
+            IpProto::Icmp(_) => return None,
+        };
+
+        let port_endpoint = |ip: Option<IpAddr>| RuleEndpoint {
+            ip: ip.map(IpNet::from),
+            port: BlockedPort::Single(dst_port),
+        };
+
+        let (local, remote) = match app_packet.direction {
+            TrafficDirection::Ingress => (
+                port_endpoint(None),
+                RuleEndpoint {
+                    ip: Some(IpNet::from(remote_ip)),
+                    port: BlockedPort::All,
+                },
+            ),
+            TrafficDirection::Egress => (RuleEndpoint::default(), port_endpoint(Some(remote_ip))),
+        };
+
+        Some(Self {
+            name: format!("Block {remote_ip} port {dst_port}"),
+            local: local.to_string(),
+            remote: remote.to_string(),
+            protocol,
+            direction: app_packet.direction,
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    collections::HashSet, fs, net::IpAddr, os::unix::fs::chown, path::PathBuf, str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::AppResult;
+
+use super::IpNet;
+
+// Remote addresses imported from a file, such as a threat intel feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleGroup {
+    pub source: PathBuf,
+    pub subnets: Vec<IpNet>,
+}
+
+impl RuleGroup {
+    // One address or subnet per line, `#` starts a comment
+    pub fn import(source: PathBuf) -> Result<Self, String> {
+        let content = fs::read_to_string(&source)
+            .map_err(|e| format!("Can not read {}. {e}", source.display()))?;
+
+        let mut subnets: Vec<IpNet> = Vec::new();
+        let mut seen: HashSet<IpNet> = HashSet::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let subnet =
+                IpNet::from_str(line).map_err(|_| format!("Invalid line {}.", index + 1))?;
+
+            if seen.insert(subnet) {
+                subnets.push(subnet);
+            }
+        }
+
+        if subnets.is_empty() {
+            return Err("Empty list.".to_string());
+        }
+
+        Ok(Self { source, subnets })
+    }
+
+    // Writes the addresses to `~/oryx/groups/{name}.txt` to import them
+    pub fn save(name: &str, addresses: &[IpAddr]) -> AppResult<PathBuf> {
+        let user_uid = unsafe { libc::geteuid() };
+
+        let oryx_dir = dirs::home_dir().unwrap().join("oryx");
+        let groups_dir = oryx_dir.join("groups");
+
+        for dir in [&oryx_dir, &groups_dir] {
+            if !dir.exists() {
+                fs::create_dir(dir)?;
+                chown(dir, Some(user_uid), Some(user_uid))?;
+            }
+        }
+
+        let content: String = addresses.iter().map(|addr| format!("{addr}\n")).collect();
+
+        let source = groups_dir.join(format!("{name}.txt"));
+        fs::write(&source, content)?;
+
+        Ok(source)
+    }
+
+    pub fn name(&self) -> String {
+        self.source
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.source.display().to_string())
+    }
+}
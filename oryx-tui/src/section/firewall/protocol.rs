@@ -0,0 +1,150 @@
+use core::fmt::Display;
+use std::str::FromStr;
+
+use oryx_common::firewall::PROTO_ANY;
+use serde::{Deserialize, Serialize};
+
+const ICMPV4_TYPES: [(&str, u8); 10] = [
+    ("echo-reply", 0),
+    ("destination-unreachable", 3),
+    ("redirect", 5),
+    ("echo-request", 8),
+    ("router-advertisement", 9),
+    ("router-solicitation", 10),
+    ("time-exceeded", 11),
+    ("parameter-problem", 12),
+    ("timestamp-request", 13),
+    ("timestamp-reply", 14),
+];
+
+const ICMPV6_TYPES: [(&str, u8); 11] = [
+    ("destination-unreachable", 1),
+    ("packet-too-big", 2),
+    ("time-exceeded", 3),
+    ("parameter-problem", 4),
+    ("echo-request", 128),
+    ("echo-reply", 129),
+    ("router-solicitation", 133),
+    ("router-advertisement", 134),
+    ("neighbor-solicitation", 135),
+    ("neighbor-advertisement", 136),
+    ("redirect", 137),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum RuleProtocol {
+    #[default]
+    Any,
+    Tcp,
+    Udp,
+    Sctp,
+    // None -> all the types
+    Icmp(Option<u8>),
+    Icmpv6(Option<u8>),
+}
+
+impl RuleProtocol {
+    // IP protocol number
+    pub fn number(&self) -> u8 {
+        match self {
+            RuleProtocol::Any => PROTO_ANY,
+            RuleProtocol::Tcp => 6,
+            RuleProtocol::Udp => 17,
+            RuleProtocol::Sctp => 132,
+            RuleProtocol::Icmp(_) => 1,
+            RuleProtocol::Icmpv6(_) => 58,
+        }
+    }
+
+    pub fn has_ports(&self) -> bool {
+        !matches!(self, RuleProtocol::Icmp(_) | RuleProtocol::Icmpv6(_))
+    }
+
+    // Inclusive ICMP type range
+    pub fn icmp_types(&self) -> Option<(u16, u16)> {
+        match self {
+            RuleProtocol::Icmp(icmp_type) | RuleProtocol::Icmpv6(icmp_type) => {
+                Some(icmp_type.map_or((0, u8::MAX as u16), |icmp_type| {
+                    (icmp_type as u16, icmp_type as u16)
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn overlaps(&self, other: &RuleProtocol) -> bool {
+        match (self, other) {
+            (RuleProtocol::Any, _) | (_, RuleProtocol::Any) => true,
+            (RuleProtocol::Icmp(a), RuleProtocol::Icmp(b))
+            | (RuleProtocol::Icmpv6(a), RuleProtocol::Icmpv6(b)) => {
+                a.is_none() || b.is_none() || a == b
+            }
+            _ => self == other,
+        }
+    }
+}
+
+impl Display for RuleProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (name, icmp_type, types) = match self {
+            RuleProtocol::Any => return write!(f, "*"),
+            RuleProtocol::Tcp => return write!(f, "tcp"),
+            RuleProtocol::Udp => return write!(f, "udp"),
+            RuleProtocol::Sctp => return write!(f, "sctp"),
+            RuleProtocol::Icmp(icmp_type) => ("icmp", icmp_type, &ICMPV4_TYPES[..]),
+            RuleProtocol::Icmpv6(icmp_type) => ("icmpv6", icmp_type, &ICMPV6_TYPES[..]),
+        };
+
+        match icmp_type {
+            Some(icmp_type) => match types.iter().find(|(_, value)| value == icmp_type) {
+                Some((type_name, _)) => write!(f, "{name} {type_name}"),
+                None => write!(f, "{name} {icmp_type}"),
+            },
+            None => write!(f, "{name}"),
+        }
+    }
+}
+
+impl FromStr for RuleProtocol {
+    type Err = String;
+
+    // e.g. `udp`, `icmp`, `icmpv6 echo-request`, `icmp 8`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+
+        let (name, icmp_type) = (parts.next(), parts.next());
+
+        if parts.next().is_some() {
+            return Err(format!("Invalid protocol {s}"));
+        }
+
+        let parse_icmp_type = |types: &[(&str, u8)]| -> Result<Option<u8>, String> {
+            let Some(icmp_type) = icmp_type else {
+                return Ok(None);
+            };
+            if let Some((_, value)) = types.iter().find(|(name, _)| *name == icmp_type) {
+                return Ok(Some(*value));
+            }
+            icmp_type
+                .parse::<u8>()
+                .map(Some)
+                .map_err(|_| format!("Unknown ICMP type {icmp_type}"))
+        };
+
+        let protocol = match name.map(|name| name.to_lowercase()).as_deref() {
+            None | Some("*") | Some("any") => RuleProtocol::Any,
+            Some("tcp") => RuleProtocol::Tcp,
+            Some("udp") => RuleProtocol::Udp,
+            Some("sctp") => RuleProtocol::Sctp,
+            Some("icmp") => return Ok(RuleProtocol::Icmp(parse_icmp_type(&ICMPV4_TYPES)?)),
+            Some("icmpv6") => return Ok(RuleProtocol::Icmpv6(parse_icmp_type(&ICMPV6_TYPES)?)),
+            Some(other) => return Err(format!("Unknown protocol {other}")),
+        };
+
+        if icmp_type.is_some() {
+            return Err(format!("Invalid protocol {s}"));
+        }
+
+        Ok(protocol)
+    }
+}
@@ -0,0 +1,88 @@
+use core::fmt::Display;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const PREFIXES: [(&str, u32); 3] = [("M", 1_000_000), ("k", 1_000), ("", 1)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RateUnit {
+    #[default]
+    Packets,
+    Bytes,
+}
+
+impl RateUnit {
+    fn suffix(&self) -> &'static str {
+        match self {
+            RateUnit::Packets => "pps",
+            RateUnit::Bytes => "B/s",
+        }
+    }
+}
+
+// Maximum traffic of each remote address
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RateLimit {
+    pub value: u32,
+    pub unit: RateUnit,
+}
+
+impl Display for RateLimit {
+    // e.g. `100pps`, `1MB/s`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (prefix, multiplier) = PREFIXES
+            .iter()
+            .find(|(_, multiplier)| self.value != 0 && self.value % multiplier == 0)
+            .unwrap_or(&("", 1));
+
+        write!(
+            f,
+            "{}{prefix}{}",
+            self.value / multiplier,
+            self.unit.suffix()
+        )
+    }
+}
+
+impl FromStr for RateLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        let (value, unit) = [RateUnit::Packets, RateUnit::Bytes]
+            .into_iter()
+            .find_map(|unit| s.strip_suffix(unit.suffix()).map(|value| (value, unit)))
+            .ok_or(format!("Unknown unit {s}"))?;
+
+        let (value, multiplier) = PREFIXES
+            .iter()
+            .find_map(|(prefix, multiplier)| {
+                value.strip_suffix(prefix).map(|value| (value, *multiplier))
+            })
+            .unwrap_or((value, 1));
+
+        let value = value
+            .parse::<u32>()
+            .ok()
+            .and_then(|value| value.checked_mul(multiplier))
+            .filter(|value| *value != 0)
+            .ok_or(format!("Invalid rate {s}"))?;
+
+        Ok(RateLimit { value, unit })
+    }
+}
+
+impl Serialize for RateLimit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RateLimit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        RateLimit::from_str(&value).map_err(serde::de::Error::custom)
+    }
+}
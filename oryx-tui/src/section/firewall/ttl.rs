@@ -0,0 +1,96 @@
+use core::fmt::Display;
+use std::{str::FromStr, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const UNITS: [(char, u64); 4] = [('d', 86400), ('h', 3600), ('m', 60), ('s', 1)];
+
+// Longest accepted TTL, the expiry date stays representable
+const MAX_TTL: Duration = Duration::from_secs(365 * 86400);
+
+// Time a rule stays enabled before disabling itself
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RuleTtl(pub Duration);
+
+impl RuleTtl {
+    // Expiry of a rule enabled now
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        TimeDelta::from_std(self.0)
+            .ok()
+            .and_then(|ttl| Utc::now().checked_add_signed(ttl))
+    }
+}
+
+impl Display for RuleTtl {
+    // e.g. `15m`, `1h30m`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut secs = self.0.as_secs();
+
+        if secs == 0 {
+            return write!(f, "0s");
+        }
+
+        for (unit, unit_secs) in UNITS {
+            if secs >= unit_secs {
+                write!(f, "{}{unit}", secs / unit_secs)?;
+                secs %= unit_secs;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RuleTtl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut secs: u64 = 0;
+        let mut value = String::new();
+
+        for c in s.trim().chars() {
+            if c.is_ascii_digit() {
+                value.push(c);
+                continue;
+            }
+
+            let (_, unit_secs) = UNITS
+                .iter()
+                .find(|(unit, _)| *unit == c)
+                .ok_or(format!("Unknown unit {c}"))?;
+
+            let value = std::mem::take(&mut value)
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid duration {s}"))?;
+
+            secs = value
+                .checked_mul(*unit_secs)
+                .and_then(|value| secs.checked_add(value))
+                .ok_or(format!("Invalid duration {s}"))?;
+        }
+
+        // A trailing number without unit
+        if !value.is_empty() || secs == 0 {
+            return Err(format!("Invalid duration {s}"));
+        }
+
+        if secs > MAX_TTL.as_secs() {
+            return Err(format!("The duration can not exceed {}", RuleTtl(MAX_TTL)));
+        }
+
+        Ok(RuleTtl(Duration::from_secs(secs)))
+    }
+}
+
+impl Serialize for RuleTtl {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RuleTtl {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        RuleTtl::from_str(&value).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,629 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::Display,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Flex, Layout, Margin, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Row, Table, TableState},
+};
+
+use crate::{
+    packet::{
+        AppPacket, NetworkPacket,
+        network::{IpPacket, ip::IpProto},
+        transport::TcpPacket,
+    },
+    packet_store::PacketStore,
+};
+
+// Ethernet header, not part of the ip lengths
+const ETH_HEADER_LEN: usize = 14;
+const IPV6_HEADER_LEN: usize = 40;
+
+// Flows kept in the table, the least recently seen are dropped past it
+const MAX_FLOWS: usize = 10_000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FlowProtocol {
+    Tcp,
+    Udp,
+    Sctp,
+}
+
+impl Display for FlowProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp => write!(f, "TCP"),
+            Self::Udp => write!(f, "UDP"),
+            Self::Sctp => write!(f, "SCTP"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Endpoint {
+    pub ip: IpAddr,
+    pub port: u16,
+}
+
+impl Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.ip {
+            IpAddr::V4(ip) => write!(f, "{}:{}", ip, self.port),
+            IpAddr::V6(ip) => write!(f, "[{}]:{}", ip, self.port),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TcpState {
+    SynSent,
+    SynReceived,
+    Established,
+    Closing,
+    Closed,
+    Reset,
+}
+
+impl Display for TcpState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SynSent => write!(f, "SYN Sent"),
+            Self::SynReceived => write!(f, "SYN Received"),
+            Self::Established => write!(f, "Established"),
+            Self::Closing => write!(f, "Closing"),
+            Self::Closed => write!(f, "Closed"),
+            Self::Reset => write!(f, "Reset"),
+        }
+    }
+}
+
+// Both directions of a conversation share the same key
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FlowKey {
+    protocol: FlowProtocol,
+    a: Endpoint,
+    b: Endpoint,
+}
+
+impl FlowKey {
+    fn new(protocol: FlowProtocol, src: Endpoint, dst: Endpoint) -> Self {
+        Self {
+            protocol,
+            a: src.min(dst),
+            b: src.max(dst),
+        }
+    }
+}
+
+// The 5-tuple of a TCP, UDP or SCTP packet
+#[derive(Debug, Copy, Clone)]
+pub struct PacketFlow {
+    pub key: FlowKey,
+    pub protocol: FlowProtocol,
+    pub src: Endpoint,
+    pub dst: Endpoint,
+    // Frame length
+    pub len: usize,
+    pub tcp: Option<TcpPacket>,
+}
+
+impl PacketFlow {
+    pub fn parse(app_packet: &AppPacket) -> Option<Self> {
+        let NetworkPacket::Ip(ip_packet) = app_packet.frame.payload else {
+            return None;
+        };
+
+        let (src_ip, dst_ip, len, proto): (IpAddr, IpAddr, usize, IpProto) = match ip_packet {
+            IpPacket::V4(p) => (
+                p.src_ip.into(),
+                p.dst_ip.into(),
+                p.total_length as usize,
+                p.proto,
+            ),
+            IpPacket::V6(p) => (
+                p.src_ip.into(),
+                p.dst_ip.into(),
+                p.payload_length as usize + IPV6_HEADER_LEN,
+                p.proto,
+            ),
+        };
+
+        let (protocol, src_port, dst_port, tcp) = match proto {
+            IpProto::Tcp(p) => (FlowProtocol::Tcp, p.src_port, p.dst_port, Some(p)),
+            IpProto::Udp(p) => (FlowProtocol::Udp, p.src_port, p.dst_port, None),
+            IpProto::Sctp(p) => (FlowProtocol::Sctp, p.src_port, p.dst_port, None),
+            IpProto::Icmp(_) => return None,
+        };
+
+        let src = Endpoint {
+            ip: src_ip,
+            port: src_port,
+        };
+        let dst = Endpoint {
+            ip: dst_ip,
+            port: dst_port,
+        };
+
+        Some(Self {
+            key: FlowKey::new(protocol, src, dst),
+            protocol,
+            src,
+            dst,
+            len: len + ETH_HEADER_LEN,
+            tcp,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Flow {
+    pub key: FlowKey,
+    pub protocol: FlowProtocol,
+    // The initiator of the conversation
+    pub src: Endpoint,
+    pub dst: Endpoint,
+    pub packets_sent: usize,
+    pub packets_received: usize,
+    pub bytes_sent: usize,
+    pub bytes_received: usize,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub tcp_state: Option<TcpState>,
+    pub pid: Option<u32>,
+    src_fin: bool,
+    dst_fin: bool,
+}
+
+impl Flow {
+    fn update(
+        &mut self,
+        src: Endpoint,
+        len: usize,
+        app_packet: &AppPacket,
+        tcp: Option<&TcpPacket>,
+    ) {
+        let is_forward = src == self.src;
+
+        if is_forward {
+            self.packets_sent += 1;
+            self.bytes_sent += len;
+        } else {
+            self.packets_received += 1;
+            self.bytes_received += len;
+        }
+
+        self.first_seen = self.first_seen.min(app_packet.timestamp);
+        self.last_seen = self.last_seen.max(app_packet.timestamp);
+
+        if self.pid.is_none() {
+            self.pid = app_packet.pid;
+        }
+
+        if let Some(tcp) = tcp {
+            self.update_tcp_state(tcp, is_forward);
+        }
+    }
+
+    fn update_tcp_state(&mut self, p: &TcpPacket, is_forward: bool) {
+        let state = if p.rst == 1 {
+            TcpState::Reset
+        } else if p.syn == 1 {
+            // A new connection reusing the same ports
+            self.src_fin = false;
+            self.dst_fin = false;
+            if p.ack == 1 {
+                TcpState::SynReceived
+            } else {
+                TcpState::SynSent
+            }
+        } else if p.fin == 1 {
+            if is_forward {
+                self.src_fin = true;
+            } else {
+                self.dst_fin = true;
+            }
+            if self.src_fin && self.dst_fin {
+                TcpState::Closed
+            } else {
+                TcpState::Closing
+            }
+        } else {
+            match self.tcp_state {
+                Some(TcpState::SynSent | TcpState::SynReceived) | None => TcpState::Established,
+                Some(state) => state,
+            }
+        };
+
+        self.tcp_state = Some(state);
+    }
+
+    // Display filter matching the packets of the conversation
+    pub fn display_filter(&self) -> String {
+        let protocol = self.protocol.to_string().to_lowercase();
+        let side = |src: &Endpoint, dst: &Endpoint| {
+            format!(
+                "ip.src == {} && {protocol}.srcport == {} && ip.dst == {} && {protocol}.dstport == {}",
+                src.ip, src.port, dst.ip, dst.port
+            )
+        };
+        format!(
+            "({}) || ({})",
+            side(&self.src, &self.dst),
+            side(&self.dst, &self.src)
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct FlowTable {
+    flows: HashMap<FlowKey, Flow>,
+}
+
+impl FlowTable {
+    fn add(&mut self, app_packet: &AppPacket) {
+        let Some(PacketFlow {
+            key,
+            protocol,
+            src,
+            dst,
+            len,
+            tcp,
+        }) = PacketFlow::parse(app_packet)
+        else {
+            return;
+        };
+
+        let flow = self.flows.entry(key).or_insert_with(|| {
+            // The SYN was missed, the sender of the SYN-ACK is the responder
+            let is_syn_ack = tcp.is_some_and(|p| p.syn == 1 && p.ack == 1);
+            let (src, dst) = if is_syn_ack { (dst, src) } else { (src, dst) };
+
+            Flow {
+                key,
+                protocol,
+                src,
+                dst,
+                packets_sent: 0,
+                packets_received: 0,
+                bytes_sent: 0,
+                bytes_received: 0,
+                first_seen: app_packet.timestamp,
+                last_seen: app_packet.timestamp,
+                tcp_state: None,
+                pid: None,
+                src_fin: false,
+                dst_fin: false,
+            }
+        });
+
+        flow.update(src, len, app_packet, tcp.as_ref());
+    }
+
+    // Drops the least recently seen flows, a tenth below the limit to not run it on each packet
+    fn evict(&mut self) {
+        if self.flows.len() <= MAX_FLOWS {
+            return;
+        }
+
+        let mut last_seen: Vec<DateTime<Utc>> =
+            self.flows.values().map(|flow| flow.last_seen).collect();
+        let nb_evicted = last_seen.len() - MAX_FLOWS * 9 / 10;
+        let (_, cutoff, _) = last_seen.select_nth_unstable(nb_evicted);
+        let cutoff = *cutoff;
+
+        self.flows.retain(|_, flow| flow.last_seen >= cutoff);
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FlowColumn {
+    Protocol,
+    Source,
+    Destination,
+    PacketsSent,
+    PacketsReceived,
+    BytesSent,
+    BytesReceived,
+    FirstSeen,
+    LastSeen,
+    State,
+    Pid,
+}
+
+impl FlowColumn {
+    const ALL: [FlowColumn; 11] = [
+        Self::Protocol,
+        Self::Source,
+        Self::Destination,
+        Self::PacketsSent,
+        Self::PacketsReceived,
+        Self::BytesSent,
+        Self::BytesReceived,
+        Self::FirstSeen,
+        Self::LastSeen,
+        Self::State,
+        Self::Pid,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::Protocol => "Protocol",
+            Self::Source => "Source",
+            Self::Destination => "Destination",
+            Self::PacketsSent => "Packets →",
+            Self::PacketsReceived => "Packets ←",
+            Self::BytesSent => "Bytes →",
+            Self::BytesReceived => "Bytes ←",
+            Self::FirstSeen => "First Seen",
+            Self::LastSeen => "Last Seen",
+            Self::State => "State",
+            Self::Pid => "Pid",
+        }
+    }
+
+    fn position(self) -> usize {
+        Self::ALL.iter().position(|c| *c == self).unwrap()
+    }
+
+    fn next(self) -> Self {
+        Self::ALL[(self.position() + 1) % Self::ALL.len()]
+    }
+
+    fn previous(self) -> Self {
+        Self::ALL[(self.position() + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    fn compare(self, a: &Flow, b: &Flow) -> Ordering {
+        match self {
+            Self::Protocol => a.protocol.cmp(&b.protocol),
+            Self::Source => a.src.cmp(&b.src),
+            Self::Destination => a.dst.cmp(&b.dst),
+            Self::PacketsSent => a.packets_sent.cmp(&b.packets_sent),
+            Self::PacketsReceived => a.packets_received.cmp(&b.packets_received),
+            Self::BytesSent => a.bytes_sent.cmp(&b.bytes_sent),
+            Self::BytesReceived => a.bytes_received.cmp(&b.bytes_received),
+            Self::FirstSeen => a.first_seen.cmp(&b.first_seen),
+            Self::LastSeen => a.last_seen.cmp(&b.last_seen),
+            Self::State => a.tcp_state.cmp(&b.tcp_state),
+            Self::Pid => a.pid.cmp(&b.pid),
+        }
+    }
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        b if b >= 1 << 30 => format!("{:.1} GB", b as f64 / (1 << 30) as f64),
+        b if b >= 1 << 20 => format!("{:.1} MB", b as f64 / (1 << 20) as f64),
+        b if b >= 1 << 10 => format!("{:.1} KB", b as f64 / (1 << 10) as f64),
+        b => format!("{b} B"),
+    }
+}
+
+#[derive(Debug)]
+pub struct Flows {
+    table: Arc<Mutex<FlowTable>>,
+    state: TableState,
+    sort_column: FlowColumn,
+    is_descending: bool,
+    // Kept by key, the rows move as the counters change
+    selected_flow: Option<FlowKey>,
+    // Flows in the order of the last render
+    displayed_flows: Vec<FlowKey>,
+    // First row shown
+    offset: usize,
+}
+
+impl Flows {
+    pub fn new(packets: PacketStore) -> Self {
+        let table = Arc::new(Mutex::new(FlowTable::default()));
+
+        thread::spawn({
+            let table = table.clone();
+            move || {
+                let mut last_index: usize = 0;
+                loop {
+                    thread::sleep(Duration::from_millis(500));
+
+                    if packets.is_empty() {
+                        continue;
+                    }
+
+                    let mut table = table.lock().unwrap();
+                    last_index += packets
+                        .for_each_range(last_index.., |app_packet| {
+                            table.add(app_packet);
+                            Ok(())
+                        })
+                        .unwrap();
+                    table.evict();
+                }
+            }
+        });
+
+        Self {
+            table,
+            state: TableState::default(),
+            sort_column: FlowColumn::LastSeen,
+            is_descending: true,
+            selected_flow: None,
+            displayed_flows: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    pub fn selected_flow(&self) -> Option<Flow> {
+        let key = self.selected_flow?;
+        self.table.lock().unwrap().flows.get(&key).cloned()
+    }
+
+    fn selected_position(&self) -> Option<usize> {
+        let key = self.selected_flow?;
+        self.displayed_flows.iter().position(|flow| *flow == key)
+    }
+
+    pub fn handle_keys(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                if self.displayed_flows.is_empty() {
+                    return;
+                }
+                let i = match self.selected_position() {
+                    Some(i) => (i + 1).min(self.displayed_flows.len() - 1),
+                    None => 0,
+                };
+                self.selected_flow = Some(self.displayed_flows[i]);
+            }
+
+            KeyCode::Char('k') | KeyCode::Up => {
+                if self.displayed_flows.is_empty() {
+                    return;
+                }
+                let i = match self.selected_position() {
+                    Some(i) => i.saturating_sub(1),
+                    None => 0,
+                };
+                self.selected_flow = Some(self.displayed_flows[i]);
+            }
+
+            KeyCode::Char('l') | KeyCode::Right => {
+                self.sort_column = self.sort_column.next();
+            }
+
+            KeyCode::Char('h') | KeyCode::Left => {
+                self.sort_column = self.sort_column.previous();
+            }
+
+            KeyCode::Char('s') => {
+                self.is_descending = !self.is_descending;
+            }
+
+            _ => {}
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, block: Rect) {
+        let flow_table = self.table.lock().unwrap();
+
+        if flow_table.flows.is_empty() {
+            let block = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Fill(1),
+                    Constraint::Length(3),
+                    Constraint::Fill(1),
+                ])
+                .flex(ratatui::layout::Flex::SpaceBetween)
+                .split(block)[1];
+            let message = Text::from("No TCP, UDP or SCTP conversations yet.")
+                .bold()
+                .centered();
+            frame.render_widget(message, block);
+            return;
+        }
+
+        let mut flows: Vec<&Flow> = flow_table.flows.values().collect();
+        flows.sort_by(|a, b| {
+            let ordering = self.sort_column.compare(a, b);
+            if self.is_descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+
+        self.displayed_flows = flows.iter().map(|flow| flow.key).collect();
+
+        // The first flow when the selected one was dropped
+        let selected = self.selected_position().unwrap_or(0);
+        self.selected_flow = Some(self.displayed_flows[selected]);
+
+        let block = block.inner(Margin {
+            horizontal: 2,
+            vertical: 2,
+        });
+
+        // Only the visible rows are built, the header takes 2 lines
+        let nb_rows = (block.height as usize).saturating_sub(2).max(1);
+        if selected < self.offset {
+            self.offset = selected;
+        } else if selected >= self.offset + nb_rows {
+            self.offset = selected + 1 - nb_rows;
+        }
+        self.offset = self.offset.min(flows.len().saturating_sub(nb_rows));
+        *self.state.offset_mut() = 0;
+        self.state.select(Some(selected - self.offset));
+
+        let widths = [
+            Constraint::Length(8),  // Protocol
+            Constraint::Min(21),    // Source
+            Constraint::Min(21),    // Destination
+            Constraint::Length(10), // Packets →
+            Constraint::Length(10), // Packets ←
+            Constraint::Length(10), // Bytes →
+            Constraint::Length(10), // Bytes ←
+            Constraint::Length(10), // First Seen
+            Constraint::Length(10), // Last Seen
+            Constraint::Length(12), // State
+            Constraint::Length(8),  // Pid
+        ];
+
+        let rows = flows.iter().skip(self.offset).take(nb_rows).map(|flow| {
+            Row::new(vec![
+                Line::from(flow.protocol.to_string()).centered().cyan(),
+                Line::from(flow.src.to_string()).centered().blue(),
+                Line::from(flow.dst.to_string()).centered().blue(),
+                Line::from(flow.packets_sent.to_string()).centered(),
+                Line::from(flow.packets_received.to_string()).centered(),
+                Line::from(format_bytes(flow.bytes_sent)).centered(),
+                Line::from(format_bytes(flow.bytes_received)).centered(),
+                Line::from(flow.first_seen.format("%H:%M:%S").to_string()).centered(),
+                Line::from(flow.last_seen.format("%H:%M:%S").to_string()).centered(),
+                match flow.tcp_state {
+                    Some(state @ (TcpState::Reset | TcpState::Closed)) => {
+                        Line::from(state.to_string()).centered().red()
+                    }
+                    Some(TcpState::Established) => Line::from(TcpState::Established.to_string())
+                        .centered()
+                        .green(),
+                    Some(state) => Line::from(state.to_string()).centered().yellow(),
+                    None => Line::from("-").centered(),
+                },
+                match flow.pid {
+                    Some(pid) => Line::from(pid.to_string()).centered().cyan(),
+                    None => Line::from("-").centered().yellow(),
+                },
+            ])
+        });
+
+        let header = FlowColumn::ALL.iter().map(|column| {
+            if *column == self.sort_column {
+                let arrow = if self.is_descending { "▼" } else { "▲" };
+                Line::from(format!("{} {arrow}", column.title()))
+                    .centered()
+                    .yellow()
+            } else {
+                Line::from(column.title()).centered().blue()
+            }
+        });
+
+        let table = Table::new(rows, widths)
+            .column_spacing(2)
+            .flex(Flex::SpaceBetween)
+            .row_highlight_style(Style::default().bg(Color::DarkGray))
+            .header(Row::new(header).style(Style::new().bold()).bottom_margin(1));
+
+        frame.render_stateful_widget(table, block, &mut self.state);
+    }
+}
@@ -5,18 +5,18 @@ use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Flex, Layout, Margin, Rect},
     style::{Style, Stylize},
-    text::{Line, Span},
+    text::{Line, Span, Text},
     widgets::{
-        Block, BorderType, Borders, Cell, Clear, HighlightSpacing, Padding, Paragraph, Row,
-        Scrollbar, ScrollbarOrientation, ScrollbarState, Table, TableState,
+        Block, BorderType, Borders, Clear, HighlightSpacing, Padding, Paragraph, Row, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Table, TableState,
     },
 };
-use tui_input::backend::crossterm::EventHandler;
+use tui_input::{Input, backend::crossterm::EventHandler};
 
 use crate::{
     app::AppResult,
-    export,
-    filter::fuzzy::{self, Fuzzy},
+    export::{self, ExportFormat},
+    filter::{display::DisplayFilter, fuzzy::Fuzzy},
     notification::{Notification, NotificationLevel},
     packet::{
         AppPacket, NetworkPacket,
@@ -36,10 +36,11 @@ pub struct Inspection {
     pub packet_window_size: usize,
     pub packet_index: Option<usize>,
     pub packets_display_buffer: Vec<AppPacket>,
+    pub export_format: ExportFormat,
 }
 
 impl Inspection {
-    pub fn new(packets: PacketStore) -> Self {
+    pub fn new(packets: PacketStore, export_format: ExportFormat) -> Self {
         Self {
             packets: packets.clone(),
             state: TableState::default(),
@@ -49,6 +50,31 @@ impl Inspection {
             packet_window_size: 0,
             packet_index: None,
             packets_display_buffer: Vec::with_capacity(128),
+            export_format,
+        }
+    }
+
+    // Enables the display filter with an expression, paused to navigate right away
+    pub fn filter(&mut self, expression: String) {
+        let mut fuzzy = self.fuzzy.lock().unwrap();
+        fuzzy.enable();
+        fuzzy.filter = Input::new(expression);
+        fuzzy.error = None;
+        fuzzy.pause();
+        self.manual_scroll = false;
+    }
+
+    // Only a packet picked while scrolling, none while following the new packets
+    pub fn selected_packet(&self) -> Option<AppPacket> {
+        if !self.manual_scroll {
+            return None;
+        }
+        let index = self.packet_index?;
+        let fuzzy = self.fuzzy.lock().unwrap();
+        if fuzzy.is_enabled() && !fuzzy.filter.value().is_empty() {
+            fuzzy.packets.get(index).copied()
+        } else {
+            self.packets.get(index)
         }
     }
 
@@ -85,6 +111,7 @@ impl Inspection {
                         fuzzy
                             .filter
                             .handle_event(&crossterm::event::Event::Key(key_event));
+                        fuzzy.error = DisplayFilter::parse(fuzzy.filter.value()).err();
                     } else {
                         match key_event.code {
                             KeyCode::Char('j') | KeyCode::Down => {
@@ -142,23 +169,7 @@ impl Inspection {
                             NotificationLevel::Info,
                             event_sender,
                         )?;
-                    } else {
-                        match export::export(&self.packets) {
-                            Ok(_) => {
-                                Notification::send(
-                                    "Packets exported to ~/oryx directory".to_string(),
-                                    NotificationLevel::Info,
-                                    event_sender,
-                                )?;
-                            }
-                            Err(e) => {
-                                Notification::send(
-                                    e.to_string(),
-                                    NotificationLevel::Error,
-                                    event_sender,
-                                )?;
-                            }
-                        }
+                        return Err("There is no packets to export".into());
                     }
                 }
 
@@ -168,6 +179,44 @@ impl Inspection {
         Ok(())
     }
 
+    pub fn handle_export_popup_keys(&mut self, key_event: KeyEvent) {
+        let formats = ExportFormat::all();
+        let index = formats
+            .iter()
+            .position(|format| *format == self.export_format)
+            .unwrap_or_default();
+
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.export_format = formats[(index + 1).min(formats.len() - 1)];
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.export_format = formats[index.saturating_sub(1)];
+            }
+            _ => {}
+        }
+    }
+
+    pub fn export(
+        &self,
+        interface: &str,
+        event_sender: kanal::Sender<crate::event::Event>,
+    ) -> AppResult<()> {
+        match export::export(&self.packets, self.export_format, &[interface.to_string()]) {
+            Ok(path) => {
+                Notification::send(
+                    format!("Packets exported to {}", path.display()),
+                    NotificationLevel::Info,
+                    event_sender,
+                )?;
+            }
+            Err(e) => {
+                Notification::send(e.to_string(), NotificationLevel::Error, event_sender)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn scroll_up(&mut self) {
         if !self.manual_scroll {
             self.manual_scroll = true;
@@ -220,9 +269,6 @@ impl Inspection {
         let mut fuzzy = self.fuzzy.lock().unwrap();
         let fuzzy_packets = fuzzy.clone().packets.clone();
 
-        let pattern = fuzzy.clone();
-        let pattern = pattern.filter.value();
-
         let (packet_block, fuzzy_block) = {
             if fuzzy.is_enabled() {
                 let chunks = Layout::default()
@@ -336,263 +382,156 @@ impl Inspection {
         };
 
         // Style the packets
-        let packets: Vec<Row> = if fuzzy.is_enabled() & !fuzzy.filter.value().is_empty() {
-            pdb.iter()
-                .map(|app_packet| {
-                    let pid = match app_packet.pid {
-                        Some(pid) => fuzzy::highlight(pattern, pid.to_string()).blue(),
-                        None => Cell::from(Line::from("-").centered()).yellow(),
-                    };
-
-                    match app_packet.frame.payload {
-                        NetworkPacket::Arp(packet) => Row::new(vec![
-                            fuzzy::highlight(pattern, packet.src_mac.to_string()).blue(),
-                            Cell::from(Line::from("-").centered()).yellow(),
-                            fuzzy::highlight(pattern, packet.dst_mac.to_string()).blue(),
-                            Cell::from(Line::from("-").centered()).yellow(),
-                            fuzzy::highlight(pattern, "ARP".to_string()).cyan(),
-                            pid,
-                        ]),
-                        NetworkPacket::Ip(packet) => match packet {
-                            IpPacket::V4(ipv4_packet) => match ipv4_packet.proto {
-                                IpProto::Tcp(p) => Row::new(vec![
-                                    fuzzy::highlight(pattern, ipv4_packet.src_ip.to_string())
-                                        .blue(),
-                                    fuzzy::highlight(pattern, p.src_port.to_string()).yellow(),
-                                    fuzzy::highlight(pattern, ipv4_packet.dst_ip.to_string())
-                                        .blue(),
-                                    fuzzy::highlight(pattern, p.dst_port.to_string()).yellow(),
-                                    fuzzy::highlight(pattern, "TCP".to_string()).cyan(),
-                                    pid,
-                                ]),
-                                IpProto::Udp(p) => Row::new(vec![
-                                    fuzzy::highlight(pattern, ipv4_packet.src_ip.to_string())
-                                        .blue(),
-                                    fuzzy::highlight(pattern, p.src_port.to_string()).yellow(),
-                                    fuzzy::highlight(pattern, ipv4_packet.dst_ip.to_string())
-                                        .blue(),
-                                    fuzzy::highlight(pattern, p.dst_port.to_string()).yellow(),
-                                    fuzzy::highlight(pattern, "UDP".to_string()).cyan(),
-                                    pid,
-                                ]),
-                                IpProto::Sctp(p) => Row::new(vec![
-                                    fuzzy::highlight(pattern, ipv4_packet.src_ip.to_string())
-                                        .blue(),
-                                    fuzzy::highlight(pattern, p.src_port.to_string()).yellow(),
-                                    fuzzy::highlight(pattern, ipv4_packet.dst_ip.to_string())
-                                        .blue(),
-                                    fuzzy::highlight(pattern, p.dst_port.to_string()).yellow(),
-                                    fuzzy::highlight(pattern, "SCTP".to_string()).cyan(),
-                                    pid,
-                                ]),
-                                IpProto::Icmp(_) => Row::new(vec![
-                                    fuzzy::highlight(pattern, ipv4_packet.src_ip.to_string())
-                                        .blue(),
-                                    Cell::from(Line::from("-").centered()).yellow(),
-                                    fuzzy::highlight(pattern, ipv4_packet.dst_ip.to_string())
-                                        .blue(),
-                                    Cell::from(Line::from("-").centered()).yellow(),
-                                    fuzzy::highlight(pattern, "ICMPv4".to_string()).cyan(),
-                                    pid,
-                                ]),
-                            },
-                            IpPacket::V6(ipv6_packet) => match ipv6_packet.proto {
-                                IpProto::Tcp(p) => Row::new(vec![
-                                    fuzzy::highlight(pattern, ipv6_packet.src_ip.to_string())
-                                        .blue(),
-                                    fuzzy::highlight(pattern, p.src_port.to_string()).yellow(),
-                                    fuzzy::highlight(pattern, ipv6_packet.dst_ip.to_string())
-                                        .blue(),
-                                    fuzzy::highlight(pattern, p.dst_port.to_string()).yellow(),
-                                    fuzzy::highlight(pattern, "TCP".to_string()).cyan(),
-                                    pid,
-                                ]),
-                                IpProto::Udp(p) => Row::new(vec![
-                                    fuzzy::highlight(pattern, ipv6_packet.src_ip.to_string())
-                                        .blue(),
-                                    fuzzy::highlight(pattern, p.src_port.to_string()).yellow(),
-                                    fuzzy::highlight(pattern, ipv6_packet.dst_ip.to_string())
-                                        .blue(),
-                                    fuzzy::highlight(pattern, p.dst_port.to_string()).yellow(),
-                                    fuzzy::highlight(pattern, "UDP".to_string()).cyan(),
-                                    pid,
-                                ]),
-                                IpProto::Sctp(p) => Row::new(vec![
-                                    fuzzy::highlight(pattern, ipv6_packet.src_ip.to_string())
-                                        .blue(),
-                                    fuzzy::highlight(pattern, p.src_port.to_string()).yellow(),
-                                    fuzzy::highlight(pattern, ipv6_packet.dst_ip.to_string())
-                                        .blue(),
-                                    fuzzy::highlight(pattern, p.dst_port.to_string()).yellow(),
-                                    fuzzy::highlight(pattern, "SCTP".to_string()).cyan(),
-                                    pid,
-                                ]),
-                                IpProto::Icmp(_) => Row::new(vec![
-                                    fuzzy::highlight(pattern, ipv6_packet.src_ip.to_string())
-                                        .blue(),
-                                    Cell::from(Line::from("-").centered()).yellow(),
-                                    fuzzy::highlight(pattern, ipv6_packet.dst_ip.to_string())
-                                        .blue(),
-                                    Cell::from(Line::from("-").centered()).yellow(),
-                                    fuzzy::highlight(pattern, "ICMPv6".to_string()).cyan(),
-                                    pid,
-                                ]),
-                            },
+        let packets: Vec<Row> = pdb
+            .iter()
+            .map(|app_packet| {
+                let pid = match app_packet.pid {
+                    Some(pid) => Span::from(pid.to_string()).into_centered_line().cyan(),
+                    None => Span::from("-").into_centered_line().yellow(),
+                };
+
+                match app_packet.frame.payload {
+                    NetworkPacket::Arp(packet) => Row::new(vec![
+                        Span::from(packet.src_mac.to_string())
+                            .into_centered_line()
+                            .blue(),
+                        Span::from("-").into_centered_line().yellow(),
+                        Span::from(packet.dst_mac.to_string())
+                            .into_centered_line()
+                            .blue(),
+                        Span::from("-").into_centered_line().yellow(),
+                        Span::from("ARP".to_string()).into_centered_line().cyan(),
+                        pid,
+                    ]),
+                    NetworkPacket::Ip(packet) => match packet {
+                        IpPacket::V4(ipv4_packet) => match ipv4_packet.proto {
+                            IpProto::Tcp(p) => Row::new(vec![
+                                Span::from(ipv4_packet.src_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from(p.src_port.to_string())
+                                    .into_centered_line()
+                                    .yellow(),
+                                Span::from(ipv4_packet.dst_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from(p.dst_port.to_string())
+                                    .into_centered_line()
+                                    .yellow(),
+                                Span::from("TCP".to_string()).into_centered_line().cyan(),
+                                pid,
+                            ]),
+                            IpProto::Udp(p) => Row::new(vec![
+                                Span::from(ipv4_packet.src_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from(p.src_port.to_string())
+                                    .into_centered_line()
+                                    .yellow(),
+                                Span::from(ipv4_packet.dst_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from(p.dst_port.to_string())
+                                    .into_centered_line()
+                                    .yellow(),
+                                Span::from("UDP".to_string()).into_centered_line().cyan(),
+                                pid,
+                            ]),
+                            IpProto::Sctp(p) => Row::new(vec![
+                                Span::from(ipv4_packet.src_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from(p.src_port.to_string())
+                                    .into_centered_line()
+                                    .yellow(),
+                                Span::from(ipv4_packet.dst_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from(p.dst_port.to_string())
+                                    .into_centered_line()
+                                    .yellow(),
+                                Span::from("SCTP".to_string()).into_centered_line().cyan(),
+                                pid,
+                            ]),
+                            IpProto::Icmp(_) => Row::new(vec![
+                                Span::from(ipv4_packet.src_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from("-").into_centered_line().yellow(),
+                                Span::from(ipv4_packet.dst_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from("-").into_centered_line().yellow(),
+                                Span::from("ICMPv4".to_string()).into_centered_line().cyan(),
+                                pid,
+                            ]),
                         },
-                    }
-                })
-                .collect()
-        } else {
-            pdb.iter()
-                .map(|app_packet| {
-                    let pid = match app_packet.pid {
-                        Some(pid) => Span::from(pid.to_string()).into_centered_line().cyan(),
-                        None => Span::from("-").into_centered_line().yellow(),
-                    };
-
-                    match app_packet.frame.payload {
-                        NetworkPacket::Arp(packet) => Row::new(vec![
-                            Span::from(packet.src_mac.to_string())
-                                .into_centered_line()
-                                .blue(),
-                            Span::from("-").into_centered_line().yellow(),
-                            Span::from(packet.dst_mac.to_string())
-                                .into_centered_line()
-                                .blue(),
-                            Span::from("-").into_centered_line().yellow(),
-                            Span::from("ARP".to_string()).into_centered_line().cyan(),
-                            pid,
-                        ]),
-                        NetworkPacket::Ip(packet) => match packet {
-                            IpPacket::V4(ipv4_packet) => match ipv4_packet.proto {
-                                IpProto::Tcp(p) => Row::new(vec![
-                                    Span::from(ipv4_packet.src_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from(p.src_port.to_string())
-                                        .into_centered_line()
-                                        .yellow(),
-                                    Span::from(ipv4_packet.dst_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from(p.dst_port.to_string())
-                                        .into_centered_line()
-                                        .yellow(),
-                                    Span::from("TCP".to_string()).into_centered_line().cyan(),
-                                    pid,
-                                ]),
-                                IpProto::Udp(p) => Row::new(vec![
-                                    Span::from(ipv4_packet.src_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from(p.src_port.to_string())
-                                        .into_centered_line()
-                                        .yellow(),
-                                    Span::from(ipv4_packet.dst_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from(p.dst_port.to_string())
-                                        .into_centered_line()
-                                        .yellow(),
-                                    Span::from("UDP".to_string()).into_centered_line().cyan(),
-                                    pid,
-                                ]),
-                                IpProto::Sctp(p) => Row::new(vec![
-                                    Span::from(ipv4_packet.src_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from(p.src_port.to_string())
-                                        .into_centered_line()
-                                        .yellow(),
-                                    Span::from(ipv4_packet.dst_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from(p.dst_port.to_string())
-                                        .into_centered_line()
-                                        .yellow(),
-                                    Span::from("SCTP".to_string()).into_centered_line().cyan(),
-                                    pid,
-                                ]),
-                                IpProto::Icmp(_) => Row::new(vec![
-                                    Span::from(ipv4_packet.src_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from("-").into_centered_line().yellow(),
-                                    Span::from(ipv4_packet.dst_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from("-").into_centered_line().yellow(),
-                                    Span::from("ICMPv4".to_string()).into_centered_line().cyan(),
-                                    pid,
-                                ]),
-                            },
-                            IpPacket::V6(ipv6_packet) => match ipv6_packet.proto {
-                                IpProto::Tcp(p) => Row::new(vec![
-                                    Span::from(ipv6_packet.src_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from(p.src_port.to_string())
-                                        .into_centered_line()
-                                        .yellow(),
-                                    Span::from(ipv6_packet.dst_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from(p.dst_port.to_string())
-                                        .into_centered_line()
-                                        .yellow(),
-                                    Span::from("TCP".to_string()).into_centered_line().cyan(),
-                                    pid,
-                                ]),
-                                IpProto::Udp(p) => Row::new(vec![
-                                    Span::from(ipv6_packet.src_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from(p.src_port.to_string())
-                                        .into_centered_line()
-                                        .yellow(),
-                                    Span::from(ipv6_packet.dst_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from(p.dst_port.to_string())
-                                        .into_centered_line()
-                                        .yellow(),
-                                    Span::from("UDP".to_string()).into_centered_line().cyan(),
-                                    pid,
-                                ]),
-                                IpProto::Sctp(p) => Row::new(vec![
-                                    Span::from(ipv6_packet.src_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from(p.src_port.to_string())
-                                        .into_centered_line()
-                                        .yellow(),
-                                    Span::from(ipv6_packet.dst_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from(p.dst_port.to_string())
-                                        .into_centered_line()
-                                        .yellow(),
-                                    Span::from("SCTP".to_string()).into_centered_line().cyan(),
-                                    pid,
-                                ]),
-                                IpProto::Icmp(_) => Row::new(vec![
-                                    Span::from(ipv6_packet.src_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from("-").into_centered_line().yellow(),
-                                    Span::from(ipv6_packet.dst_ip.to_string())
-                                        .into_centered_line()
-                                        .blue(),
-                                    Span::from("-").into_centered_line().yellow(),
-                                    Span::from("ICMPv6".to_string()).into_centered_line().cyan(),
-                                    pid,
-                                ]),
-                            },
+                        IpPacket::V6(ipv6_packet) => match ipv6_packet.proto {
+                            IpProto::Tcp(p) => Row::new(vec![
+                                Span::from(ipv6_packet.src_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from(p.src_port.to_string())
+                                    .into_centered_line()
+                                    .yellow(),
+                                Span::from(ipv6_packet.dst_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from(p.dst_port.to_string())
+                                    .into_centered_line()
+                                    .yellow(),
+                                Span::from("TCP".to_string()).into_centered_line().cyan(),
+                                pid,
+                            ]),
+                            IpProto::Udp(p) => Row::new(vec![
+                                Span::from(ipv6_packet.src_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from(p.src_port.to_string())
+                                    .into_centered_line()
+                                    .yellow(),
+                                Span::from(ipv6_packet.dst_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from(p.dst_port.to_string())
+                                    .into_centered_line()
+                                    .yellow(),
+                                Span::from("UDP".to_string()).into_centered_line().cyan(),
+                                pid,
+                            ]),
+                            IpProto::Sctp(p) => Row::new(vec![
+                                Span::from(ipv6_packet.src_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from(p.src_port.to_string())
+                                    .into_centered_line()
+                                    .yellow(),
+                                Span::from(ipv6_packet.dst_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from(p.dst_port.to_string())
+                                    .into_centered_line()
+                                    .yellow(),
+                                Span::from("SCTP".to_string()).into_centered_line().cyan(),
+                                pid,
+                            ]),
+                            IpProto::Icmp(_) => Row::new(vec![
+                                Span::from(ipv6_packet.src_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from("-").into_centered_line().yellow(),
+                                Span::from(ipv6_packet.dst_ip.to_string())
+                                    .into_centered_line()
+                                    .blue(),
+                                Span::from("-").into_centered_line().yellow(),
+                                Span::from("ICMPv6".to_string()).into_centered_line().cyan(),
+                                pid,
+                            ]),
                         },
-                    }
-                })
-                .collect()
-        };
+                    },
+                }
+            })
+            .collect();
 
         // Always select the last packet
         if !self.manual_scroll {
@@ -680,7 +619,28 @@ impl Inspection {
         );
 
         if fuzzy.is_enabled() {
-            let fuzzy = Paragraph::new(format!("> {}", fuzzy.filter.value()))
+            let value = fuzzy.filter.value();
+
+            let text = match &fuzzy.error {
+                // Highlight from the first invalid token
+                Some(error) if !value.is_empty() => {
+                    let (valid, invalid): (String, String) = (
+                        value.chars().take(error.position).collect(),
+                        value.chars().skip(error.position).collect(),
+                    );
+                    Text::from(vec![
+                        Line::from(vec![
+                            Span::from("> "),
+                            Span::from(valid),
+                            Span::styled(invalid, Style::default().red().underlined()),
+                        ]),
+                        Line::from(error.message.clone()).red(),
+                    ])
+                }
+                _ => Text::from(format!("> {value}")),
+            };
+
+            let fuzzy = Paragraph::new(text)
                 .alignment(Alignment::Left)
                 .style(Style::default().white())
                 .block(
@@ -794,4 +754,62 @@ impl Inspection {
             }
         };
     }
+
+    pub fn render_export_popup(&self, frame: &mut Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Fill(1),
+            ])
+            .flex(ratatui::layout::Flex::SpaceBetween)
+            .split(frame.area());
+
+        let block = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(40),
+                Constraint::Fill(1),
+            ])
+            .flex(ratatui::layout::Flex::SpaceBetween)
+            .split(layout[1])[1];
+
+        let rows = ExportFormat::all().map(|format| {
+            Row::new(vec![
+                {
+                    if format == self.export_format {
+                        Line::from(" ").centered()
+                    } else {
+                        Line::from("").centered()
+                    }
+                },
+                Line::from(format.to_string()),
+            ])
+        });
+
+        let mut state = TableState::default().with_selected(
+            ExportFormat::all()
+                .iter()
+                .position(|format| *format == self.export_format),
+        );
+
+        let table = Table::new(rows, [Constraint::Length(2), Constraint::Fill(1)])
+            .column_spacing(2)
+            .row_highlight_style(Style::new().bold())
+            .block(
+                Block::new()
+                    .title(" Export Capture ")
+                    .bold()
+                    .title_alignment(Alignment::Center)
+                    .borders(Borders::all())
+                    .border_type(BorderType::Thick)
+                    .border_style(Style::default().green())
+                    .padding(Padding::uniform(1)),
+            );
+
+        frame.render_widget(Clear, block);
+        frame.render_stateful_widget(table, block, &mut state);
+    }
 }
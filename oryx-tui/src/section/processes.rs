@@ -0,0 +1,453 @@
+use std::{
+    collections::HashMap,
+    fs,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Flex, Layout, Margin, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Block, Borders, Padding, Row, Table, TableState},
+};
+
+use crate::{
+    packet::{AppPacket, direction::TrafficDirection},
+    packet_store::PacketStore,
+};
+
+use super::flows::{Endpoint, FlowKey, FlowProtocol, PacketFlow, format_bytes};
+
+// Number of remote endpoints shown for the selected process
+const MAX_REMOTES: usize = 8;
+
+// Kept in the table, the least recently seen are dropped past these limits
+const MAX_PROCESSES: usize = 1024;
+const MAX_PROCESS_REMOTES: usize = 256;
+const MAX_OWNERS: usize = 10_000;
+
+// Drops the least recently seen entries, a tenth below the limit to not run it on each packet
+fn evict<K, V>(map: &mut HashMap<K, V>, limit: usize, last_seen: impl Fn(&V) -> DateTime<Utc>) {
+    if map.len() <= limit {
+        return;
+    }
+
+    let mut times: Vec<DateTime<Utc>> = map.values().map(&last_seen).collect();
+    let nb_evicted = times.len() - limit * 9 / 10;
+    let (_, cutoff, _) = times.select_nth_unstable(nb_evicted);
+    let cutoff = *cutoff;
+
+    map.retain(|_, value| last_seen(&*value) >= cutoff);
+}
+
+#[derive(Debug, Clone)]
+pub struct Remote {
+    pub protocol: FlowProtocol,
+    pub endpoint: Endpoint,
+    pub packets: usize,
+    pub bytes: usize,
+    pub last_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Process {
+    pub pid: u32,
+    pub name: Option<String>,
+    pub cmdline: Option<String>,
+    pub packets_sent: usize,
+    pub packets_received: usize,
+    pub bytes_sent: usize,
+    pub bytes_received: usize,
+    pub last_seen: DateTime<Utc>,
+    pub remotes: HashMap<Endpoint, Remote>,
+}
+
+// Read from `/proc`, the process may already be gone
+fn process_names(pid: u32) -> (Option<String>, Option<String>) {
+    let name = fs::read_to_string(format!("/proc/{pid}/comm"))
+        .ok()
+        .map(|name| name.trim().to_string());
+
+    let cmdline = fs::read(format!("/proc/{pid}/cmdline"))
+        .ok()
+        .map(|cmdline| {
+            String::from_utf8_lossy(&cmdline)
+                .split('\0')
+                .filter(|arg| !arg.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|cmdline| !cmdline.is_empty());
+
+    (name, cmdline)
+}
+
+impl Process {
+    fn new(pid: u32, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            pid,
+            name: None,
+            cmdline: None,
+            packets_sent: 0,
+            packets_received: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
+            last_seen: timestamp,
+            remotes: HashMap::new(),
+        }
+    }
+
+    fn update(&mut self, flow: &PacketFlow, app_packet: &AppPacket) {
+        let remote = match app_packet.direction {
+            TrafficDirection::Egress => {
+                self.packets_sent += 1;
+                self.bytes_sent += flow.len;
+                flow.dst
+            }
+            TrafficDirection::Ingress => {
+                self.packets_received += 1;
+                self.bytes_received += flow.len;
+                flow.src
+            }
+        };
+
+        self.last_seen = self.last_seen.max(app_packet.timestamp);
+
+        let remote = self.remotes.entry(remote).or_insert(Remote {
+            protocol: flow.protocol,
+            endpoint: remote,
+            packets: 0,
+            bytes: 0,
+            last_seen: app_packet.timestamp,
+        });
+        remote.packets += 1;
+        remote.bytes += flow.len;
+        remote.last_seen = remote.last_seen.max(app_packet.timestamp);
+
+        evict(&mut self.remotes, MAX_PROCESS_REMOTES, |remote| {
+            remote.last_seen
+        });
+    }
+}
+
+#[derive(Debug, Default)]
+struct ProcessTable {
+    processes: HashMap<u32, Process>,
+    // Only the egress packets carry the pid, the ingress ones are matched on the 5-tuple
+    owners: HashMap<FlowKey, (u32, DateTime<Utc>)>,
+    // Processes seen since the last resolution of the names
+    unresolved: Vec<u32>,
+}
+
+impl ProcessTable {
+    fn add(&mut self, app_packet: &AppPacket) {
+        let Some(flow) = PacketFlow::parse(app_packet) else {
+            return;
+        };
+
+        let pid = match (app_packet.direction, app_packet.pid) {
+            (TrafficDirection::Egress, Some(pid)) => {
+                self.owners.insert(flow.key, (pid, app_packet.timestamp));
+                pid
+            }
+            _ => match self.owners.get_mut(&flow.key) {
+                Some((pid, last_seen)) => {
+                    *last_seen = (*last_seen).max(app_packet.timestamp);
+                    *pid
+                }
+                None => return,
+            },
+        };
+
+        self.processes
+            .entry(pid)
+            .or_insert_with(|| {
+                self.unresolved.push(pid);
+                Process::new(pid, app_packet.timestamp)
+            })
+            .update(&flow, app_packet);
+    }
+
+    fn evict(&mut self) {
+        evict(&mut self.owners, MAX_OWNERS, |(_, last_seen)| *last_seen);
+        evict(&mut self.processes, MAX_PROCESSES, |process| {
+            process.last_seen
+        });
+    }
+}
+
+#[derive(Debug)]
+pub struct Processes {
+    table: Arc<Mutex<ProcessTable>>,
+    state: TableState,
+    // Kept by pid, the rows move as the counters change
+    selected_process: Option<u32>,
+    // Pids in the order of the last render
+    displayed_processes: Vec<u32>,
+    // First row shown
+    offset: usize,
+}
+
+impl Processes {
+    pub fn new(packets: PacketStore, is_offline: bool) -> Self {
+        let table = Arc::new(Mutex::new(ProcessTable::default()));
+
+        thread::spawn({
+            let table = table.clone();
+            move || {
+                let mut last_index: usize = 0;
+                loop {
+                    thread::sleep(Duration::from_millis(500));
+
+                    if packets.is_empty() {
+                        continue;
+                    }
+
+                    let unresolved = {
+                        let mut table = table.lock().unwrap();
+                        last_index += packets
+                            .for_each_range(last_index.., |app_packet| {
+                                table.add(app_packet);
+                                Ok(())
+                            })
+                            .unwrap();
+                        table.evict();
+                        std::mem::take(&mut table.unresolved)
+                    };
+
+                    // The pids of a capture file are not the ones of this host
+                    if is_offline || unresolved.is_empty() {
+                        continue;
+                    }
+
+                    // Without holding the lock, the render waits on it
+                    let names: Vec<_> = unresolved
+                        .into_iter()
+                        .map(|pid| (pid, process_names(pid)))
+                        .collect();
+
+                    let mut table = table.lock().unwrap();
+                    for (pid, (name, cmdline)) in names {
+                        if let Some(process) = table.processes.get_mut(&pid) {
+                            process.name = name;
+                            process.cmdline = cmdline;
+                        }
+                    }
+                }
+            }
+        });
+
+        Self {
+            table,
+            state: TableState::default(),
+            selected_process: None,
+            displayed_processes: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    fn selected_position(&self) -> Option<usize> {
+        let pid = self.selected_process?;
+        self.displayed_processes.iter().position(|p| *p == pid)
+    }
+
+    pub fn handle_keys(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                if self.displayed_processes.is_empty() {
+                    return;
+                }
+                let i = match self.selected_position() {
+                    Some(i) => (i + 1).min(self.displayed_processes.len() - 1),
+                    None => 0,
+                };
+                self.selected_process = Some(self.displayed_processes[i]);
+            }
+
+            KeyCode::Char('k') | KeyCode::Up => {
+                if self.displayed_processes.is_empty() {
+                    return;
+                }
+                let i = match self.selected_position() {
+                    Some(i) => i.saturating_sub(1),
+                    None => 0,
+                };
+                self.selected_process = Some(self.displayed_processes[i]);
+            }
+
+            _ => {}
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, block: Rect) {
+        let process_table = self.table.lock().unwrap();
+
+        if process_table.processes.is_empty() {
+            let block = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Fill(1),
+                    Constraint::Length(3),
+                    Constraint::Fill(1),
+                ])
+                .flex(ratatui::layout::Flex::SpaceBetween)
+                .split(block)[1];
+            let message = Text::from("No outgoing traffic attributed to a process yet.")
+                .bold()
+                .centered();
+            frame.render_widget(message, block);
+            return;
+        }
+
+        // Most active first
+        let mut processes: Vec<&Process> = process_table.processes.values().collect();
+        processes.sort_by(|a, b| {
+            (b.bytes_sent + b.bytes_received)
+                .cmp(&(a.bytes_sent + a.bytes_received))
+                .then(a.pid.cmp(&b.pid))
+        });
+
+        self.displayed_processes = processes.iter().map(|process| process.pid).collect();
+
+        // The first process when the selected one was dropped
+        let selected = self.selected_position().unwrap_or(0);
+        self.selected_process = Some(self.displayed_processes[selected]);
+
+        let (processes_block, remotes_block) = {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Fill(1),
+                    Constraint::Length(MAX_REMOTES as u16 + 4),
+                ])
+                .split(block.inner(Margin {
+                    horizontal: 2,
+                    vertical: 2,
+                }));
+            (chunks[0], chunks[1])
+        };
+
+        // Only the visible rows are built, the header takes 2 lines
+        let nb_rows = (processes_block.height as usize).saturating_sub(2).max(1);
+        if selected < self.offset {
+            self.offset = selected;
+        } else if selected >= self.offset + nb_rows {
+            self.offset = selected + 1 - nb_rows;
+        }
+        self.offset = self.offset.min(processes.len().saturating_sub(nb_rows));
+        *self.state.offset_mut() = 0;
+        self.state.select(Some(selected - self.offset));
+
+        let widths = [
+            Constraint::Length(8),  // Pid
+            Constraint::Length(16), // Name
+            Constraint::Fill(1),    // Command
+            Constraint::Length(10), // Packets ↑
+            Constraint::Length(10), // Packets ↓
+            Constraint::Length(10), // Bytes ↑
+            Constraint::Length(10), // Bytes ↓
+            Constraint::Length(8),  // Remotes
+            Constraint::Length(10), // Last Seen
+        ];
+
+        let rows = processes
+            .iter()
+            .skip(self.offset)
+            .take(nb_rows)
+            .map(|process| {
+                Row::new(vec![
+                    Line::from(process.pid.to_string()).centered().cyan(),
+                    Line::from(process.name.as_deref().unwrap_or("-"))
+                        .centered()
+                        .bold(),
+                    Line::from(process.cmdline.as_deref().unwrap_or("-")),
+                    Line::from(process.packets_sent.to_string()).centered(),
+                    Line::from(process.packets_received.to_string()).centered(),
+                    Line::from(format_bytes(process.bytes_sent)).centered(),
+                    Line::from(format_bytes(process.bytes_received)).centered(),
+                    Line::from(process.remotes.len().to_string()).centered(),
+                    Line::from(process.last_seen.format("%H:%M:%S").to_string()).centered(),
+                ])
+            });
+
+        let table = Table::new(rows, widths)
+            .column_spacing(2)
+            .flex(Flex::SpaceBetween)
+            .row_highlight_style(Style::default().bg(Color::DarkGray))
+            .header(
+                Row::new(vec![
+                    Line::from("Pid").centered().blue(),
+                    Line::from("Name").centered().blue(),
+                    Line::from("Command").blue(),
+                    Line::from("Packets ↑").centered().blue(),
+                    Line::from("Packets ↓").centered().blue(),
+                    Line::from("Bytes ↑").centered().blue(),
+                    Line::from("Bytes ↓").centered().blue(),
+                    Line::from("Remotes").centered().blue(),
+                    Line::from("Last Seen").centered().blue(),
+                ])
+                .style(Style::new().bold())
+                .bottom_margin(1),
+            );
+
+        frame.render_stateful_widget(table, processes_block, &mut self.state);
+
+        // Remote endpoints of the selected process
+        let process = processes[selected];
+
+        let mut remotes: Vec<&Remote> = process.remotes.values().collect();
+        remotes.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+
+        let widths = [
+            Constraint::Length(8),  // Protocol
+            Constraint::Fill(1),    // Remote
+            Constraint::Length(10), // Packets
+            Constraint::Length(10), // Bytes
+            Constraint::Length(10), // Last Seen
+        ];
+
+        let rows = remotes.iter().take(MAX_REMOTES).map(|remote| {
+            Row::new(vec![
+                Line::from(remote.protocol.to_string()).centered().cyan(),
+                Line::from(remote.endpoint.to_string()).centered().blue(),
+                Line::from(remote.packets.to_string()).centered(),
+                Line::from(format_bytes(remote.bytes)).centered(),
+                Line::from(remote.last_seen.format("%H:%M:%S").to_string()).centered(),
+            ])
+        });
+
+        let table = Table::new(rows, widths)
+            .column_spacing(2)
+            .flex(Flex::SpaceBetween)
+            .header(
+                Row::new(vec![
+                    Line::from("Protocol").centered().blue(),
+                    Line::from("Remote").centered().blue(),
+                    Line::from("Packets").centered().blue(),
+                    Line::from("Bytes").centered().blue(),
+                    Line::from("Last Seen").centered().blue(),
+                ])
+                .style(Style::new().bold())
+                .bottom_margin(1),
+            )
+            .block(
+                Block::new()
+                    .borders(Borders::TOP)
+                    .title(format!(
+                        " Remotes of {} ({}) ",
+                        process.name.as_deref().unwrap_or("-"),
+                        process.pid
+                    ))
+                    .title_style(Style::new().bold().green())
+                    .border_style(Style::new().green())
+                    .padding(Padding::top(1)),
+            );
+
+        frame.render_widget(table, remotes_block);
+    }
+}
@@ -6,6 +6,7 @@ use std::{
     time::Duration,
 };
 
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Flex, Layout, Rect},
@@ -39,6 +40,8 @@ pub struct PacketStats {
 pub struct Stats {
     pub packet_stats: Arc<Mutex<PacketStats>>,
     pub bandwidth: Bandwidth,
+    // Index in the top 10 addresses
+    selected: Option<usize>,
 }
 
 impl Stats {
@@ -154,6 +157,43 @@ impl Stats {
         Self {
             packet_stats,
             bandwidth: Bandwidth::new(),
+            selected: None,
+        }
+    }
+
+    pub fn selected_address(&self) -> Option<IpAddr> {
+        let addresses = self.packet_stats.lock().unwrap().addresses.clone();
+        self.get_top_10(addresses)
+            .get(self.selected?)
+            .map(|(ip, _)| *ip)
+    }
+
+    pub fn handle_keys(&mut self, key_event: KeyEvent) {
+        let nb_addresses = self.packet_stats.lock().unwrap().addresses.len().min(10);
+        if nb_addresses == 0 {
+            return;
+        }
+
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.selected = Some(match self.selected {
+                    Some(i) => (i + 1).min(nb_addresses - 1),
+                    None => 0,
+                });
+            }
+
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.selected = Some(match self.selected {
+                    Some(i) => i.saturating_sub(1),
+                    None => 0,
+                });
+            }
+
+            KeyCode::Esc => {
+                self.selected = None;
+            }
+
+            _ => {}
         }
     }
     pub fn get_top_10(
@@ -353,11 +393,17 @@ impl Stats {
                     &self
                         .get_top_10(packet_stats.addresses.clone())
                         .into_iter()
-                        .map(|(ip, (host, count))| {
+                        .enumerate()
+                        .map(|(index, (ip, (host, count)))| {
+                            let color = if self.selected == Some(index) {
+                                Color::LightRed
+                            } else {
+                                Color::LightYellow
+                            };
                             Bar::default()
                                 .label(Line::from(count.to_string()))
-                                .style(Style::new().fg(Color::LightYellow))
-                                .value_style(Style::new().fg(Color::Black).bg(Color::LightYellow))
+                                .style(Style::new().fg(color))
+                                .value_style(Style::new().fg(Color::Black).bg(color))
                                 .text_value(host.clone().unwrap_or(ip.to_string()))
                                 .value(count as u64)
                         })
@@ -12,6 +12,8 @@ pub fn render(app: &mut App, frame: &mut Frame) {
             ActivePopup::UpdateFilters => app.filter.render_update_popup(frame),
             ActivePopup::NewFirewallRule => app.section.firewall.render_new_rule_popup(frame),
             ActivePopup::NewMetricExplorer => app.section.metrics.render_new_rule_popup(frame),
+            ActivePopup::ExportCapture => app.section.inspection.render_export_popup(frame),
+            ActivePopup::FirewallPolicy => app.section.firewall.render_policy_popup(frame),
         }
     }
     for (index, notification) in app.notifications.iter().enumerate() {